@@ -46,6 +46,16 @@ impl<S> Aabb<S> {
     pub fn new(min: Vector3<S>, max: Vector3<S>) -> Self {
         Aabb { min, max }
     }
+
+    /// Get the min corner of the AABB.
+    pub fn min(&self) -> &Vector3<S> {
+        &self.min
+    }
+
+    /// Get the max corner of the AABB.
+    pub fn max(&self) -> &Vector3<S> {
+        &self.max
+    }
 }
 
 impl<S: BaseFloat> Aabb<S> {
@@ -91,6 +101,47 @@ impl<S: BaseFloat> Aabb<S> {
         let two = S::one() + S::one();
         self.min + (self.max - self.min) / two
     }
+
+    /// Compute the AABB enclosing a set of points if there is at least one.
+    pub fn from_points<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Vector3<S>>,
+    {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(Aabb::new(first, first), |aabb, point| {
+            Aabb::new(
+                Vector3::new(min(aabb.min.x, point.x), min(aabb.min.y, point.y), min(aabb.min.z, point.z)),
+                Vector3::new(max(aabb.max.x, point.x), max(aabb.max.y, point.y), max(aabb.max.z, point.z)),
+            )
+        }))
+    }
+
+    /// Get the 8 corners of the AABB.
+    pub fn get_corners(&self) -> [Vector3<S>; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Vector3::new(min.x, min.y, min.z),
+            Vector3::new(max.x, min.y, min.z),
+            Vector3::new(min.x, max.y, min.z),
+            Vector3::new(max.x, max.y, min.z),
+            Vector3::new(min.x, min.y, max.z),
+            Vector3::new(max.x, min.y, max.z),
+            Vector3::new(min.x, max.y, max.z),
+            Vector3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    /// Transform all 8 corners by a Matrix4 and return the AABB enclosing them.
+    /// Unlike `Aabb * Matrix4` this stays correct under rotation.
+    pub fn transform(&self, matrix: &Matrix4<S>) -> Self {
+        let corners = self
+            .get_corners()
+            .map(|corner| (matrix * Vector4::new(corner.x, corner.y, corner.z, S::one())).truncate());
+
+        Aabb::from_points(corners).unwrap()
+    }
 }
 
 /// Transform the AABB by multiplying it with a Matrix4.
@@ -115,4 +166,47 @@ impl<S: BaseFloat> Mul<S> for Aabb<S> {
     fn mul(self, rhs: S) -> Self::Output {
         Aabb::new(self.min * rhs, self.max * rhs)
     }
+}
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, Matrix4, Vector3};
+
+    use super::*;
+
+    fn assert_vector_eq(actual: Vector3<f32>, expected: Vector3<f32>) {
+        assert!((actual.x - expected.x).abs() < 1e-5 && (actual.y - expected.y).abs() < 1e-5 && (actual.z - expected.z).abs() < 1e-5, "expected {:?}, got {:?}", expected, actual);
+    }
+
+    #[test]
+    fn encloses_points() {
+        let aabb = Aabb::from_points([Vector3::new(1.0, -2.0, 3.0), Vector3::new(-1.0, 4.0, 0.0), Vector3::new(0.5, 0.0, 5.0)]).unwrap();
+
+        assert_vector_eq(*aabb.min(), Vector3::new(-1.0, -2.0, 0.0));
+        assert_vector_eq(*aabb.max(), Vector3::new(1.0, 4.0, 5.0));
+        assert_vector_eq(aabb.get_center(), Vector3::new(0.0, 1.0, 2.5));
+        assert!(Aabb::<f32>::from_points([]).is_none());
+    }
+
+    #[test]
+    fn unions_boxes() {
+        let union = Aabb::union(&[
+            Aabb::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0)),
+            Aabb::new(Vector3::new(-2.0, 0.5, 0.5), Vector3::new(0.5, 3.0, 0.75))
+        ]).unwrap();
+
+        assert_vector_eq(*union.min(), Vector3::new(-2.0, 0.0, 0.0));
+        assert_vector_eq(*union.max(), Vector3::new(1.0, 3.0, 1.0));
+        assert!(Aabb::<f32>::union(&[]).is_none());
+    }
+
+    #[test]
+    fn transform_stays_enclosing_under_rotation() {
+        let aabb = Aabb::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 1.0, 1.0));
+        let rotated = aabb.transform(&(Matrix4::from_translation(Vector3::new(0.0, 5.0, 0.0)) * Matrix4::from_angle_y(Deg(90.0))));
+
+        // x runs along -z after a quarter turn around y
+        assert_vector_eq(*rotated.min(), Vector3::new(0.0, 5.0, -2.0));
+        assert_vector_eq(*rotated.max(), Vector3::new(1.0, 6.0, 0.0));
+        assert!((rotated.get_larger_side_size() - 2.0).abs() < 1e-5);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,135 @@
+use cgmath::{BaseFloat, InnerSpace, Matrix, Matrix4, Vector3, Vector4};
+
+use super::aabb::Aabb;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Plane<S> {
+    normal: Vector3<S>,
+    distance: S,
+}
+
+impl<S: BaseFloat> Plane<S> {
+    /// Create a normalized plane from its `ax + by + cz + d = 0` coefficients.
+    pub fn from_vector4(v: Vector4<S>) -> Self {
+        let normal = v.truncate();
+        let length = normal.magnitude();
+
+        Plane {
+            normal: normal / length,
+            distance: v.w / length,
+        }
+    }
+
+    /// Signed distance from the plane to a point, positive on the side the normal points to.
+    pub fn distance_to_point(&self, point: Vector3<S>) -> S {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Frustum<S> {
+    planes: [Plane<S>; 6],
+}
+
+impl<S: BaseFloat> Frustum<S> {
+    /// Extract the frustum planes (left, right, bottom, top, near, far) from a view projection matrix.
+    /// Expects a `[0, 1]` depth range like the one used by wgpu.
+    pub fn from_matrix(view_projection: Matrix4<S>) -> Self {
+        let r0 = view_projection.row(0);
+        let r1 = view_projection.row(1);
+        let r2 = view_projection.row(2);
+        let r3 = view_projection.row(3);
+
+        Frustum {
+            planes: [
+                Plane::from_vector4(r3 + r0),
+                Plane::from_vector4(r3 - r0),
+                Plane::from_vector4(r3 + r1),
+                Plane::from_vector4(r3 - r1),
+                Plane::from_vector4(r2),
+                Plane::from_vector4(r3 - r2),
+            ],
+        }
+    }
+
+    /// Get the frustum planes, all normals point inside.
+    pub fn planes(&self) -> &[Plane<S>; 6] {
+        &self.planes
+    }
+
+    /// Check if an AABB is at least partially inside the frustum.
+    pub fn intersects_aabb(&self, aabb: &Aabb<S>) -> bool {
+        let (min, max) = (aabb.min(), aabb.max());
+
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the plane normal
+            let positive = Vector3::new(
+                if plane.normal.x >= S::zero() { max.x } else { min.x },
+                if plane.normal.y >= S::zero() { max.y } else { min.y },
+                if plane.normal.z >= S::zero() { max.z } else { min.z },
+            );
+
+            plane.distance_to_point(positive) >= S::zero()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, Matrix4, Point3, Vector3, Vector4};
+
+    use super::*;
+
+    /// Camera at the origin looking down -z, with the projection remapped to wgpu's `[0, 1]` depth.
+    fn camera_frustum() -> Frustum<f32> {
+        let opengl_to_wgpu = Matrix4::from_cols(
+            Vector4::new(1.0, 0.0, 0.0, 0.0),
+            Vector4::new(0.0, 1.0, 0.0, 0.0),
+            Vector4::new(0.0, 0.0, 0.5, 0.0),
+            Vector4::new(0.0, 0.0, 0.5, 1.0),
+        );
+        let projection = opengl_to_wgpu * cgmath::perspective(Deg(90.0), 1.0, 0.1, 100.0);
+        let view = Matrix4::look_at_rh(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, -1.0), Vector3::unit_y());
+
+        Frustum::from_matrix(projection * view)
+    }
+
+    fn aabb(min: [f32; 3], max: [f32; 3]) -> Aabb<f32> {
+        Aabb::new(Vector3::from(min), Vector3::from(max))
+    }
+
+    #[test]
+    fn plane_normals_point_inside() {
+        let frustum = camera_frustum();
+
+        for plane in frustum.planes() {
+            assert!(plane.distance_to_point(Vector3::new(0.0, 0.0, -5.0)) > 0.0, "{:?}", plane);
+        }
+    }
+
+    #[test]
+    fn keeps_box_fully_inside() {
+        assert!(camera_frustum().intersects_aabb(&aabb([-1.0, -1.0, -6.0], [1.0, 1.0, -4.0])));
+    }
+
+    #[test]
+    fn culls_boxes_fully_outside() {
+        let frustum = camera_frustum();
+
+        // behind the camera, left of it, below it and past the far plane
+        assert!(!frustum.intersects_aabb(&aabb([-1.0, -1.0, 4.0], [1.0, 1.0, 6.0])));
+        assert!(!frustum.intersects_aabb(&aabb([-20.0, -1.0, -6.0], [-18.0, 1.0, -4.0])));
+        assert!(!frustum.intersects_aabb(&aabb([-1.0, -20.0, -6.0], [1.0, -18.0, -4.0])));
+        assert!(!frustum.intersects_aabb(&aabb([-1.0, -1.0, -200.0], [1.0, 1.0, -150.0])));
+    }
+
+    #[test]
+    fn keeps_boxes_straddling_a_plane() {
+        let frustum = camera_frustum();
+
+        // across the near plane, the left plane and the far plane
+        assert!(frustum.intersects_aabb(&aabb([-1.0, -1.0, -1.0], [1.0, 1.0, 1.0])));
+        assert!(frustum.intersects_aabb(&aabb([-6.0, -1.0, -6.0], [-4.0, 1.0, -4.0])));
+        assert!(frustum.intersects_aabb(&aabb([-1.0, -1.0, -110.0], [1.0, 1.0, -90.0])));
+    }
+}
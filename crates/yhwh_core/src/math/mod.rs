@@ -1 +1,2 @@
-pub mod aabb;
\ No newline at end of file
+pub mod aabb;
+pub mod frustum;
\ No newline at end of file
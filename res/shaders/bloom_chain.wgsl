@@ -0,0 +1,139 @@
+// Downsample / upsample bloom over a chain of half sized targets,
+// "Next Generation Post Processing in Call of Duty: Advanced Warfare" (Jimenez 2014).
+// The instance index picks the filter: 0 prefilter, 1 downsample, 2 upsample.
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) mode: u32,
+}
+
+struct BloomUniform {
+    threshold: f32,
+    knee: f32,
+    radius: f32,
+    intensity: f32,
+}
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> bloom: BloomUniform;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32, @builtin(instance_index) ii: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = vec2<f32>(
+        f32((vi << 1u) & 2u),
+        f32(vi & 2u),
+    );
+    out.clip_position = vec4<f32>(out.uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv.y = 1.0 - out.uv.y;
+    out.mode = ii;
+
+    return out;
+}
+
+fn sample_source(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(source_texture, source_sampler, uv, 0.0).rgb;
+}
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// weights down the fireflies of single very bright pixels before they spread over the chain
+fn karis_average(color: vec3<f32>) -> f32 {
+    return 1.0 / (1.0 + luminance(color));
+}
+
+// quadratic soft knee around the threshold
+fn apply_threshold(color: vec3<f32>) -> vec3<f32> {
+    let brightness = max(color.r, max(color.g, color.b));
+    let knee = max(bloom.threshold * bloom.knee, 1e-5);
+    var soft = clamp(brightness - bloom.threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee);
+    let contribution = max(soft, brightness - bloom.threshold) / max(brightness, 1e-5);
+
+    return color * contribution;
+}
+
+// 13 tap filter, five overlapping 2x2 boxes
+fn downsample(uv: vec2<f32>, prefilter: bool) -> vec3<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+
+    let a = sample_source(uv + texel * vec2<f32>(-2.0, -2.0));
+    let b = sample_source(uv + texel * vec2<f32>( 0.0, -2.0));
+    let c = sample_source(uv + texel * vec2<f32>( 2.0, -2.0));
+    let d = sample_source(uv + texel * vec2<f32>(-2.0,  0.0));
+    let e = sample_source(uv);
+    let f = sample_source(uv + texel * vec2<f32>( 2.0,  0.0));
+    let g = sample_source(uv + texel * vec2<f32>(-2.0,  2.0));
+    let h = sample_source(uv + texel * vec2<f32>( 0.0,  2.0));
+    let i = sample_source(uv + texel * vec2<f32>( 2.0,  2.0));
+    let j = sample_source(uv + texel * vec2<f32>(-1.0, -1.0));
+    let k = sample_source(uv + texel * vec2<f32>( 1.0, -1.0));
+    let l = sample_source(uv + texel * vec2<f32>(-1.0,  1.0));
+    let m = sample_source(uv + texel * vec2<f32>( 1.0,  1.0));
+
+    if !prefilter {
+        var color = e * 0.125;
+        color += (a + c + g + i) * 0.03125;
+        color += (b + d + f + h) * 0.0625;
+        color += (j + k + l + m) * 0.125;
+        return color;
+    }
+
+    let box_0 = (a + b + d + e) * 0.25;
+    let box_1 = (b + c + e + f) * 0.25;
+    let box_2 = (d + e + g + h) * 0.25;
+    let box_3 = (e + f + h + i) * 0.25;
+    let box_4 = (j + k + l + m) * 0.25;
+
+    let w0 = karis_average(box_0) * 0.125;
+    let w1 = karis_average(box_1) * 0.125;
+    let w2 = karis_average(box_2) * 0.125;
+    let w3 = karis_average(box_3) * 0.125;
+    let w4 = karis_average(box_4) * 0.5;
+
+    let color = (box_0 * w0 + box_1 * w1 + box_2 * w2 + box_3 * w3 + box_4 * w4) / (w0 + w1 + w2 + w3 + w4);
+
+    return apply_threshold(color);
+}
+
+// 3x3 tent, blended additively over the next larger mip
+fn upsample(uv: vec2<f32>) -> vec3<f32> {
+    let offset = bloom.radius / vec2<f32>(textureDimensions(source_texture));
+
+    var color = sample_source(uv) * 4.0;
+    color += (sample_source(uv + vec2<f32>(-offset.x, 0.0)) + sample_source(uv + vec2<f32>(offset.x, 0.0))) * 2.0;
+    color += (sample_source(uv + vec2<f32>(0.0, -offset.y)) + sample_source(uv + vec2<f32>(0.0, offset.y))) * 2.0;
+    color += sample_source(uv + vec2<f32>(-offset.x, -offset.y));
+    color += sample_source(uv + vec2<f32>( offset.x, -offset.y));
+    color += sample_source(uv + vec2<f32>(-offset.x,  offset.y));
+    color += sample_source(uv + vec2<f32>( offset.x,  offset.y));
+
+    return color / 16.0;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color: vec3<f32>;
+
+    switch in.mode {
+        case 0u: {
+            color = downsample(in.uv, true);
+        }
+        case 1u: {
+            color = downsample(in.uv, false);
+        }
+        default: {
+            color = upsample(in.uv);
+        }
+    }
+
+    return vec4<f32>(max(color, vec3<f32>(0.0)), 1.0);
+}
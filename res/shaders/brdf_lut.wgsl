@@ -0,0 +1,89 @@
+struct VertexOutput {
+    @location(0) uv: vec2<f32>,
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = vec2<f32>(
+        f32((vi << 1u) & 2u),
+        f32(vi & 2u),
+    );
+    out.clip_position = vec4<f32>(out.uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv.y = 1.0 - out.uv.y;
+
+    return out;
+}
+
+const PI = 3.14159265359;
+const SAMPLE_COUNT = 1024u;
+
+fn hammersley(i: u32, n: u32) -> vec2<f32> {
+    return vec2<f32>(f32(i) / f32(n), f32(reverseBits(i)) * 2.3283064365386963e-10);
+}
+
+fn importance_sample_ggx(xi: vec2<f32>, n: vec3<f32>, roughness: f32) -> vec3<f32> {
+    let a = roughness * roughness;
+
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+
+    let h = vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+
+    var up = vec3<f32>(1.0, 0.0, 0.0);
+    if (abs(n.z) < 0.999) {
+        up = vec3<f32>(0.0, 0.0, 1.0);
+    }
+    let tangent = normalize(cross(up, n));
+    let bitangent = cross(n, tangent);
+
+    return normalize(tangent * h.x + bitangent * h.y + n * h.z);
+}
+
+// uses k = a^2 / 2 instead of the direct lighting remap
+fn geometry_schlick_ggx(ndotv: f32, roughness: f32) -> f32 {
+    let a = roughness;
+    let k = (a * a) / 2.0;
+
+    return ndotv / (ndotv * (1.0 - k) + k);
+}
+
+fn geometry_smith(ndotv: f32, ndotl: f32, roughness: f32) -> f32 {
+    return geometry_schlick_ggx(ndotv, roughness) * geometry_schlick_ggx(ndotl, roughness);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // sampled in the lighting shader with uv = (n.v, roughness)
+    let ndotv = max(in.uv.x, 0.0001);
+    let roughness = in.uv.y;
+
+    let v = vec3<f32>(sqrt(1.0 - ndotv * ndotv), 0.0, ndotv);
+    let n = vec3<f32>(0.0, 0.0, 1.0);
+
+    var a = 0.0;
+    var b = 0.0;
+
+    for (var i = 0u; i < SAMPLE_COUNT; i++) {
+        let xi = hammersley(i, SAMPLE_COUNT);
+        let h = importance_sample_ggx(xi, n, roughness);
+        let l = normalize(2.0 * dot(v, h) * h - v);
+
+        let ndotl = max(l.z, 0.0);
+        let ndoth = max(h.z, 0.0);
+        let vdoth = max(dot(v, h), 0.0);
+
+        if (ndotl > 0.0) {
+            let g = geometry_smith(ndotv, ndotl, roughness);
+            let g_vis = (g * vdoth) / (ndoth * ndotv);
+            let fc = pow(1.0 - vdoth, 5.0);
+
+            a += (1.0 - fc) * g_vis;
+            b += fc * g_vis;
+        }
+    }
+
+    return vec4<f32>(a / f32(SAMPLE_COUNT), b / f32(SAMPLE_COUNT), 0.0, 1.0);
+}
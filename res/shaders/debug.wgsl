@@ -1,13 +1,15 @@
+// Debug draw lines, see debug_draw.rs. Colors are written to the HDR target as they are.
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
 };
 
-
-// uniforms
 struct CameraUniform {
     view: mat4x4<f32>,
     projection: mat4x4<f32>,
@@ -17,25 +19,15 @@ struct CameraUniform {
 @group(0) @binding(0)
 var<uniform> camera: CameraUniform;
 
-struct LightUniform {
-    position: vec3<f32>,
-    color: vec3<f32>,
-}
-
-@group(1) @binding(0)
-var<uniform> light: LightUniform;
-
 @vertex
 fn vs_main(model: VertexInput) -> VertexOutput {
     var out: VertexOutput;
-    let scale = 0.25;
-
-    out.clip_position = camera.projection * camera.view * vec4<f32>(model.position * scale + light.position, 1.0);
+    out.clip_position = camera.projection * camera.view * vec4<f32>(model.position, 1.0);
+    out.color = model.color;
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+    return vec4<f32>(in.color, 1.0);
 }
-
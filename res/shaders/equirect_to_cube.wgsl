@@ -0,0 +1,50 @@
+struct VertexOutput {
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) face: u32,
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+// the face being rendered is passed in as the instance index
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32, @builtin(instance_index) face: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = vec2<f32>(
+        f32((vi << 1u) & 2u),
+        f32(vi & 2u),
+    );
+    out.clip_position = vec4<f32>(out.uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv.y = 1.0 - out.uv.y;
+    out.face = face;
+
+    return out;
+}
+
+// rgba32float isn't filterable everywhere, so the texels are loaded directly
+@group(0) @binding(0)
+var t_equirect: texture_2d<f32>;
+
+const PI = 3.14159265359;
+
+fn cube_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let st = uv * 2.0 - 1.0;
+
+    switch face {
+        case 0u: { return normalize(vec3<f32>(1.0, -st.y, -st.x)); }
+        case 1u: { return normalize(vec3<f32>(-1.0, -st.y, st.x)); }
+        case 2u: { return normalize(vec3<f32>(st.x, 1.0, st.y)); }
+        case 3u: { return normalize(vec3<f32>(st.x, -1.0, -st.y)); }
+        case 4u: { return normalize(vec3<f32>(st.x, -st.y, 1.0)); }
+        default: { return normalize(vec3<f32>(-st.x, -st.y, -1.0)); }
+    }
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let dir = cube_direction(in.face, in.uv);
+    let equirect_uv = vec2<f32>(atan2(dir.z, dir.x) / (2.0 * PI) + 0.5, acos(clamp(dir.y, -1.0, 1.0)) / PI);
+
+    let dimensions = vec2<f32>(textureDimensions(t_equirect));
+    let texel = vec2<i32>(clamp(equirect_uv * dimensions, vec2<f32>(0.0), dimensions - 1.0));
+
+    return vec4<f32>(textureLoad(t_equirect, texel, 0).rgb, 1.0);
+}
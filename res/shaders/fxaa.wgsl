@@ -0,0 +1,165 @@
+struct VertexOutput {
+    @location(0) uv: vec2<f32>,
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = vec2<f32>(
+        f32((vi << 1u) & 2u),
+        f32(vi & 2u),
+    );
+    out.clip_position = vec4<f32>(out.uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv.y = 1.0 - out.uv.y;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var t_ldr: texture_2d<f32>;
+@group(0) @binding(1)
+var s_ldr: sampler;
+
+const EDGE_THRESHOLD_MIN: f32 = 0.0312;
+const EDGE_THRESHOLD_MAX: f32 = 0.125;
+const SUBPIXEL_QUALITY: f32 = 0.75;
+const ITERATIONS: i32 = 12;
+
+// the edge search takes bigger steps the further it gets
+fn step_quality(i: i32) -> f32 {
+    if (i < 5) {
+        return 1.0;
+    }
+    if (i == 5) {
+        return 1.5;
+    }
+    if (i < 10) {
+        return 2.0;
+    }
+    if (i == 10) {
+        return 4.0;
+    }
+    return 8.0;
+}
+
+// perceptual luma, the target stores linear colors
+fn luma(color: vec3<f32>) -> f32 {
+    return sqrt(dot(color, vec3<f32>(0.299, 0.587, 0.114)));
+}
+
+fn sample_luma(uv: vec2<f32>) -> f32 {
+    return luma(textureSampleLevel(t_ldr, s_ldr, uv, 0.0).rgb);
+}
+
+// uv space has +y pointing down
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_ldr));
+    let uv = in.uv;
+
+    let color_center = textureSampleLevel(t_ldr, s_ldr, uv, 0.0).rgb;
+    let luma_center = luma(color_center);
+    let luma_down = sample_luma(uv + vec2<f32>(0.0, texel.y));
+    let luma_up = sample_luma(uv - vec2<f32>(0.0, texel.y));
+    let luma_left = sample_luma(uv - vec2<f32>(texel.x, 0.0));
+    let luma_right = sample_luma(uv + vec2<f32>(texel.x, 0.0));
+
+    let luma_min = min(luma_center, min(min(luma_down, luma_up), min(luma_left, luma_right)));
+    let luma_max = max(luma_center, max(max(luma_down, luma_up), max(luma_left, luma_right)));
+    let luma_range = luma_max - luma_min;
+
+    // flat areas are left alone
+    if (luma_range < max(EDGE_THRESHOLD_MIN, luma_max * EDGE_THRESHOLD_MAX)) {
+        return vec4<f32>(color_center, 1.0);
+    }
+
+    let luma_down_left = sample_luma(uv + vec2<f32>(-texel.x, texel.y));
+    let luma_up_right = sample_luma(uv + vec2<f32>(texel.x, -texel.y));
+    let luma_up_left = sample_luma(uv - texel);
+    let luma_down_right = sample_luma(uv + texel);
+
+    let luma_down_up = luma_down + luma_up;
+    let luma_left_right = luma_left + luma_right;
+    let luma_left_corners = luma_down_left + luma_up_left;
+    let luma_down_corners = luma_down_left + luma_down_right;
+    let luma_right_corners = luma_down_right + luma_up_right;
+    let luma_up_corners = luma_up_right + luma_up_left;
+
+    let edge_horizontal = abs(-2.0 * luma_left + luma_left_corners) + abs(-2.0 * luma_center + luma_down_up) * 2.0 + abs(-2.0 * luma_right + luma_right_corners);
+    let edge_vertical = abs(-2.0 * luma_up + luma_up_corners) + abs(-2.0 * luma_center + luma_left_right) * 2.0 + abs(-2.0 * luma_down + luma_down_corners);
+    let is_horizontal = edge_horizontal >= edge_vertical;
+
+    // pick the side of the edge with the steepest gradient
+    let luma1 = select(luma_left, luma_down, is_horizontal);
+    let luma2 = select(luma_right, luma_up, is_horizontal);
+    let gradient1 = luma1 - luma_center;
+    let gradient2 = luma2 - luma_center;
+    let is1_steepest = abs(gradient1) >= abs(gradient2);
+    let gradient_scaled = 0.25 * max(abs(gradient1), abs(gradient2));
+
+    var step_offset = select(vec2<f32>(-texel.x, 0.0), vec2<f32>(0.0, texel.y), is_horizontal);
+    var luma_local_average = 0.5 * (luma1 + luma_center);
+    if (!is1_steepest) {
+        step_offset = -step_offset;
+        luma_local_average = 0.5 * (luma2 + luma_center);
+    }
+
+    // walk along the edge in both directions until its end
+    let edge_step = select(vec2<f32>(0.0, texel.y), vec2<f32>(texel.x, 0.0), is_horizontal);
+    let current_uv = uv + step_offset * 0.5;
+    var uv1 = current_uv - edge_step;
+    var uv2 = current_uv + edge_step;
+
+    var luma_end1 = sample_luma(uv1) - luma_local_average;
+    var luma_end2 = sample_luma(uv2) - luma_local_average;
+    var reached1 = abs(luma_end1) >= gradient_scaled;
+    var reached2 = abs(luma_end2) >= gradient_scaled;
+
+    if (!reached1) {
+        uv1 -= edge_step;
+    }
+    if (!reached2) {
+        uv2 += edge_step;
+    }
+
+    for (var i = 2; i < ITERATIONS && !(reached1 && reached2); i++) {
+        if (!reached1) {
+            luma_end1 = sample_luma(uv1) - luma_local_average;
+        }
+        if (!reached2) {
+            luma_end2 = sample_luma(uv2) - luma_local_average;
+        }
+
+        reached1 = abs(luma_end1) >= gradient_scaled;
+        reached2 = abs(luma_end2) >= gradient_scaled;
+
+        if (!reached1) {
+            uv1 -= edge_step * step_quality(i);
+        }
+        if (!reached2) {
+            uv2 += edge_step * step_quality(i);
+        }
+    }
+
+    let distance1 = select(uv.y - uv1.y, uv.x - uv1.x, is_horizontal);
+    let distance2 = select(uv2.y - uv.y, uv2.x - uv.x, is_horizontal);
+    let is_direction1 = distance1 < distance2;
+    let distance_final = min(distance1, distance2);
+    let edge_thickness = distance1 + distance2;
+
+    // only blend when the luma at the closest end varies the right way
+    let is_luma_center_smaller = luma_center < luma_local_average;
+    let correct_variation = (select(luma_end2, luma_end1, is_direction1) < 0.0) != is_luma_center_smaller;
+    var final_offset = select(0.0, 0.5 - distance_final / edge_thickness, correct_variation);
+
+    // sub pixel aliasing
+    let luma_average = (1.0 / 12.0) * (2.0 * (luma_down_up + luma_left_right) + luma_left_corners + luma_right_corners);
+    let sub_pixel_offset1 = clamp(abs(luma_average - luma_center) / luma_range, 0.0, 1.0);
+    let sub_pixel_offset2 = (-2.0 * sub_pixel_offset1 + 3.0) * sub_pixel_offset1 * sub_pixel_offset1;
+    final_offset = max(final_offset, sub_pixel_offset2 * sub_pixel_offset2 * SUBPIXEL_QUALITY);
+
+    let color = textureSampleLevel(t_ldr, s_ldr, uv + step_offset * final_offset, 0.0).rgb;
+
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,68 @@
+struct VertexOutput {
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) face: u32,
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+// the face being rendered is passed in as the instance index
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32, @builtin(instance_index) face: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = vec2<f32>(
+        f32((vi << 1u) & 2u),
+        f32(vi & 2u),
+    );
+    out.clip_position = vec4<f32>(out.uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv.y = 1.0 - out.uv.y;
+    out.face = face;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var t_environment: texture_cube<f32>;
+@group(0) @binding(1)
+var s_environment: sampler;
+
+const PI = 3.14159265359;
+const SAMPLE_DELTA = 0.025;
+
+fn cube_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let st = uv * 2.0 - 1.0;
+
+    switch face {
+        case 0u: { return normalize(vec3<f32>(1.0, -st.y, -st.x)); }
+        case 1u: { return normalize(vec3<f32>(-1.0, -st.y, st.x)); }
+        case 2u: { return normalize(vec3<f32>(st.x, 1.0, st.y)); }
+        case 3u: { return normalize(vec3<f32>(st.x, -1.0, -st.y)); }
+        case 4u: { return normalize(vec3<f32>(st.x, -st.y, 1.0)); }
+        default: { return normalize(vec3<f32>(-st.x, -st.y, -1.0)); }
+    }
+}
+
+// cosine weighted convolution of the hemisphere around the normal
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = cube_direction(in.face, in.uv);
+    var up = vec3<f32>(0.0, 1.0, 0.0);
+    if (abs(normal.y) > 0.999) {
+        up = vec3<f32>(0.0, 0.0, 1.0);
+    }
+    let right = normalize(cross(up, normal));
+    up = normalize(cross(normal, right));
+
+    var irradiance = vec3<f32>(0.0);
+    var sample_count = 0.0;
+
+    for (var phi = 0.0; phi < 2.0 * PI; phi += SAMPLE_DELTA) {
+        for (var theta = 0.0; theta < 0.5 * PI; theta += SAMPLE_DELTA) {
+            let tangent_sample = vec3<f32>(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta));
+            let sample_dir = tangent_sample.x * right + tangent_sample.y * up + tangent_sample.z * normal;
+
+            irradiance += textureSampleLevel(t_environment, s_environment, sample_dir, 0.0).rgb * cos(theta) * sin(theta);
+            sample_count += 1.0;
+        }
+    }
+
+    return vec4<f32>(PI * irradiance / sample_count, 1.0);
+}
@@ -8,6 +8,18 @@ struct VertexInput {
     @location(6) weights: vec4<f32>,
 }
 
+struct InstanceInput {
+    @location(7) model_matrix_0: vec4<f32>,
+    @location(8) model_matrix_1: vec4<f32>,
+    @location(9) model_matrix_2: vec4<f32>,
+    @location(10) model_matrix_3: vec4<f32>,
+    @location(11) normal_matrix_0: vec4<f32>,
+    @location(12) normal_matrix_1: vec4<f32>,
+    @location(13) normal_matrix_2: vec4<f32>,
+    // xy tex scale, z alpha cutoff, w 1 when alpha blended
+    @location(14) tex_scale: vec4<f32>,
+}
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
@@ -15,6 +27,7 @@ struct VertexOutput {
     @location(2) tangent: vec3<f32>,
     @location(3) bitangent: vec3<f32>,
     @location(4) normal: vec3<f32>, 
+    @location(5) @interpolate(flat) alpha: vec2<f32>,
 }
 
 struct CameraUniform {
@@ -25,21 +38,28 @@ struct CameraUniform {
 
 struct LightUniform {
     position: vec3<f32>,
-    _pad0: u32,
+    light_type: u32,
     color: vec3<f32>, 
-    _pad1: u32,
+    _pad0: u32,
+    direction: vec3<f32>,
+    shadow_index: i32,
     strength: f32,
     radius: f32,
-    _pad2: u32,
-    _pad3: u32
+    inner_cone_cos: f32,
+    outer_cone_cos: f32
+}
 
+struct ShadowCaster {
+    light_type: u32,
+    layer: u32,
+    matrix_index: u32,
+    _pad0: u32
 }
 
-struct ModelUniform {
-    model_matrix: mat4x4<f32>,
-    normal_matrix: mat3x4<f32>,
-    tex_scale: vec2<f32>,
-    _padding_0: vec2<f32>
+struct ShadowsUniform {
+    matrices: array<mat4x4<f32>, 32>,
+    casters: array<ShadowCaster, 9>,
+    cascade_splits: vec4<f32>,
 }
 
 @group(0) @binding(0)
@@ -61,21 +81,143 @@ var s_rma_map: sampler;
 var<uniform> camera: CameraUniform;
 
 @group(2) @binding(0)
-var<uniform> model: ModelUniform;
-
-@group(3) @binding(0)
 var<storage, read> lights: array<LightUniform>;
 //var<uniform> light: LightUniform;
 
-@group(3) @binding(1)
+@group(2) @binding(1)
 var<uniform> light_count: u32;
 
+@group(3) @binding(0)
+var t_point_shadows: texture_depth_2d_array;
+@group(3) @binding(1)
+var s_point_shadows: sampler_comparison;
+
+@group(3) @binding(2)
+var t_spot_shadows: texture_depth_2d_array;
+@group(3) @binding(3)
+var s_spot_shadows: sampler_comparison;
+
+@group(3) @binding(4)
+var t_cascade_shadows: texture_depth_2d_array;
+@group(3) @binding(5)
+var s_cascade_shadows: sampler_comparison;
+
+@group(3) @binding(6)
+var<uniform> shadows: ShadowsUniform;
+
+@group(4) @binding(0)
+var t_irradiance: texture_cube<f32>;
+@group(4) @binding(1)
+var s_irradiance: sampler;
+
+@group(4) @binding(2)
+var t_prefiltered: texture_cube<f32>;
+@group(4) @binding(3)
+var s_prefiltered: sampler;
+
+@group(4) @binding(4)
+var t_brdf_lut: texture_2d<f32>;
+@group(4) @binding(5)
+var s_brdf_lut: sampler;
+
+@group(5) @binding(0)
+var t_ssao: texture_2d<f32>;
+@group(5) @binding(1)
+var s_ssao: sampler;
+
 const PI = 3.14159265359;
 
+const LIGHT_TYPE_POINT: u32 = 0u;
+const LIGHT_TYPE_DIRECTIONAL: u32 = 1u;
+const LIGHT_TYPE_SPOT: u32 = 2u;
+
+const SHADOW_CASCADE_COUNT: u32 = 4u;
+const SHADOW_NORMAL_OFFSET: f32 = 0.05;
+
+// must match PREFILTER_MIP_LEVELS - 1 in constants.rs
+const MAX_REFLECTION_LOD: f32 = 4.0;
+
+fn project_shadow_coords(matrix_index: u32, world_pos: vec3<f32>) -> vec3<f32> {
+    let clip = shadows.matrices[matrix_index] * vec4<f32>(world_pos, 1.0);
+    let ndc = clip.xyz / clip.w;
+    return vec3<f32>(ndc.xy * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5), ndc.z);
+}
+
+// 3x3 PCF, anything outside the shadow map counts as lit
+fn sample_shadow_pcf(shadow_map: texture_depth_2d_array, shadow_sampler: sampler_comparison, coords: vec3<f32>, layer: u32) -> f32 {
+    if (coords.z > 1.0 || any(coords.xy < vec2<f32>(0.0)) || any(coords.xy > vec2<f32>(1.0))) {
+        return 1.0;
+    }
+
+    let texel_size = 1.0 / vec2<f32>(textureDimensions(shadow_map));
+    var visibility = 0.0;
+
+    for (var x: i32 = -1; x <= 1; x = x + 1) {
+        for (var y: i32 = -1; y <= 1; y = y + 1) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel_size;
+            visibility += textureSampleCompareLevel(shadow_map, shadow_sampler, coords.xy + offset, layer, coords.z);
+        }
+    }
+
+    return visibility / 9.0;
+}
+
+fn point_shadow_face(light_to_frag: vec3<f32>) -> u32 {
+    let a = abs(light_to_frag);
+
+    if (a.x >= a.y && a.x >= a.z) {
+        return select(1u, 0u, light_to_frag.x > 0.0);
+    }
+    if (a.y >= a.z) {
+        return select(3u, 2u, light_to_frag.y > 0.0);
+    }
+    return select(5u, 4u, light_to_frag.z > 0.0);
+}
+
+fn get_shadow(light: LightUniform, world_pos: vec3<f32>, normal: vec3<f32>) -> f32 {
+    if (light.shadow_index < 0) {
+        return 1.0;
+    }
+
+    let caster = shadows.casters[u32(light.shadow_index)];
+    let biased_pos = world_pos + normal * SHADOW_NORMAL_OFFSET;
+
+    if (caster.light_type == LIGHT_TYPE_POINT) {
+        let face = point_shadow_face(biased_pos - light.position);
+        let coords = project_shadow_coords(caster.matrix_index + face, biased_pos);
+        return sample_shadow_pcf(t_point_shadows, s_point_shadows, coords, caster.layer + face);
+    }
+
+    if (caster.light_type == LIGHT_TYPE_SPOT) {
+        let coords = project_shadow_coords(caster.matrix_index, biased_pos);
+        return sample_shadow_pcf(t_spot_shadows, s_spot_shadows, coords, caster.layer);
+    }
+
+    let view_depth = -(camera.view * vec4<f32>(world_pos, 1.0)).z;
+    var cascade = SHADOW_CASCADE_COUNT;
+    for (var i: u32 = 0u; i < SHADOW_CASCADE_COUNT; i = i + 1u) {
+        if (view_depth < shadows.cascade_splits[i]) {
+            cascade = i;
+            break;
+        }
+    }
+
+    if (cascade >= SHADOW_CASCADE_COUNT) {
+        return 1.0;
+    }
+
+    let coords = project_shadow_coords(caster.matrix_index + cascade, biased_pos);
+    return sample_shadow_pcf(t_cascade_shadows, s_cascade_shadows, coords, caster.layer + cascade);
+}
+
 fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
     return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
 }
 
+fn fresnel_schlick_roughness(cos_theta: f32, f0: vec3<f32>, roughness: f32) -> vec3<f32> {
+    return f0 + (max(vec3<f32>(1.0 - roughness), f0) - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
 fn distribution_ggx(n: vec3<f32>, h: vec3<f32>, roughness: f32) -> f32 {
     let a = roughness * roughness;
     let a2 = a * a;
@@ -132,17 +274,53 @@ fn microfacet_brdf(l: vec3<f32>, v: vec3<f32>, n: vec3<f32>, base_color: vec3<f3
     return (kd * base_color / PI + specular) * ndotl;
 }
 
+// split sum approximation of the environment, uses the same f0 as microfacet_brdf
+fn get_ambient_lighting(v: vec3<f32>, n: vec3<f32>, base_color: vec3<f32>, metallic: f32, fresnel_reflect: f32, roughness: f32) -> vec3<f32> {
+    let ndotv = max(dot(n, v), 0.0);
+    let r = reflect(-v, n);
+
+    var f0 = vec3<f32>(0.04 * fresnel_reflect);
+    f0 = mix(f0, base_color, metallic);
+
+    let f = fresnel_schlick_roughness(ndotv, f0, roughness);
+    var kd = vec3<f32>(1.0) - f;
+    kd *= 1.0 - metallic;
+
+    let irradiance = textureSample(t_irradiance, s_irradiance, n).rgb;
+    let diffuse = irradiance * base_color;
+
+    let prefiltered = textureSampleLevel(t_prefiltered, s_prefiltered, r, roughness * MAX_REFLECTION_LOD).rgb;
+    let brdf = textureSample(t_brdf_lut, s_brdf_lut, vec2<f32>(ndotv, roughness)).rg;
+    let specular = prefiltered * (f * brdf.x + brdf.y);
+
+    return kd * diffuse + specular;
+}
+
 @vertex
-fn vs_main(vert_in: VertexInput) -> VertexOutput {
+fn vs_main(vert_in: VertexInput, instance: InstanceInput) -> VertexOutput {
     var out: VertexOutput;
 
-    out.tex_coords = model.tex_scale * vert_in.tex_coords;
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+
+    out.tex_coords = instance.tex_scale.xy * vert_in.tex_coords;
+    out.alpha = instance.tex_scale.zw;
     
-    let world_position: vec4<f32> = model.model_matrix * vec4<f32>(vert_in.position, 1.0);
+    let world_position: vec4<f32> = model_matrix * vec4<f32>(vert_in.position, 1.0);
     out.world_position = world_position.xyz;
 
-    let N = normalize((model.model_matrix * vec4<f32>(vert_in.normal, 0.0)).xyz);
-    var T = normalize((model.model_matrix * vec4<f32>(vert_in.tangent, 0.0)).xyz);
+    let normal_matrix = mat3x3<f32>(
+        instance.normal_matrix_0.xyz,
+        instance.normal_matrix_1.xyz,
+        instance.normal_matrix_2.xyz,
+    );
+
+    let N = normalize(normal_matrix * vert_in.normal);
+    var T = normalize((model_matrix * vec4<f32>(vert_in.tangent, 0.0)).xyz);
     T = normalize(T - dot(T, N) * N);
     let B = cross(N, T);
 
@@ -155,24 +333,44 @@ fn vs_main(vert_in: VertexInput) -> VertexOutput {
     return out;
 }
 
-fn get_spot_light_lighting(light_pos: vec3<f32>, light_color: vec3<f32>, light_strength: f32, light_radius: f32, world_pos: vec3<f32>, view_pos: vec3<f32>, normal: vec3<f32>, base_color: vec3<f32>, metallic: f32, roughness: f32) -> vec3<f32> {
-    let l = normalize(light_pos - world_pos);
+fn get_point_light_lighting(light: LightUniform, world_pos: vec3<f32>, view_pos: vec3<f32>, normal: vec3<f32>, base_color: vec3<f32>, metallic: f32, roughness: f32) -> vec3<f32> {
+    let l = normalize(light.position - world_pos);
     let v = normalize(view_pos - world_pos);
 
-    let distance = length(light_pos - world_pos);
-    let d = length(light_pos - world_pos);
-    let nd = d / light_radius;
+    let d = length(light.position - world_pos);
+    let nd = d / light.radius;
     let attenuation = 1.0 / (nd * nd + 1.0);
-    let radiance = light_color * attenuation * light_strength;
+    let radiance = light.color * attenuation * light.strength;
 
     let brdf = microfacet_brdf(l, v, normal, base_color, metallic, 1.0, roughness) * radiance;
     return brdf;
 }
 
+fn get_directional_light_lighting(light: LightUniform, world_pos: vec3<f32>, view_pos: vec3<f32>, normal: vec3<f32>, base_color: vec3<f32>, metallic: f32, roughness: f32) -> vec3<f32> {
+    let l = normalize(-light.direction);
+    let v = normalize(view_pos - world_pos);
+    let radiance = light.color * light.strength;
+
+    return microfacet_brdf(l, v, normal, base_color, metallic, 1.0, roughness) * radiance;
+}
+
+fn get_spot_light_lighting(light: LightUniform, world_pos: vec3<f32>, view_pos: vec3<f32>, normal: vec3<f32>, base_color: vec3<f32>, metallic: f32, roughness: f32) -> vec3<f32> {
+    let l = normalize(light.position - world_pos);
+    let theta = dot(l, normalize(-light.direction));
+    let cone = smoothstep(light.outer_cone_cos, light.inner_cone_cos, theta);
+
+    return get_point_light_lighting(light, world_pos, view_pos, normal, base_color, metallic, roughness) * cone;
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let base_texture: vec4<f32> = textureSample(t_base_color, s_base_color, in.tex_coords);
 
+    // alpha tested cut-outs, the cutoff is 0 for the other modes
+    if base_texture.a < in.alpha.x {
+        discard;
+    }
+
     let albedo: vec3<f32> = pow(textureSample(t_base_color, s_base_color, in.tex_coords).rgb, vec3<f32>(2.2));
     let tangent_normal: vec3<f32> = textureSample(t_normal_map, s_normal_map, in.tex_coords).xyz * 2.0 - 1.0;
     let world_normal = normalize(mat3x3<f32>(in.tangent, in.bitangent, in.normal) * tangent_normal);
@@ -180,15 +378,36 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
     let roughness = rma.r;
     let metallic = rma.g;
-    let ao = rma.b;
+    // screen space occlusion is rendered at the same resolution as this pass
+    let ssao = textureLoad(t_ssao, vec2<i32>(in.clip_position.xy), 0).r;
+    let ao = rma.b * ssao;
 
     var final_color = vec3<f32>(0.0);
 
     for (var i: u32 = 0u; i < light_count; i = i + 1u) {
         let light = lights[i];
 
-        final_color += get_spot_light_lighting(light.position, light.color, light.strength, light.radius, in.world_position, camera.view_position.xyz, world_normal, albedo, metallic, roughness);
+        let shadow = get_shadow(light, in.world_position, normalize(in.normal));
+
+        var radiance = vec3<f32>(0.0);
+        switch light.light_type {
+            case LIGHT_TYPE_DIRECTIONAL: {
+                radiance = get_directional_light_lighting(light, in.world_position, camera.view_position.xyz, world_normal, albedo, metallic, roughness);
+            }
+            case LIGHT_TYPE_SPOT: {
+                radiance = get_spot_light_lighting(light, in.world_position, camera.view_position.xyz, world_normal, albedo, metallic, roughness);
+            }
+            default: {
+                radiance = get_point_light_lighting(light, in.world_position, camera.view_position.xyz, world_normal, albedo, metallic, roughness);
+            }
+        }
+
+        final_color += radiance * shadow;
     }
+
+    let view_dir = normalize(camera.view_position.xyz - in.world_position);
+    final_color += get_ambient_lighting(view_dir, world_normal, albedo, metallic, 1.0, roughness) * ao;
    
-    return vec4<f32>(final_color, 1.0);
+    let alpha = select(1.0, base_texture.a, in.alpha.y > 0.5);
+    return vec4<f32>(final_color, alpha);
 }
\ No newline at end of file
@@ -0,0 +1,84 @@
+// Eye adaptation: bins the log luminance of the HDR image into a histogram, then averages it
+// and moves the adapted luminance towards the average.
+
+const BIN_COUNT: u32 = 256u;
+
+struct ExposureUniform {
+    min_log_luminance: f32,
+    log_luminance_range: f32,
+    // fraction of the way to the new average covered this frame, 1 snaps to it
+    adaptation: f32,
+    _pad: f32,
+}
+
+@group(0) @binding(0)
+var hdr_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var hdr_sampler: sampler;
+
+@group(1) @binding(0)
+var<storage, read_write> histogram: array<atomic<u32>, BIN_COUNT>;
+@group(1) @binding(1)
+var<storage, read_write> adapted_luminance: f32;
+
+@group(2) @binding(0)
+var<uniform> exposure: ExposureUniform;
+
+var<workgroup> local_bins: array<atomic<u32>, BIN_COUNT>;
+var<workgroup> weighted_bins: array<f32, BIN_COUNT>;
+
+// bin 0 holds the (nearly) black pixels which are left out of the average
+fn luminance_to_bin(color: vec3<f32>) -> u32 {
+    let luminance = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+
+    if luminance < 0.005 {
+        return 0u;
+    }
+
+    let t = clamp((log2(luminance) - exposure.min_log_luminance) / exposure.log_luminance_range, 0.0, 1.0);
+    return u32(t * 254.0 + 1.0);
+}
+
+@compute @workgroup_size(16, 16)
+fn cs_histogram(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invocation_index) li: u32) {
+    atomicStore(&local_bins[li], 0u);
+    workgroupBarrier();
+
+    let size = textureDimensions(hdr_texture);
+
+    if gid.x < size.x && gid.y < size.y {
+        let color = textureLoad(hdr_texture, vec2<i32>(gid.xy), 0).rgb;
+        atomicAdd(&local_bins[luminance_to_bin(color)], 1u);
+    }
+
+    workgroupBarrier();
+    atomicAdd(&histogram[li], atomicLoad(&local_bins[li]));
+}
+
+@compute @workgroup_size(256)
+fn cs_average(@builtin(local_invocation_index) li: u32) {
+    let count = atomicLoad(&histogram[li]);
+    weighted_bins[li] = f32(count) * f32(li);
+
+    // cleared for the next frame
+    atomicStore(&histogram[li], 0u);
+    workgroupBarrier();
+
+    for (var stride = BIN_COUNT / 2u; stride > 0u; stride = stride >> 1u) {
+        if li < stride {
+            weighted_bins[li] += weighted_bins[li + stride];
+        }
+        workgroupBarrier();
+    }
+
+    if li == 0u {
+        let size = textureDimensions(hdr_texture);
+        let lit_pixels = max(f32(size.x * size.y) - f32(count), 1.0);
+
+        let average_bin = max(weighted_bins[0] / lit_pixels - 1.0, 0.0);
+        let log_average = average_bin / 254.0 * exposure.log_luminance_range + exposure.min_log_luminance;
+        let target_luminance = exp2(log_average);
+
+        adapted_luminance = adapted_luminance + (target_luminance - adapted_luminance) * exposure.adaptation;
+    }
+}
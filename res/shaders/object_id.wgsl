@@ -0,0 +1,89 @@
+// Editor picking, writes the id + 1 of whatever covers each pixel (0 is nothing).
+// Meshes get their id through the instance index, lights are drawn as camera facing icons.
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(5) joints: vec4<u32>,
+    @location(6) weights: vec4<f32>,
+}
+
+struct IconInput {
+    @location(0) position: vec3<f32>,
+    @location(1) id: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) id: u32,
+}
+
+struct CameraUniform {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    view_position: vec4<f32>,
+}
+
+struct ModelUniform {
+    model_matrix: mat4x4<f32>
+}
+
+struct SkinUniform {
+   joint_matrices: array<mat4x4<f32>, 512>
+}
+
+const ICON_SIZE: f32 = 0.25;
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+@group(1) @binding(0)
+var<uniform> model: ModelUniform;
+
+@group(2) @binding(0)
+var<uniform> skin: SkinUniform;
+
+@vertex
+fn vs_main(vert_in: VertexInput, @builtin(instance_index) id: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    // only skinned vertices have weights
+    var position = vec4<f32>(vert_in.position, 1.0);
+
+    if dot(vert_in.weights, vec4<f32>(1.0)) > 0.0 {
+        var skinned = vec4<f32>(0.0);
+
+        for (var i = 0u; i < 4u; i++) {
+            skinned += vert_in.weights[i] * (skin.joint_matrices[vert_in.joints[i]] * position);
+        }
+
+        position = skinned;
+    }
+
+    out.clip_position = camera.projection * camera.view * model.model_matrix * position;
+    out.id = id;
+    return out;
+}
+
+@vertex
+fn vs_icon(@builtin(vertex_index) vi: u32, icon: IconInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, -1.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, 1.0), vec2<f32>(-1.0, 1.0),
+    );
+    let corner = corners[vi] * ICON_SIZE;
+
+    let right = vec3<f32>(camera.view[0].x, camera.view[1].x, camera.view[2].x);
+    let up = vec3<f32>(camera.view[0].y, camera.view[1].y, camera.view[2].y);
+    let world_position = icon.position + right * corner.x + up * corner.y;
+
+    out.clip_position = camera.projection * camera.view * vec4<f32>(world_position, 1.0);
+    out.id = icon.id;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) u32 {
+    return in.id;
+}
@@ -0,0 +1,135 @@
+// One effect of the post effect stack, applied to the tonemapped image.
+// The source is an srgb target so colors are linear here.
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+struct PostEffectUniform {
+    kind: u32, // 0 color grade, 1 vignette, 2 film grain, 3 chromatic aberration, 4 lift gamma gain
+    time: f32,
+    _pad0: f32,
+    _pad1: f32,
+    values0: vec4<f32>,
+    values1: vec4<f32>,
+    values2: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> effect: PostEffectUniform;
+
+@group(2) @binding(0)
+var lut_texture: texture_3d<f32>;
+@group(2) @binding(1)
+var lut_sampler: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = vec2<f32>(
+        f32((vi << 1u) & 2u),
+        f32(vi & 2u),
+    );
+    out.clip_position = vec4<f32>(out.uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv.y = 1.0 - out.uv.y;
+
+    return out;
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+// .cube tables are authored against display encoded colors
+fn color_grade(color: vec3<f32>) -> vec3<f32> {
+    let size = f32(textureDimensions(lut_texture).x);
+    let encoded = clamp(linear_to_srgb(color), vec3<f32>(0.0), vec3<f32>(1.0));
+    let uvw = encoded * ((size - 1.0) / size) + 0.5 / size;
+    let graded = srgb_to_linear(textureSampleLevel(lut_texture, lut_sampler, uvw, 0.0).rgb);
+
+    return mix(color, graded, effect.values0.x);
+}
+
+fn vignette(color: vec3<f32>, uv: vec2<f32>) -> vec3<f32> {
+    let intensity = effect.values0.x;
+    let radius = effect.values0.y;
+    let smoothness = max(effect.values0.z, 1e-4);
+
+    let distance_to_center = distance(uv, vec2<f32>(0.5)) * 1.41421356;
+    let factor = 1.0 - intensity * smoothstep(radius, radius + smoothness, distance_to_center);
+
+    return color * factor;
+}
+
+fn hash(p: vec2<f32>) -> f32 {
+    let q = fract(p * vec2<f32>(123.34, 456.21));
+    let r = q + dot(q, q + 45.32);
+    return fract(r.x * r.y);
+}
+
+// stronger in the mid tones, like grain on film
+fn film_grain(color: vec3<f32>, position: vec2<f32>) -> vec3<f32> {
+    let noise = hash(position + fract(effect.time) * 1000.0) - 0.5;
+    let luminance = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let response = 1.0 - abs(luminance * 2.0 - 1.0);
+
+    return max(color + noise * effect.values0.x * response, vec3<f32>(0.0));
+}
+
+fn chromatic_aberration(uv: vec2<f32>) -> vec3<f32> {
+    let offset = (uv - 0.5) * effect.values0.x * 0.02;
+
+    let r = textureSampleLevel(source_texture, source_sampler, uv + offset, 0.0).r;
+    let g = textureSampleLevel(source_texture, source_sampler, uv, 0.0).g;
+    let b = textureSampleLevel(source_texture, source_sampler, uv - offset, 0.0).b;
+
+    return vec3<f32>(r, g, b);
+}
+
+fn lift_gamma_gain(color: vec3<f32>) -> vec3<f32> {
+    let lift = effect.values0.rgb;
+    let gamma = max(effect.values1.rgb, vec3<f32>(1e-3));
+    let gain = effect.values2.rgb;
+
+    let lifted = gain * (color + lift * (1.0 - color));
+    return pow(max(lifted, vec3<f32>(0.0)), 1.0 / gamma);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = textureSampleLevel(source_texture, source_sampler, in.uv, 0.0).rgb;
+
+    switch effect.kind {
+        case 0u: {
+            color = color_grade(color);
+        }
+        case 1u: {
+            color = vignette(color, in.uv);
+        }
+        case 2u: {
+            color = film_grain(color, in.clip_position.xy);
+        }
+        case 3u: {
+            color = chromatic_aberration(in.uv);
+        }
+        default: {
+            color = lift_gamma_gain(color);
+        }
+    }
+
+    return vec4<f32>(color, 1.0);
+}
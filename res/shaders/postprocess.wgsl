@@ -15,6 +15,41 @@ fn aces_tone_map(hdr: vec3<f32>) -> vec3<f32> {
     return clamp(m2 * (a / b), vec3(0.0), vec3(1.0));
 }
 
+fn agx_contrast_approx(x: vec3<f32>) -> vec3<f32> {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+// minimal AgX fit (Benjamin Wrensch), the result is display encoded so it's linearized again for the srgb target
+fn agx_tone_map(hdr: vec3<f32>) -> vec3<f32> {
+    let inset = mat3x3(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104,
+    );
+    let outset = mat3x3(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116,
+    );
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+
+    var color = inset * hdr;
+    color = clamp(log2(max(color, vec3(1e-10))), vec3(min_ev), vec3(max_ev));
+    color = (color - min_ev) / (max_ev - min_ev);
+    color = agx_contrast_approx(color);
+    color = outset * color;
+
+    return pow(clamp(color, vec3(0.0), vec3(1.0)), vec3(2.2));
+}
+
+fn reinhard_tone_map(hdr: vec3<f32>) -> vec3<f32> {
+    return hdr / (1.0 + hdr);
+}
+
 struct VertexOutput {
     @location(0) uv: vec2<f32>,
     @builtin(position) clip_position: vec4<f32>,
@@ -44,21 +79,68 @@ var hdr_image: texture_2d<f32>;
 var hdr_sampler: sampler;
 
 @group(0) @binding(2)
-var emissive_image: texture_2d<f32>;
+var bloom_image: texture_2d<f32>;
 @group(0) @binding(3)
-var emissive_sampler: sampler;
+var bloom_sampler: sampler;
+
+struct BloomUniform {
+    threshold: f32,
+    knee: f32,
+    radius: f32,
+    intensity: f32,
+}
+
+struct PostProcessUniform {
+    tonemapper: u32, // 0 aces, 1 agx, 2 reinhard, 3 none
+    exposure: f32, // EV
+    auto_exposure: u32,
+    _pad: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> bloom: BloomUniform;
+
+@group(2) @binding(0)
+var<uniform> post_process: PostProcessUniform;
+
+@group(3) @binding(0)
+var<storage, read> adapted_luminance: f32;
+
+fn get_exposure() -> f32 {
+    var exposure = exp2(post_process.exposure);
+
+    // saturation based exposure for the EV100 of the adapted luminance, 1 / (1.2 * 2^ev100)
+    if post_process.auto_exposure != 0u {
+        exposure /= 9.6 * max(adapted_luminance, 1e-4);
+    }
+
+    return exposure;
+}
 
 @fragment
 fn fs_main(vs: VertexOutput) -> @location(0) vec4<f32> {
     let hdr = textureSample(hdr_image, hdr_sampler, vs.uv).rgb;
-    let emissive = textureSample(emissive_image, emissive_sampler, vs.uv).rgb;
+    let bloom_color = textureSample(bloom_image, bloom_sampler, vs.uv).rgb;
+
+    let hdr_with_bloom = (hdr + bloom_color * bloom.intensity) * get_exposure();
 
-    let hdr_with_emissive = hdr + emissive;
+    // no gamma correction here, the target is an srgb format
+    var sdr: vec3<f32>;
 
-    var sdr = aces_tone_map(hdr_with_emissive);
+    switch post_process.tonemapper {
+        case 0u: {
+            sdr = aces_tone_map(hdr_with_bloom);
+        }
+        case 1u: {
+            sdr = agx_tone_map(hdr_with_bloom);
+        }
+        case 2u: {
+            sdr = reinhard_tone_map(hdr_with_bloom);
+        }
+        default: {
+            sdr = clamp(hdr_with_bloom, vec3(0.0), vec3(1.0));
+        }
+    }
 
-    // let gamma: f32 = 2.2;
-    // sdr = pow(sdr, vec3<f32>(1.0 / gamma));
-        
     return vec4(sdr, 1.0);
 }
\ No newline at end of file
@@ -0,0 +1,94 @@
+struct VertexOutput {
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) face: u32,
+    @location(2) @interpolate(flat) mip: u32,
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+// the instance index encodes both the face and the mip level (mip * 6 + face)
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32, @builtin(instance_index) instance: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = vec2<f32>(
+        f32((vi << 1u) & 2u),
+        f32(vi & 2u),
+    );
+    out.clip_position = vec4<f32>(out.uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv.y = 1.0 - out.uv.y;
+    out.face = instance % 6u;
+    out.mip = instance / 6u;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var t_environment: texture_cube<f32>;
+@group(0) @binding(1)
+var s_environment: sampler;
+
+const PI = 3.14159265359;
+// must match PREFILTER_MIP_LEVELS in environment_map.rs
+const MIP_LEVELS = 5u;
+const SAMPLE_COUNT = 512u;
+
+fn cube_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let st = uv * 2.0 - 1.0;
+
+    switch face {
+        case 0u: { return normalize(vec3<f32>(1.0, -st.y, -st.x)); }
+        case 1u: { return normalize(vec3<f32>(-1.0, -st.y, st.x)); }
+        case 2u: { return normalize(vec3<f32>(st.x, 1.0, st.y)); }
+        case 3u: { return normalize(vec3<f32>(st.x, -1.0, -st.y)); }
+        case 4u: { return normalize(vec3<f32>(st.x, -st.y, 1.0)); }
+        default: { return normalize(vec3<f32>(-st.x, -st.y, -1.0)); }
+    }
+}
+
+fn hammersley(i: u32, n: u32) -> vec2<f32> {
+    return vec2<f32>(f32(i) / f32(n), f32(reverseBits(i)) * 2.3283064365386963e-10);
+}
+
+fn importance_sample_ggx(xi: vec2<f32>, n: vec3<f32>, roughness: f32) -> vec3<f32> {
+    let a = roughness * roughness;
+
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+
+    let h = vec3<f32>(cos(phi) * sin_theta, sin(phi) * sin_theta, cos_theta);
+
+    var up = vec3<f32>(1.0, 0.0, 0.0);
+    if (abs(n.z) < 0.999) {
+        up = vec3<f32>(0.0, 0.0, 1.0);
+    }
+    let tangent = normalize(cross(up, n));
+    let bitangent = cross(n, tangent);
+
+    return normalize(tangent * h.x + bitangent * h.y + n * h.z);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let n = cube_direction(in.face, in.uv);
+    let roughness = f32(in.mip) / f32(MIP_LEVELS - 1u);
+
+    // assume the view direction equals the normal
+    let v = n;
+
+    var prefiltered = vec3<f32>(0.0);
+    var total_weight = 0.0;
+
+    for (var i = 0u; i < SAMPLE_COUNT; i++) {
+        let xi = hammersley(i, SAMPLE_COUNT);
+        let h = importance_sample_ggx(xi, n, roughness);
+        let l = normalize(2.0 * dot(v, h) * h - v);
+
+        let ndotl = max(dot(n, l), 0.0);
+        if (ndotl > 0.0) {
+            prefiltered += textureSampleLevel(t_environment, s_environment, l, 0.0).rgb * ndotl;
+            total_weight += ndotl;
+        }
+    }
+
+    return vec4<f32>(prefiltered / max(total_weight, 0.0001), 1.0);
+}
@@ -0,0 +1,113 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+}
+
+struct SkinnedVertexInput {
+    @location(0) position: vec3<f32>,
+    @location(5) joints: vec4<u32>,
+    @location(6) weights: vec4<f32>,
+}
+
+struct MaskedVertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct SkinnedMaskedVertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(5) joints: vec4<u32>,
+    @location(6) weights: vec4<f32>,
+}
+
+// only the instance alpha is read, z holds the cutoff
+struct AlphaInput {
+    @location(14) tex_scale: vec4<f32>,
+}
+
+struct MaskedVertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) @interpolate(flat) alpha_cutoff: f32,
+}
+
+struct ShadowViewUniform {
+    view_projection: mat4x4<f32>,
+}
+
+struct ModelUniform {
+    model_matrix: mat4x4<f32>,
+    normal_matrix: mat3x4<f32>,
+    tex_scale: vec2<f32>,
+    _padding_0: vec2<f32>
+}
+
+@group(0) @binding(0)
+var<uniform> shadow_view: ShadowViewUniform;
+
+struct SkinUniform {
+   joint_matrices: array<mat4x4<f32>, 512>
+}
+
+@group(1) @binding(0)
+var<uniform> model: ModelUniform;
+
+// the material, bound by the skinned and alpha tested pipelines
+@group(2) @binding(0)
+var t_base_color: texture_2d<f32>;
+@group(2) @binding(1)
+var s_base_color: sampler;
+
+// only bound by the skinned pipelines
+@group(3) @binding(0)
+var<uniform> skin: SkinUniform;
+
+fn skin_position(position: vec3<f32>, joints: vec4<u32>, weights: vec4<f32>) -> vec4<f32> {
+    var skinned = vec4<f32>(0.0);
+
+    for (var i = 0u; i < 4u; i++) {
+        skinned += weights[i] * (skin.joint_matrices[joints[i]] * vec4<f32>(position, 1.0));
+    }
+
+    return skinned;
+}
+
+@vertex
+fn vs_main(vert_in: VertexInput) -> @builtin(position) vec4<f32> {
+    return shadow_view.view_projection * model.model_matrix * vec4<f32>(vert_in.position, 1.0);
+}
+
+@vertex
+fn vs_skinned(vert_in: SkinnedVertexInput) -> @builtin(position) vec4<f32> {
+    let position = skin_position(vert_in.position, vert_in.joints, vert_in.weights);
+
+    return shadow_view.view_projection * model.model_matrix * position;
+}
+
+@vertex
+fn vs_masked(vert_in: MaskedVertexInput, alpha: AlphaInput) -> MaskedVertexOutput {
+    var out: MaskedVertexOutput;
+    out.clip_position = shadow_view.view_projection * model.model_matrix * vec4<f32>(vert_in.position, 1.0);
+    out.tex_coords = model.tex_scale * vert_in.tex_coords;
+    out.alpha_cutoff = alpha.tex_scale.z;
+    return out;
+}
+
+@vertex
+fn vs_skinned_masked(vert_in: SkinnedMaskedVertexInput, alpha: AlphaInput) -> MaskedVertexOutput {
+    let position = skin_position(vert_in.position, vert_in.joints, vert_in.weights);
+
+    var out: MaskedVertexOutput;
+    out.clip_position = shadow_view.view_projection * model.model_matrix * position;
+    out.tex_coords = model.tex_scale * vert_in.tex_coords;
+    out.alpha_cutoff = alpha.tex_scale.z;
+    return out;
+}
+
+// alpha tested cut-outs, same test as the lighting shader
+@fragment
+fn fs_masked(in: MaskedVertexOutput) {
+    if textureSample(t_base_color, s_base_color, in.tex_coords).a < in.alpha_cutoff {
+        discard;
+    }
+}
@@ -0,0 +1,113 @@
+struct VertexOutput {
+    @location(0) uv: vec2<f32>,
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+struct SsaoUniform {
+    projection: mat4x4<f32>,
+    inv_projection: mat4x4<f32>,
+    kernel: array<vec4<f32>, 32>,
+    radius: f32,
+    bias: f32,
+    intensity: f32,
+    power: f32,
+    sample_count: u32,
+    blur_sharpness: f32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = vec2<f32>(
+        f32((vi << 1u) & 2u),
+        f32(vi & 2u),
+    );
+    out.clip_position = vec4<f32>(out.uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv.y = 1.0 - out.uv.y;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var t_depth: texture_depth_2d;
+
+@group(1) @binding(0)
+var<uniform> ssao: SsaoUniform;
+
+fn view_position(coord: vec2<i32>) -> vec3<f32> {
+    let dimensions = vec2<i32>(textureDimensions(t_depth));
+    let clamped = clamp(coord, vec2<i32>(0), dimensions - 1);
+    let depth = textureLoad(t_depth, clamped, 0);
+
+    let uv = (vec2<f32>(clamped) + 0.5) / vec2<f32>(dimensions);
+    let ndc = vec4<f32>(uv.x * 2.0 - 1.0, (1.0 - uv.y) * 2.0 - 1.0, depth, 1.0);
+    let position = ssao.inv_projection * ndc;
+
+    return position.xyz / position.w;
+}
+
+// picks the neighbour with the smallest depth difference on each axis so edges don't bleed
+fn reconstruct_normal(coord: vec2<i32>, center: vec3<f32>) -> vec3<f32> {
+    let left = view_position(coord - vec2<i32>(1, 0));
+    let right = view_position(coord + vec2<i32>(1, 0));
+    let up = view_position(coord - vec2<i32>(0, 1));
+    let down = view_position(coord + vec2<i32>(0, 1));
+
+    var dx = right - center;
+    if (abs(center.z - left.z) < abs(right.z - center.z)) {
+        dx = center - left;
+    }
+
+    var dy = up - center;
+    if (abs(center.z - down.z) < abs(up.z - center.z)) {
+        dy = center - down;
+    }
+
+    return normalize(cross(dx, dy));
+}
+
+fn interleaved_gradient_noise(position: vec2<f32>) -> f32 {
+    return fract(52.9829189 * fract(dot(position, vec2<f32>(0.06711056, 0.00583715))));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coord = vec2<i32>(in.clip_position.xy);
+    if (textureLoad(t_depth, coord, 0) >= 1.0) {
+        return vec4<f32>(1.0);
+    }
+
+    let position = view_position(coord);
+    let normal = reconstruct_normal(coord, position);
+
+    let angle = interleaved_gradient_noise(in.clip_position.xy) * 6.28318530718;
+    let random = vec3<f32>(cos(angle), sin(angle), 0.0);
+    let tangent = normalize(random - normal * dot(random, normal));
+    let bitangent = cross(normal, tangent);
+    let tbn = mat3x3<f32>(tangent, bitangent, normal);
+
+    let dimensions = vec2<f32>(textureDimensions(t_depth));
+    let sample_count = min(ssao.sample_count, 32u);
+    var occlusion = 0.0;
+
+    for (var i = 0u; i < sample_count; i++) {
+        let sample_position = position + tbn * ssao.kernel[i].xyz * ssao.radius;
+
+        var offset = ssao.projection * vec4<f32>(sample_position, 1.0);
+        offset = vec4<f32>(offset.xyz / offset.w, 1.0);
+        let sample_uv = vec2<f32>(offset.x * 0.5 + 0.5, 0.5 - offset.y * 0.5);
+
+        let sample_depth = view_position(vec2<i32>(sample_uv * dimensions)).z;
+        let range_check = smoothstep(0.0, 1.0, ssao.radius / max(abs(position.z - sample_depth), 0.0001));
+
+        if (sample_depth >= sample_position.z + ssao.bias) {
+            occlusion += range_check;
+        }
+    }
+
+    let ao = clamp(1.0 - ssao.intensity * occlusion / f32(max(sample_count, 1u)), 0.0, 1.0);
+
+    return vec4<f32>(pow(ao, ssao.power), 0.0, 0.0, 1.0);
+}
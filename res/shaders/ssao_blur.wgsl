@@ -0,0 +1,80 @@
+struct VertexOutput {
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) direction: vec2<i32>,
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+struct SsaoUniform {
+    projection: mat4x4<f32>,
+    inv_projection: mat4x4<f32>,
+    kernel: array<vec4<f32>, 32>,
+    radius: f32,
+    bias: f32,
+    intensity: f32,
+    power: f32,
+    sample_count: u32,
+    blur_sharpness: f32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+// instance 0 blurs horizontally, instance 1 vertically
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32, @builtin(instance_index) instance: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = vec2<f32>(
+        f32((vi << 1u) & 2u),
+        f32(vi & 2u),
+    );
+    out.clip_position = vec4<f32>(out.uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv.y = 1.0 - out.uv.y;
+    out.direction = select(vec2<i32>(1, 0), vec2<i32>(0, 1), instance == 1u);
+
+    return out;
+}
+
+@group(0) @binding(0)
+var t_ao: texture_2d<f32>;
+@group(0) @binding(1)
+var s_ao: sampler;
+
+@group(0) @binding(2)
+var t_depth: texture_depth_2d;
+@group(0) @binding(3)
+var s_depth: sampler_comparison;
+
+@group(1) @binding(0)
+var<uniform> ssao: SsaoUniform;
+
+const BLUR_RADIUS: i32 = 4;
+
+fn linear_depth(coord: vec2<i32>) -> f32 {
+    let depth = textureLoad(t_depth, coord, 0);
+    let position = ssao.inv_projection * vec4<f32>(0.0, 0.0, depth, 1.0);
+
+    return -position.z / position.w;
+}
+
+// depth aware gaussian so occlusion doesn't leak across silhouettes
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let dimensions = vec2<i32>(textureDimensions(t_ao));
+    let coord = vec2<i32>(in.clip_position.xy);
+    let center_depth = linear_depth(coord);
+
+    var total = 0.0;
+    var total_weight = 0.0;
+
+    for (var i = -BLUR_RADIUS; i <= BLUR_RADIUS; i++) {
+        let sample_coord = clamp(coord + in.direction * i, vec2<i32>(0), dimensions - 1);
+        let depth_difference = abs(linear_depth(sample_coord) - center_depth);
+
+        let spatial = exp(-f32(i * i) / (2.0 * f32(BLUR_RADIUS * BLUR_RADIUS) * 0.5));
+        let weight = spatial * exp(-depth_difference * ssao.blur_sharpness);
+
+        total += textureLoad(t_ao, sample_coord, 0).r * weight;
+        total_weight += weight;
+    }
+
+    return vec4<f32>(total / max(total_weight, 0.0001), 0.0, 0.0, 1.0);
+}
@@ -0,0 +1,69 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+}
+
+struct MaskedVertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+}
+
+struct InstanceInput {
+    @location(7) model_matrix_0: vec4<f32>,
+    @location(8) model_matrix_1: vec4<f32>,
+    @location(9) model_matrix_2: vec4<f32>,
+    @location(10) model_matrix_3: vec4<f32>,
+    // xy tex scale, z alpha cutoff
+    @location(14) tex_scale: vec4<f32>,
+}
+
+struct MaskedVertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) @interpolate(flat) alpha_cutoff: f32,
+}
+
+struct CameraUniform {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    view_position: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// only bound by the alpha tested pipeline
+@group(1) @binding(0)
+var t_base_color: texture_2d<f32>;
+@group(1) @binding(1)
+var s_base_color: sampler;
+
+fn get_model_matrix(instance: InstanceInput) -> mat4x4<f32> {
+    return mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+}
+
+@vertex
+fn vs_main(vert_in: VertexInput, instance: InstanceInput) -> @builtin(position) vec4<f32> {
+    return camera.projection * camera.view * get_model_matrix(instance) * vec4<f32>(vert_in.position, 1.0);
+}
+
+@vertex
+fn vs_masked(vert_in: MaskedVertexInput, instance: InstanceInput) -> MaskedVertexOutput {
+    var out: MaskedVertexOutput;
+    out.clip_position = camera.projection * camera.view * get_model_matrix(instance) * vec4<f32>(vert_in.position, 1.0);
+    out.tex_coords = instance.tex_scale.xy * vert_in.tex_coords;
+    out.alpha_cutoff = instance.tex_scale.z;
+    return out;
+}
+
+// alpha tested cut-outs, same test as the lighting shader
+@fragment
+fn fs_masked(in: MaskedVertexOutput) {
+    if textureSample(t_base_color, s_base_color, in.tex_coords).a < in.alpha_cutoff {
+        discard;
+    }
+}
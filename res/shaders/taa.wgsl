@@ -0,0 +1,96 @@
+struct VertexOutput {
+    @location(0) uv: vec2<f32>,
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+struct TaaUniform {
+    inv_view_proj: mat4x4<f32>,
+    view_proj: mat4x4<f32>,
+    prev_view_proj: mat4x4<f32>,
+    feedback: f32,
+    reset: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = vec2<f32>(
+        f32((vi << 1u) & 2u),
+        f32(vi & 2u),
+    );
+    out.clip_position = vec4<f32>(out.uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv.y = 1.0 - out.uv.y;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var t_current: texture_2d<f32>;
+@group(0) @binding(1)
+var s_current: sampler;
+
+@group(0) @binding(2)
+var t_history: texture_2d<f32>;
+@group(0) @binding(3)
+var s_history: sampler;
+
+@group(1) @binding(0)
+var t_depth: texture_depth_2d;
+
+@group(2) @binding(0)
+var<uniform> taa: TaaUniform;
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn clip_to_uv(clip: vec4<f32>) -> vec2<f32> {
+    let ndc = clip.xy / clip.w;
+    return vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let dimensions = vec2<i32>(textureDimensions(t_current));
+    let coord = vec2<i32>(in.clip_position.xy);
+    let current = textureLoad(t_current, coord, 0).rgb;
+
+    if (taa.reset == 1u) {
+        return vec4<f32>(current, 1.0);
+    }
+
+    // the history is clamped to the colors around the pixel to reject stale samples
+    var color_min = current;
+    var color_max = current;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let neighbour_coord = clamp(coord + vec2<i32>(x, y), vec2<i32>(0), dimensions - 1);
+            let neighbour = textureLoad(t_current, neighbour_coord, 0).rgb;
+            color_min = min(color_min, neighbour);
+            color_max = max(color_max, neighbour);
+        }
+    }
+
+    // camera motion only, rebuilt from the depth buffer
+    let depth = textureLoad(t_depth, coord, 0);
+    let ndc = vec2<f32>(in.uv.x * 2.0 - 1.0, 1.0 - in.uv.y * 2.0);
+    let world = taa.inv_view_proj * vec4<f32>(ndc, depth, 1.0);
+    let position = vec4<f32>(world.xyz / world.w, 1.0);
+    let velocity = clip_to_uv(taa.view_proj * position) - clip_to_uv(taa.prev_view_proj * position);
+    let history_uv = in.uv - velocity;
+
+    if (any(history_uv < vec2<f32>(0.0)) || any(history_uv > vec2<f32>(1.0))) {
+        return vec4<f32>(current, 1.0);
+    }
+
+    let history = clamp(textureSampleLevel(t_history, s_history, history_uv, 0.0).rgb, color_min, color_max);
+
+    // weighting by luminance keeps bright pixels from smearing
+    let current_weight = (1.0 - taa.feedback) / (1.0 + luminance(current));
+    let history_weight = taa.feedback / (1.0 + luminance(history));
+    let result = (current * current_weight + history * history_weight) / (current_weight + history_weight);
+
+    return vec4<f32>(result, 1.0);
+}
@@ -2,6 +2,7 @@ use std::cmp::Ordering;
 
 use cgmath::{InnerSpace, VectorSpace};
 use gltf::{animation::{util::ReadOutputs, Reader}, buffer::Data, Buffer};
+use serde::{Deserialize, Serialize};
 
 
 use crate::animation::{node::Nodes};
@@ -44,12 +45,17 @@ struct NodesKeyFrame(
     Vec<(usize, cgmath::Vector3<f32>)>,
 );
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Default)]
 pub enum PlaybackMode {
+    #[default]
     Loop,
     Once,
 }
 
+impl PlaybackMode {
+    pub const ALL: [PlaybackMode; 2] = [PlaybackMode::Loop, PlaybackMode::Once];
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct AnimationState {
     pub current: usize,
@@ -57,10 +63,12 @@ pub struct AnimationState {
     pub total_time: f32,
     pub paused: bool,
     pub playback_mode: PlaybackMode,
+    pub speed: f32,
 }
 
 impl AnimationState {
      fn advance(&mut self, delta_time: f32) {
+        let delta_time = delta_time * self.speed;
         self.time = match self.playback_mode {
             PlaybackMode::Loop => (self.time + delta_time) % self.total_time,
             PlaybackMode::Once => f32::min(self.time + delta_time, self.total_time),
@@ -100,6 +108,27 @@ impl Animations {
         &self.animation_state
     }
 
+    /// Like `update` but with playback state owned by the caller, so objects sharing the model
+    /// keep their own clip and time. Paused states are still posed at their time.
+    pub fn update_state(&mut self, nodes: &mut Nodes, state: &mut AnimationState, delta_time: f32) -> bool {
+        match self.animations.get_mut(state.current) {
+            Some(animation) => {
+                if !state.paused {
+                    state.advance(delta_time);
+                }
+                animation.animate(nodes, state.time)
+            }
+            _ => false,
+        }
+    }
+
+    /// Switches `state` to another clip, restarting it when the clip changes.
+    pub fn set_state_current(&self, state: &mut AnimationState, index: usize) {
+        if let Some(animation) = self.animations.get(index) {
+            state.set_current(index, animation);
+        }
+    }
+
     pub fn set_current(&mut self, index: usize) {
         if index < self.animations.len() {
             if let Some(animation) = self.animations.get(index) {
@@ -112,6 +141,14 @@ impl Animations {
         self.animation_state.playback_mode = playback_mode;
     }
 
+    pub fn set_speed(&mut self, speed: f32) {
+        self.animation_state.speed = speed;
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.animation_state.paused = paused;
+    }
+
     pub fn toggle(&mut self) {
         self.animation_state.paused = !self.animation_state.paused;
     }
@@ -347,6 +384,7 @@ impl Animation {
             total_time,
             paused: false,
             playback_mode: PlaybackMode::Loop,
+            speed: 1.0,
         },
     })
   }
@@ -98,6 +98,10 @@ impl AssetManager {
         }
     }
 
+    pub fn has_material(&self, name: &str) -> bool {
+        self.material_index_map.contains_key(name)
+    }
+
     pub fn get_material_by_name(&self, name: &str) -> Option<&Material> {
         if let Some(&index) = self.material_index_map.get(name) {
              Some(&self.materials[index])
@@ -212,6 +216,11 @@ impl AssetManager {
         }
     }
 
+    /// Whether the model is loaded, without reporting a miss.
+    pub fn has_model(&self, name: &str) -> bool {
+        self.model_index_map.contains_key(name)
+    }
+
     pub fn get_model_by_name_mut(&mut self, name: &str) -> Option<&mut Model> {
         if let Some(index) = self.model_index_map.get(name) {
             self.models.get_mut(*index)
@@ -11,6 +11,7 @@ pub enum TL {
     NonfilterableFloat,
     NonfilterableFloatMultisampled,
     Cube,
+    Float3D,
     UInt,
     SInt,
 }
@@ -40,7 +41,7 @@ impl BindGroupManager {
         it.enumerate().flat_map(move |(i, bgtype)| {
             std::iter::once(wgpu::BindGroupLayoutEntry {
                 binding: binding_offset + (i * 2) as u32,
-                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
                 ty: wgpu::BindingType::Texture {
                    multisampled: matches!(
                         bgtype,
@@ -48,6 +49,7 @@ impl BindGroupManager {
                     ),
                     view_dimension: match bgtype {
                         TL::Cube => wgpu::TextureViewDimension::Cube,
+                        TL::Float3D => wgpu::TextureViewDimension::D3,
                         TL::DepthArray => wgpu::TextureViewDimension::D2Array,
                         _ => wgpu::TextureViewDimension::D2,
                     },
@@ -68,7 +70,7 @@ impl BindGroupManager {
                 count: None
             }).chain(std::iter::once(wgpu::BindGroupLayoutEntry {
               binding: binding_offset + (i * 2 + 1) as u32,
-              visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+              visibility: wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
               ty: wgpu::BindingType::Sampler(
                     if matches!(bgtype, TL::Depth | TL::DepthMultisampled | TL::DepthArray) {
                         wgpu::SamplerBindingType::Comparison
@@ -88,6 +88,18 @@ impl Projection {
     pub fn calc_matrix(&self) -> Matrix4<f32> {
         OPENGL_TO_WGPU_MATRIX * perspective(self.fovy, self.aspect, self.znear, self.zfar)
     }
+
+    pub fn calc_matrix_with_range(&self, znear: f32, zfar: f32) -> Matrix4<f32> {
+        OPENGL_TO_WGPU_MATRIX * perspective(self.fovy, self.aspect, znear, zfar)
+    }
+
+    pub fn get_znear(&self) -> f32 {
+        self.znear
+    }
+
+    pub fn get_zfar(&self) -> f32 {
+        self.zfar
+    }
 }
  
 pub struct CameraController {
@@ -0,0 +1,184 @@
+use crate::{bind_group_manager::{BindGroupManager, TL}, common::{constants::{COLOR_LUT_DIRECTORY, MAX_COLOR_LUT_SIZE}, errors::ColorLutError}, texture::Texture, wgpu_context::WgpuContext};
+
+/// 3D color lookup table loaded from an Adobe/Resolve `.cube` file in res/luts.
+pub struct ColorLut {
+    pub name: String,
+    pub size: u32,
+    pub texture: Texture
+}
+
+impl ColorLut {
+    pub fn load(ctx: &WgpuContext, name: &str) -> Result<Self, ColorLutError> {
+        let source = std::fs::read_to_string(format!("{COLOR_LUT_DIRECTORY}/{name}")).map_err(ColorLutError::Read)?;
+        let (size, entries) = parse_cube(&source)?;
+
+        Ok(Self {
+            name: name.to_string(),
+            size,
+            texture: Self::upload(ctx, size, &entries)
+        })
+    }
+
+    /// 2x2x2 table mapping every color to itself, bound while no LUT is selected.
+    pub fn identity(ctx: &WgpuContext) -> Self {
+        let entries: Vec<[f32; 3]> = (0..8).map(|i| [(i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32]).collect();
+
+        Self {
+            name: String::new(),
+            size: 2,
+            texture: Self::upload(ctx, 2, &entries)
+        }
+    }
+
+    pub fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        BindGroupManager::create_texture_bind_group_layout(device, [TL::Float3D]).unwrap()
+    }
+
+    pub fn create_bind_group(&self, device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> wgpu::BindGroup {
+        BindGroupManager::create_texture_bind_group(device, layout, &self.texture).unwrap()
+    }
+
+    /// Names of the `.cube` files available to the editor.
+    pub fn list_available() -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(COLOR_LUT_DIRECTORY) else {
+            return Vec::new();
+        };
+
+        let mut names: Vec<String> = entries.flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".cube"))
+            .collect();
+
+        names.sort();
+        names
+    }
+
+    fn upload(ctx: &WgpuContext, size: u32, entries: &[[f32; 3]]) -> Texture {
+        // 8 bits per channel is enough once the table is filtered
+        let pixel_data: Vec<u8> = entries.iter()
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 1.0])
+            .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+
+        let extent = wgpu::Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: size,
+        };
+
+        let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Color_LUT_Texture"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[]
+        });
+
+        ctx.queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &pixel_data,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * size),
+                rows_per_image: Some(size),
+            },
+            extent
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D3),
+            ..Default::default()
+        });
+
+        let sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Texture {
+            texture,
+            view,
+            sampler,
+            dimensions: (size, size),
+            pixel_data: Vec::new()
+        }
+    }
+}
+
+/// Parses the 3D table of a `.cube` file, red changes fastest. Entries are normalized to the declared domain.
+fn parse_cube(source: &str) -> Result<(u32, Vec<[f32; 3]>), ColorLutError> {
+    let mut size = None;
+    let mut domain_min = [0.0; 3];
+    let mut domain_max = [1.0; 3];
+    let mut entries = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        let line_number = index + 1;
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next().unwrap_or_default();
+
+        match keyword {
+            "TITLE" | "LUT_1D_INPUT_RANGE" | "LUT_3D_INPUT_RANGE" => {}
+            "LUT_1D_SIZE" => {
+                return Err(ColorLutError::Parse { line: line_number, message: "1D tables are not supported".to_string() });
+            }
+            "LUT_3D_SIZE" => {
+                let value: u32 = parse_value(tokens.next(), line_number)?;
+
+                if !(2..=MAX_COLOR_LUT_SIZE).contains(&value) {
+                    return Err(ColorLutError::UnsupportedSize(value));
+                }
+
+                size = Some(value);
+            }
+            "DOMAIN_MIN" => domain_min = parse_triplet(tokens, line_number)?,
+            "DOMAIN_MAX" => domain_max = parse_triplet(tokens, line_number)?,
+            _ => {
+                let rgb = parse_triplet(line.split_whitespace(), line_number)?;
+                entries.push([0, 1, 2].map(|c| (rgb[c] - domain_min[c]) / (domain_max[c] - domain_min[c]).max(f32::EPSILON)));
+            }
+        }
+    }
+
+    let size = size.ok_or(ColorLutError::MissingSize)?;
+    let expected = (size * size * size) as usize;
+
+    if entries.len() != expected {
+        return Err(ColorLutError::WrongEntryCount { expected, found: entries.len() });
+    }
+
+    Ok((size, entries))
+}
+
+fn parse_value<T: std::str::FromStr>(token: Option<&str>, line: usize) -> Result<T, ColorLutError> {
+    token
+        .and_then(|token| token.parse().ok())
+        .ok_or_else(|| ColorLutError::Parse { line, message: format!("invalid value {:?}", token.unwrap_or_default()) })
+}
+
+fn parse_triplet<'a>(mut tokens: impl Iterator<Item = &'a str>, line: usize) -> Result<[f32; 3], ColorLutError> {
+    Ok([
+        parse_value(tokens.next(), line)?,
+        parse_value(tokens.next(), line)?,
+        parse_value(tokens.next(), line)?
+    ])
+}
@@ -0,0 +1,286 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{common::{constants::MAX_COMMAND_HISTORY, create_info::{AnimatedGameObjectCreateInfo, GameObjectCreateInfo, LightObjectCreateInfo, PrefabInstanceCreateInfo, SceneNodeRef}}, engine::GameData, objects::{animated_game_object::AnimatedGameObject, game_object::GameObject, light_object::LightObject}};
+
+/// Reversible editor mutation. Objects are restored with their original id so the selection,
+/// the per object uniforms and the children parented to them keep pointing at them.
+pub enum EditorCommand {
+    EditGameObject { id: usize, before: GameObjectCreateInfo, after: GameObjectCreateInfo },
+    EditAnimatedGameObject { id: usize, before: AnimatedGameObjectCreateInfo, after: AnimatedGameObjectCreateInfo },
+    EditLight { id: usize, before: LightObjectCreateInfo, after: LightObjectCreateInfo },
+    AddGameObject { id: usize, index: usize, parent: Option<usize>, create_info: GameObjectCreateInfo },
+    RemoveGameObject { id: usize, index: usize, parent: Option<usize>, create_info: GameObjectCreateInfo },
+    AddAnimatedGameObject { id: usize, index: usize, parent: Option<usize>, create_info: AnimatedGameObjectCreateInfo },
+    RemoveAnimatedGameObject { id: usize, index: usize, parent: Option<usize>, create_info: AnimatedGameObjectCreateInfo },
+    AddLight { id: usize, index: usize, create_info: LightObjectCreateInfo },
+    RemoveLight { id: usize, index: usize, parent: Option<usize>, create_info: LightObjectCreateInfo },
+    /// `ids` are the spawned objects by their prefab reference, redoing spawns them with the same ids.
+    AddPrefabInstance { index: usize, create_info: PrefabInstanceCreateInfo, ids: Vec<(SceneNodeRef, usize)> },
+    EditEnvironment { before: Option<String>, after: Option<String> },
+    /// Reparenting keeps the world transform, so the local transform changes with the parent.
+    SetParent { id: usize, before: Option<usize>, after: Option<usize>, before_transform: cgmath::Matrix4<f32>, after_transform: cgmath::Matrix4<f32> },
+    /// Commands undone and redone as one entry, e.g. a drag over several selected objects.
+    Batch(Vec<EditorCommand>)
+}
+
+impl EditorCommand {
+    fn apply(&self, game_data: &mut GameData, undo: bool) {
+        match self {
+            EditorCommand::EditGameObject { id, before, after } => {
+                let create_info = if undo { before } else { after };
+
+                if let Some(index) = game_data.scene.game_objects.iter().position(|game_object| game_object.id == *id) {
+                    let is_selected = game_data.scene.game_objects[index].is_selected;
+                    let parent = game_data.scene.game_objects[index].parent;
+                    game_data.scene.game_objects[index] = Self::create_game_object(game_data, *id, create_info);
+                    game_data.scene.game_objects[index].set_selected(is_selected);
+                    game_data.scene.game_objects[index].parent = parent;
+                }
+            }
+            EditorCommand::EditAnimatedGameObject { id, before, after } => {
+                let create_info = if undo { before } else { after };
+
+                if let Some(index) = game_data.scene.animated_game_objects.iter().position(|animated_game_object| animated_game_object.object_id == *id) {
+                    let parent = game_data.scene.animated_game_objects[index].parent;
+                    let mut animated_game_object = AnimatedGameObject::new(create_info, &game_data.asset_manager);
+                    animated_game_object.object_id = *id;
+                    animated_game_object.parent = parent;
+                    game_data.scene.animated_game_objects[index] = animated_game_object;
+                }
+            }
+            EditorCommand::EditLight { id, before, after } => {
+                let create_info = if undo { before } else { after };
+
+                if let Some(light) = game_data.scene.lights.iter_mut().find(|light| light.id == *id) {
+                    let parent = light.parent;
+                    *light = Self::create_light(*id, create_info);
+                    light.parent = parent;
+                }
+            }
+            EditorCommand::AddGameObject { id, index, parent, create_info } | EditorCommand::RemoveGameObject { id, index, parent, create_info } => {
+                let is_add = matches!(self, EditorCommand::AddGameObject { .. });
+
+                if is_add == undo {
+                    game_data.scene.remove_game_object_by_id(*id);
+                } else {
+                    let mut game_object = Self::create_game_object(game_data, *id, create_info);
+                    game_object.parent = *parent;
+                    game_data.scene.insert_game_object(*index, game_object);
+                }
+            }
+            EditorCommand::AddAnimatedGameObject { id, index, parent, create_info } | EditorCommand::RemoveAnimatedGameObject { id, index, parent, create_info } => {
+                let is_add = matches!(self, EditorCommand::AddAnimatedGameObject { .. });
+
+                if is_add == undo {
+                    game_data.scene.remove_animated_game_object_by_id(*id);
+                } else {
+                    let mut animated_game_object = AnimatedGameObject::new(create_info, &game_data.asset_manager);
+                    animated_game_object.object_id = *id;
+                    animated_game_object.parent = *parent;
+                    game_data.scene.insert_animated_game_object(*index, animated_game_object);
+                }
+            }
+            EditorCommand::AddLight { id, index, create_info } => {
+                if undo {
+                    game_data.scene.remove_light_by_id(*id);
+                } else {
+                    game_data.scene.insert_light(*index, Self::create_light(*id, create_info));
+                }
+            }
+            EditorCommand::RemoveLight { id, index, parent, create_info } => {
+                if undo {
+                    let mut light = Self::create_light(*id, create_info);
+                    light.parent = *parent;
+                    game_data.scene.insert_light(*index, light);
+                } else {
+                    game_data.scene.remove_light_by_id(*id);
+                }
+            }
+            EditorCommand::AddPrefabInstance { index, create_info, ids } => {
+                if undo {
+                    game_data.scene.remove_prefab_instance(*index);
+                } else {
+                    game_data.scene.insert_prefab_instance(*index, create_info.clone(), ids, &game_data.asset_manager);
+                }
+            }
+            EditorCommand::EditEnvironment { before, after } => {
+                game_data.scene.environment = if undo { before.clone() } else { after.clone() };
+            }
+            EditorCommand::SetParent { id, before, after, before_transform, after_transform } => {
+                let (parent, transform) = if undo { (before, before_transform) } else { (after, after_transform) };
+
+                game_data.scene.set_parent(*id, *parent);
+                game_data.scene.set_local_matrix(*id, *transform);
+            }
+            EditorCommand::Batch(commands) => {
+                if undo {
+                    commands.iter().rev().for_each(|command| command.apply(game_data, true));
+                } else {
+                    commands.iter().for_each(|command| command.apply(game_data, false));
+                }
+            }
+        }
+    }
+
+    fn create_game_object(game_data: &GameData, id: usize, create_info: &GameObjectCreateInfo) -> GameObject {
+        let mut game_object = GameObject::new(create_info, &game_data.asset_manager);
+        game_object.id = id;
+        game_object
+    }
+
+    fn create_light(id: usize, create_info: &LightObjectCreateInfo) -> LightObject {
+        let mut light = LightObject::new(create_info);
+        light.id = id;
+        light
+    }
+}
+
+/// Bounded undo/redo stacks of editor commands.
+/// Edits made through the editor windows are picked up by diffing the selected objects between
+/// `begin_edit` and `end_edit`, a drag only becomes a command once the pointer is released.
+pub struct CommandHistory {
+    undo_stack: VecDeque<EditorCommand>,
+    redo_stack: Vec<EditorCommand>,
+    pending_game_objects: HashMap<usize, GameObjectCreateInfo>,
+    pending_animated_game_objects: HashMap<usize, AnimatedGameObjectCreateInfo>,
+    pending_lights: HashMap<usize, LightObjectCreateInfo>
+}
+
+impl CommandHistory {
+    pub fn new() -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            pending_game_objects: HashMap::new(),
+            pending_animated_game_objects: HashMap::new(),
+            pending_lights: HashMap::new()
+        }
+    }
+
+    /// Records a command that was already applied to the scene.
+    pub fn push(&mut self, command: EditorCommand) {
+        if self.undo_stack.len() >= MAX_COMMAND_HISTORY {
+            self.undo_stack.pop_front();
+        }
+
+        self.undo_stack.push_back(command);
+        self.redo_stack.clear();
+    }
+
+    /// Records commands that were applied together as one entry.
+    pub fn push_all(&mut self, mut commands: Vec<EditorCommand>) {
+        match commands.len() {
+            0 => {}
+            1 => self.push(commands.pop().unwrap()),
+            _ => self.push(EditorCommand::Batch(commands))
+        }
+    }
+
+    pub fn undo(&mut self, game_data: &mut GameData) {
+        self.discard_pending();
+
+        if let Some(command) = self.undo_stack.pop_back() {
+            command.apply(game_data, true);
+            self.redo_stack.push(command);
+        }
+    }
+
+    pub fn redo(&mut self, game_data: &mut GameData) {
+        self.discard_pending();
+
+        if let Some(command) = self.redo_stack.pop() {
+            command.apply(game_data, false);
+            self.undo_stack.push_back(command);
+        }
+    }
+
+    fn discard_pending(&mut self) {
+        self.pending_game_objects.clear();
+        self.pending_animated_game_objects.clear();
+        self.pending_lights.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Ctrl+Z undoes, Ctrl+Y and Ctrl+Shift+Z redo.
+    pub fn handle_shortcuts(&mut self, ctx: &egui::Context, game_data: &mut GameData) {
+        let (redo, undo) = ctx.input_mut(|input| {
+            let redo = input.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z)
+                || input.consume_key(egui::Modifiers::COMMAND, egui::Key::Y);
+
+            (redo, input.consume_key(egui::Modifiers::COMMAND, egui::Key::Z))
+        });
+
+        if undo {
+            self.undo(game_data);
+        } else if redo {
+            self.redo(game_data);
+        }
+    }
+
+    /// Snapshots the selected objects before the editor windows run. Snapshots taken while a drag
+    /// is in progress are kept so the whole drag is diffed against the state it started from.
+    pub fn begin_edit(&mut self, game_data: &GameData, selected_game_object_ids: &HashSet<usize>, selected_animated_object_ids: &HashSet<usize>, selected_light_ids: &HashSet<usize>) {
+        for game_object in game_data.scene.game_objects.iter().filter(|game_object| selected_game_object_ids.contains(&game_object.id)) {
+            self.pending_game_objects.entry(game_object.id).or_insert_with(|| game_object.get_create_info(&game_data.asset_manager));
+        }
+
+        for animated_game_object in game_data.scene.animated_game_objects.iter().filter(|animated_game_object| selected_animated_object_ids.contains(&animated_game_object.object_id)) {
+            self.pending_animated_game_objects.entry(animated_game_object.object_id).or_insert_with(|| animated_game_object.get_create_info(&game_data.asset_manager));
+        }
+
+        for light in game_data.scene.lights.iter().filter(|light| selected_light_ids.contains(&light.id)) {
+            self.pending_lights.entry(light.id).or_insert_with(|| light.get_create_info());
+        }
+    }
+
+    /// Pushes one command for every snapshotted object that changed, once the pointer is released.
+    pub fn end_edit(&mut self, game_data: &GameData, pointer_down: bool) {
+        if pointer_down {
+            return;
+        }
+
+        let mut commands = Vec::new();
+
+        for (id, before) in std::mem::take(&mut self.pending_game_objects) {
+            let Some(game_object) = game_data.scene.game_objects.iter().find(|game_object| game_object.id == id) else {
+                continue;
+            };
+
+            let after = game_object.get_create_info(&game_data.asset_manager);
+            if after != before {
+                commands.push(EditorCommand::EditGameObject { id, before, after });
+            }
+        }
+
+        for (id, before) in std::mem::take(&mut self.pending_animated_game_objects) {
+            let Some(animated_game_object) = game_data.scene.animated_game_objects.iter().find(|animated_game_object| animated_game_object.object_id == id) else {
+                continue;
+            };
+
+            let after = animated_game_object.get_create_info(&game_data.asset_manager);
+            if after != before {
+                commands.push(EditorCommand::EditAnimatedGameObject { id, before, after });
+            }
+        }
+
+        for (id, before) in std::mem::take(&mut self.pending_lights) {
+            let Some(light) = game_data.scene.lights.iter().find(|light| light.id == id) else {
+                continue;
+            };
+
+            let after = light.get_create_info();
+            if after != before {
+                commands.push(EditorCommand::EditLight { id, before, after });
+            }
+        }
+
+        self.push_all(commands);
+    }
+}
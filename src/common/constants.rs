@@ -3,9 +3,119 @@ pub const HDR_TEX_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float
 pub const DEPTH_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 pub const DEPTH_TEXTURE_STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32FloatStencil8;  
 
+// the lighting pipeline binds the material, camera, lights, shadow, environment and ssao groups
+pub const REQUIRED_BIND_GROUPS: u32 = 6;
+
 pub const WINDOW_WIDTH: f64 = 1280.0;
 pub const WINDOW_HEIGHT: f64 = 720.0;
 
 pub const FIXED_DELTA_TIME: f32 = 1.0 / 120.0;
 
-pub const MAX_LIGHTS: i32 = 100;
\ No newline at end of file
+pub const MAX_LIGHTS: i32 = 100;
+
+pub const SHADOW_MAP_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+pub const POINT_SHADOW_MAP_SIZE: u32 = 512;
+pub const SPOT_SHADOW_MAP_SIZE: u32 = 1024;
+pub const CASCADE_SHADOW_MAP_SIZE: u32 = 2048;
+
+pub const MAX_POINT_SHADOWS: usize = 4;
+pub const MAX_SPOT_SHADOWS: usize = 4;
+pub const MAX_DIRECTIONAL_SHADOWS: usize = 1;
+pub const SHADOW_CASCADE_COUNT: usize = 4;
+pub const MAX_SHADOW_CASTERS: usize = MAX_POINT_SHADOWS + MAX_SPOT_SHADOWS + MAX_DIRECTIONAL_SHADOWS;
+pub const MAX_SHADOW_MATRICES: usize = MAX_POINT_SHADOWS * 6 + MAX_SPOT_SHADOWS + MAX_DIRECTIONAL_SHADOWS * SHADOW_CASCADE_COUNT;
+
+pub const ENVIRONMENT_MAP_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+pub const BRDF_LUT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rg16Float;
+
+pub const ENVIRONMENT_MAP_SIZE: u32 = 512;
+pub const IRRADIANCE_MAP_SIZE: u32 = 32;
+pub const PREFILTER_MAP_SIZE: u32 = 128;
+pub const BRDF_LUT_SIZE: u32 = 512;
+
+// must match MIP_LEVELS in prefilter.wgsl and MAX_REFLECTION_LOD in lighting.wgsl
+pub const PREFILTER_MIP_LEVELS: u32 = 5;
+
+// must match the kernel array size in ssao.wgsl
+pub const SSAO_KERNEL_SIZE: usize = 32;
+pub const SSAO_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+// render graph resources
+pub const GRAPH_SWAPCHAIN: &str = "swapchain";
+pub const GRAPH_SHADOW_MAPS: &str = "shadow_maps";
+pub const GRAPH_DEPTH: &str = "depth";
+pub const GRAPH_HDR: &str = "hdr";
+pub const GRAPH_EMISSIVE: &str = "emissive";
+pub const GRAPH_SSAO_DEPTH: &str = "ssao_depth";
+pub const GRAPH_SSAO_RAW: &str = "ssao_raw";
+pub const GRAPH_SSAO_BLUR: &str = "ssao_blur";
+pub const GRAPH_SSAO: &str = "ssao";
+
+// bloom chain, each mip is half the size of the previous one starting at half the render size
+pub const BLOOM_MIP_COUNT: usize = 6;
+pub const GRAPH_BLOOM_MIPS: [&str; BLOOM_MIP_COUNT] = ["bloom_mip_0", "bloom_mip_1", "bloom_mip_2", "bloom_mip_3", "bloom_mip_4", "bloom_mip_5"];
+
+
+// render scale applied to the surface sized render targets
+pub const MIN_RENDER_SCALE: f32 = 0.25;
+pub const MAX_RENDER_SCALE: f32 = 2.0;
+
+// anti aliasing
+pub const MSAA_SAMPLE_COUNT: u32 = 4;
+pub const TAA_JITTER_SAMPLES: usize = 8;
+pub const GRAPH_HDR_MSAA: &str = "hdr_msaa";
+pub const GRAPH_EMISSIVE_MSAA: &str = "emissive_msaa";
+pub const GRAPH_LDR: &str = "ldr";
+pub const GRAPH_TAA_RESOLVE: &str = "taa_resolve";
+pub const GRAPH_TAA_HISTORY: &str = "taa_history";
+
+// auto exposure, must match luminance_histogram.wgsl
+pub const LUMINANCE_HISTOGRAM_BINS: usize = 256;
+pub const LUMINANCE_HISTOGRAM_WORKGROUP_SIZE: u32 = 16;
+pub const GRAPH_EXPOSURE: &str = "exposure";
+
+// color grading
+pub const COLOR_LUT_DIRECTORY: &str = "res/luts";
+pub const MAX_COLOR_LUT_SIZE: u32 = 65;
+pub const GRAPH_POST_EFFECT_A: &str = "post_effect_a";
+pub const GRAPH_POST_EFFECT_B: &str = "post_effect_b";
+
+// materials
+pub const DEFAULT_ALPHA_CUTOFF: f32 = 0.5;
+
+// editor picking, the icon size must match object_id.wgsl
+pub const OBJECT_ID_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+pub const LIGHT_ICON_SIZE: f32 = 0.25;
+pub const GRAPH_OBJECT_ID: &str = "object_id";
+pub const GRAPH_OBJECT_ID_DEPTH: &str = "object_id_depth";
+pub const GRAPH_PICKING_READBACK: &str = "picking_readback";
+
+// editor gizmo
+pub const GIZMO_SCREEN_SCALE: f32 = 0.15;
+pub const GIZMO_PICK_RADIUS: f32 = 8.0;
+pub const DEFAULT_GRID_SNAP: f32 = 0.5;
+pub const DEFAULT_ANGLE_SNAP: f32 = 15.0;
+pub const DEFAULT_SCALE_SNAP: f32 = 0.1;
+
+// editor history
+pub const MAX_COMMAND_HISTORY: usize = 128;
+
+// levels
+pub const LEVEL_DIRECTORY: &str = "res/scenes";
+pub const DEFAULT_LEVEL: &str = "test";
+pub const MAX_RECENT_LEVELS: usize = 8;
+// written by the editor so the recent levels survive a restart
+pub const RECENT_LEVELS_FILE: &str = "res/recent_levels.json";
+pub const LEVEL_FORMAT_VERSION: u32 = 1;
+// levels with this extension are saved in the binary format
+pub const LEVEL_BINARY_EXTENSION: &str = "lvl";
+pub const LEVEL_BINARY_MAGIC: [u8; 4] = *b"YLVL";
+
+// scene graph
+pub const MAX_HIERARCHY_DEPTH: usize = 64;
+
+// prefabs
+pub const PREFAB_DIRECTORY: &str = "res/prefabs";
+pub const PREFAB_PLACE_DISTANCE: f32 = 5.0;
+pub const PREFAB_FORMAT_VERSION: u32 = 1;
\ No newline at end of file
@@ -1,15 +1,20 @@
 use serde::{Deserialize, Serialize};
 
-use crate::common::enums::LightType;
+use crate::{animation::animation::PlaybackMode, common::enums::{AlphaMode, LightType, SceneNodeKind}, render_passes::postprocess_pass::PostProcessSettings};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct MeshNodeCreateInfo {
     pub mesh_name: String,
     pub material_name: String,
-    pub emissive: bool
+    pub emissive: bool,
+    // the mesh's imported alpha mode is used when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alpha_mode: Option<AlphaMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alpha_cutoff: Option<f32>
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct GameObjectCreateInfo {
     pub model_name: String,
     pub position: [f32; 3],
@@ -19,18 +24,141 @@ pub struct GameObjectCreateInfo {
     pub mesh_rendering_info: Vec<MeshNodeCreateInfo>
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Skinned object with the animation it starts with, the transform fields are shared with `GameObjectCreateInfo`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AnimatedGameObjectCreateInfo {
+    #[serde(flatten)]
+    pub game_object: GameObjectCreateInfo,
+    // name of the clip, the first clip of the model when missing
+    #[serde(default)]
+    pub animation: Option<String>,
+    #[serde(default)]
+    pub playback_mode: PlaybackMode,
+    #[serde(default = "default_animation_speed")]
+    pub speed: f32,
+    #[serde(default)]
+    pub paused: bool
+}
+
+fn default_animation_speed() -> f32 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct LightObjectCreateInfo {
     pub color: [f32; 3],
     pub position: [f32; 3],
     pub strength: f32,
     pub radius: f32,
-    pub light_type: LightType
+    pub light_type: LightType,
+    #[serde(default = "default_light_direction")]
+    pub direction: [f32; 3],
+    #[serde(default = "default_inner_cone_angle")]
+    pub inner_cone_angle: f32,
+    #[serde(default = "default_outer_cone_angle")]
+    pub outer_cone_angle: f32,
+    #[serde(default)]
+    pub casts_shadows: bool
+}
+
+fn default_light_direction() -> [f32; 3] {
+    [0.0, -1.0, 0.0]
+}
+
+fn default_inner_cone_angle() -> f32 {
+    20.0
+}
+
+fn default_outer_cone_angle() -> f32 {
+    30.0
+}
+
+/// Object in a level file, by its kind and its index in that kind's list.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SceneNodeRef {
+    pub kind: SceneNodeKind,
+    pub index: usize,
+    // index into the level's prefab instances, `index` is then the object's index in the prefab
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefab_instance: Option<usize>
+}
+
+impl SceneNodeRef {
+    /// Object outside of any prefab instance, or an object of the prefab itself inside a prefab file.
+    pub fn new(kind: SceneNodeKind, index: usize) -> Self {
+        Self { kind, index, prefab_instance: None }
+    }
+}
+
+/// Transform hierarchy link, the child's transform is relative to the parent's.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ParentCreateInfo {
+    pub child: SceneNodeRef,
+    pub parent: SceneNodeRef
+}
+
+/// Objects saved together in `res/prefabs` and placed in levels with `PrefabInstanceCreateInfo`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PrefabCreateInfo {
+    // format of the file, older prefabs are migrated when loaded
+    pub version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub game_objects: Vec<GameObjectCreateInfo>,
+    #[serde(default)]
+    pub animated_game_objects: Vec<AnimatedGameObjectCreateInfo>,
+    #[serde(default)]
+    pub lights: Vec<LightObjectCreateInfo>,
+    #[serde(default)]
+    pub parents: Vec<ParentCreateInfo>
+}
+
+/// Prefab placed in a level, the transform applies to the prefab's root objects.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PrefabInstanceCreateInfo {
+    pub prefab: String,
+    pub position: [f32; 3],
+    #[serde(default)]
+    pub rotation: [f32; 3],
+    #[serde(default = "default_prefab_scale")]
+    pub scale: f32,
+    // properties of the prefab objects that differ in this instance
+    #[serde(default)]
+    pub overrides: Vec<PrefabOverrideCreateInfo>,
+    // prefab objects deleted from this instance
+    #[serde(default)]
+    pub removed: Vec<SceneNodeRef>
+}
+
+fn default_prefab_scale() -> f32 {
+    1.0
+}
+
+/// Create info fields of one prefab object replaced in an instance, keyed by field name.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PrefabOverrideCreateInfo {
+    pub object: SceneNodeRef,
+    pub values: serde_json::Map<String, serde_json::Value>
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct LevelCreateInfo {
+    // format of the file, older levels are migrated when loaded
+    pub version: u32,
     pub name: String,
+    // equirectangular HDR in res/environments, the default sky is used when missing
+    #[serde(default)]
+    pub environment: Option<String>,
+    // tonemapping and exposure, defaults when missing
+    #[serde(default)]
+    pub post_process: PostProcessSettings,
     pub game_objects: Vec<GameObjectCreateInfo>,
-    pub lights: Vec<LightObjectCreateInfo>
+    #[serde(default)]
+    pub animated_game_objects: Vec<AnimatedGameObjectCreateInfo>,
+    pub lights: Vec<LightObjectCreateInfo>,
+    #[serde(default)]
+    pub prefab_instances: Vec<PrefabInstanceCreateInfo>,
+    // objects without an entry are roots
+    #[serde(default)]
+    pub parents: Vec<ParentCreateInfo>
 }
\ No newline at end of file
@@ -1,14 +1,145 @@
 use serde::{Deserialize, Serialize};
 
+use crate::common::constants::MSAA_SAMPLE_COUNT;
+
 #[derive(PartialEq)]
 pub enum GameState {
     Playing,
     Editor
 }
 
+/// Level switch asked for during the frame, applied by the engine before rendering.
+#[derive(Debug, Clone)]
+pub enum LevelRequest {
+    Open(std::path::PathBuf),
+    New,
+    /// Rebuilds the current scene so prefab instances pick up edited prefab files.
+    ReloadPrefabs
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum LightType {
     Point,
     Directional,
     Spot
-}
\ No newline at end of file
+}
+
+impl LightType {
+    /// Index used to identify the light type inside shaders.
+    pub fn to_shader_index(&self) -> u32 {
+        match self {
+            LightType::Point => 0,
+            LightType::Directional => 1,
+            LightType::Spot => 2
+        }
+    }
+}
+
+/// Kinds of objects that can be part of the scene transform hierarchy.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SceneNodeKind {
+    GameObject,
+    AnimatedGameObject,
+    Light
+}
+
+/// Passes scheduled by the render graph, see `WgpuRenderer::build_render_graph`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderGraphPass {
+    Shadows,
+    Ssao,
+    Forward,
+    Outline,
+    Taa,
+    Bloom,
+    Exposure,
+    PostProcess,
+    PostEffects,
+    Fxaa,
+    Ui,
+    Picking
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AntiAliasing {
+    Off,
+    Msaa,
+    Fxaa,
+    Taa
+}
+
+impl AntiAliasing {
+    pub const ALL: [AntiAliasing; 4] = [AntiAliasing::Off, AntiAliasing::Msaa, AntiAliasing::Fxaa, AntiAliasing::Taa];
+
+    /// Sample count of the forward pass targets.
+    pub fn get_sample_count(&self) -> u32 {
+        match self {
+            AntiAliasing::Msaa => MSAA_SAMPLE_COUNT,
+            _ => 1
+        }
+    }
+}
+
+/// Operator mapping the exposed HDR image to the display range, saved per level.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Tonemapper {
+    #[default]
+    Aces,
+    AgX,
+    Reinhard,
+    None
+}
+
+impl Tonemapper {
+    pub const ALL: [Tonemapper; 4] = [Tonemapper::Aces, Tonemapper::AgX, Tonemapper::Reinhard, Tonemapper::None];
+
+    /// Index used to identify the tonemapper inside postprocess.wgsl.
+    pub fn to_shader_index(&self) -> u32 {
+        match self {
+            Tonemapper::Aces => 0,
+            Tonemapper::AgX => 1,
+            Tonemapper::Reinhard => 2,
+            Tonemapper::None => 3
+        }
+    }
+}
+/// How the albedo alpha of a mesh is used, imported from the glTF `alphaMode` and saved per mesh node.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AlphaMode {
+    #[default]
+    Opaque,
+    /// Fragments below the cutoff are discarded.
+    Mask,
+    /// Blended over what is behind, drawn after everything else sorted back to front.
+    Blend
+}
+
+impl AlphaMode {
+    pub const ALL: [AlphaMode; 3] = [AlphaMode::Opaque, AlphaMode::Mask, AlphaMode::Blend];
+
+    pub fn from_gltf(alpha_mode: gltf::material::AlphaMode) -> Self {
+        match alpha_mode {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale
+}
+
+impl GizmoMode {
+    pub const ALL: [GizmoMode; 3] = [GizmoMode::Translate, GizmoMode::Rotate, GizmoMode::Scale];
+}
+
+/// Orientation of the gizmo handles, scaling always uses the local axes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GizmoSpace {
+    World,
+    Local
+}
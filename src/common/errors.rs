@@ -1,7 +1,13 @@
+use std::{fmt, path::PathBuf};
+
+use crate::common::create_info::SceneNodeRef;
+
 #[derive(Debug)]
 pub enum WgpuContextError {
     RequestDeviceError(wgpu::RequestDeviceError),
-    NoAdapterFound
+    NoAdapterFound,
+    /// The adapter can't provide a limit the renderer's pipelines depend on.
+    UnsupportedLimit { limit: &'static str, required: u32, supported: u32 }
 }
 
 pub enum MeshNodesError {
@@ -14,4 +20,116 @@ pub enum CharacterControllerError {
     ControllerNotFound,
     ControllerHandleNotFound,
     ControllerBodyNotFound
-}
\ No newline at end of file
+}
+
+#[derive(Debug)]
+pub enum RenderGraphError {
+    UnknownResource { pass: String, resource: String },
+    MissingWriter { pass: String, resource: String },
+    Cycle
+}
+
+#[derive(Debug)]
+pub enum FrameCaptureError {
+    Surface(wgpu::SurfaceError),
+    MissingCopySource,
+    UnsupportedFormat(wgpu::TextureFormat),
+    MapFailed(wgpu::BufferAsyncError),
+    Image(image::ImageError)
+}
+#[derive(Debug)]
+pub enum ColorLutError {
+    Read(std::io::Error),
+    Parse { line: usize, message: String },
+    MissingSize,
+    UnsupportedSize(u32),
+    WrongEntryCount { expected: usize, found: usize }
+}
+
+#[derive(Debug)]
+pub enum LoadLevelError {
+    Read { path: PathBuf, error: std::io::Error },
+    /// Malformed file, `location` is the 1-based line and column when it's known.
+    Parse { path: PathBuf, location: Option<(usize, usize)>, message: String },
+    /// Binary level with a bad header, length or checksum.
+    Corrupt { path: PathBuf, message: String },
+    /// The file was written by a newer build.
+    UnsupportedVersion { path: PathBuf, version: u64, supported: u32 },
+    /// The file parsed but references assets or objects that don't exist.
+    Invalid { path: PathBuf, errors: Vec<LevelValidationError> }
+}
+
+impl fmt::Display for LoadLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadLevelError::Read { path, error } => write!(f, "could not read {}: {}", path.display(), error),
+            LoadLevelError::Parse { path, message, .. } => write!(f, "could not parse {}: {}", path.display(), message),
+            LoadLevelError::Corrupt { path, message } => write!(f, "{} is corrupt: {}", path.display(), message),
+            LoadLevelError::UnsupportedVersion { path, version, supported } => {
+                write!(f, "{} has format version {}, this build supports up to {}", path.display(), version, supported)
+            }
+            LoadLevelError::Invalid { path, errors } => {
+                write!(f, "{} has {} invalid value(s):", path.display(), errors.len())?;
+
+                for error in errors {
+                    match error.location {
+                        Some((line, column)) => write!(f, "\n  {}:{}:{}: {}", path.display(), line, column, error)?,
+                        None => write!(f, "\n  {}: {}", path.display(), error)?
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadLevelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadLevelError::Read { error, .. } => Some(error),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LevelValidationError {
+    /// JSON pointer of the offending value, e.g. `/game_objects/3/model_name`.
+    pub pointer: String,
+    /// 1-based line and column of the value, `None` when it isn't in the file as written.
+    pub location: Option<(usize, usize)>,
+    pub kind: LevelValidationErrorKind
+}
+
+#[derive(Debug)]
+pub enum LevelValidationErrorKind {
+    UnknownModel(String),
+    UnknownMesh { model: String, mesh: String },
+    UnknownMaterial(String),
+    UnknownAnimation { model: String, animation: String },
+    /// Parent link, prefab override or removal of an object that doesn't exist.
+    InvalidReference(SceneNodeRef),
+    MissingPrefab(String),
+    /// The prefab file exists but can't be loaded.
+    InvalidPrefab { prefab: String, message: String },
+    /// Override values that don't fit the prefab object.
+    InvalidOverride(String)
+}
+
+impl fmt::Display for LevelValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.pointer)?;
+
+        match &self.kind {
+            LevelValidationErrorKind::UnknownModel(model) => write!(f, "unknown model `{}`", model),
+            LevelValidationErrorKind::UnknownMesh { model, mesh } => write!(f, "unknown mesh `{}` in model `{}`", mesh, model),
+            LevelValidationErrorKind::UnknownMaterial(material) => write!(f, "unknown material `{}`", material),
+            LevelValidationErrorKind::UnknownAnimation { model, animation } => write!(f, "model `{}` has no animation `{}`", model, animation),
+            LevelValidationErrorKind::InvalidReference(node) => write!(f, "no {:?} at index {}", node.kind, node.index),
+            LevelValidationErrorKind::MissingPrefab(prefab) => write!(f, "prefab `{}` not found", prefab),
+            LevelValidationErrorKind::InvalidPrefab { prefab, message } => write!(f, "prefab `{}` can't be loaded: {}", prefab, message),
+            LevelValidationErrorKind::InvalidOverride(message) => write!(f, "invalid override: {}", message)
+        }
+    }
+}
@@ -1,5 +1,9 @@
+use crate::common::enums::AlphaMode;
+
 pub struct MeshRenderingInfo {
    pub mesh_index: usize,
    pub material_index: usize,
-   pub emissive: bool
+   pub emissive: bool,
+   pub alpha_mode: AlphaMode,
+   pub alpha_cutoff: f32
 }
\ No newline at end of file
@@ -0,0 +1,218 @@
+use cgmath::{InnerSpace, SquareMatrix};
+use yhwh_core::math::aabb::Aabb;
+
+const CIRCLE_SEGMENTS: usize = 32;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DebugVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 3]
+}
+
+impl DebugVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Float32x3
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<DebugVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// Lines of one debug draw call. Lives for a single frame unless given a duration.
+pub struct DebugShape {
+    vertices: Vec<DebugVertex>,
+    remaining: f32,
+    on_top: bool
+}
+
+impl DebugShape {
+    /// Keeps the shape around for `seconds` instead of a single frame.
+    pub fn duration(&mut self, seconds: f32) -> &mut Self {
+        self.remaining = seconds;
+        self
+    }
+
+    /// Draws the shape over the scene instead of depth testing it.
+    pub fn on_top(&mut self) -> &mut Self {
+        self.on_top = true;
+        self
+    }
+}
+
+/// Editor overlays drawn every frame through the debug draw, see `GameData::draw_debug_overlays`.
+#[derive(Default)]
+pub struct DebugDrawSettings {
+    pub mesh_aabbs: bool,
+    pub light_radii: bool,
+    pub colliders: bool,
+    pub camera_frusta: bool
+}
+
+/// Immediate mode line drawing, shapes queued during the update are rendered by the debug pass.
+/// e.g. `game_data.debug_draw.sphere(position, 1.0, color).duration(2.0).on_top()`
+#[derive(Default)]
+pub struct DebugDraw {
+    shapes: Vec<DebugShape>,
+    pub settings: DebugDrawSettings
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ages the timed shapes and drops the expired ones, call once per frame before queueing new shapes.
+    pub fn update(&mut self, delta_time: f32) {
+        self.shapes.retain_mut(|shape| {
+            shape.remaining -= delta_time;
+            shape.remaining > 0.0
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+    }
+
+    /// Line list vertices of the depth tested shapes followed by the on top ones, and the depth tested count.
+    pub fn get_vertices(&self) -> (Vec<DebugVertex>, usize) {
+        let depth_tested = self.shapes.iter().filter(|shape| !shape.on_top).flat_map(|shape| shape.vertices.iter().copied());
+        let on_top = self.shapes.iter().filter(|shape| shape.on_top).flat_map(|shape| shape.vertices.iter().copied());
+
+        let mut vertices: Vec<DebugVertex> = depth_tested.collect();
+        let depth_tested_count = vertices.len();
+        vertices.extend(on_top);
+
+        (vertices, depth_tested_count)
+    }
+
+    fn push(&mut self, lines: Vec<(cgmath::Vector3<f32>, cgmath::Vector3<f32>)>, color: cgmath::Vector3<f32>) -> &mut DebugShape {
+        let vertices = lines.into_iter()
+            .flat_map(|(start, end)| [start, end])
+            .map(|position| DebugVertex { position: position.into(), color: color.into() })
+            .collect();
+
+        self.shapes.push(DebugShape {
+            vertices,
+            remaining: 0.0,
+            on_top: false
+        });
+
+        self.shapes.last_mut().unwrap()
+    }
+
+    pub fn line(&mut self, start: cgmath::Vector3<f32>, end: cgmath::Vector3<f32>, color: cgmath::Vector3<f32>) -> &mut DebugShape {
+        self.push(vec![(start, end)], color)
+    }
+
+    pub fn aabb(&mut self, aabb: &Aabb<f32>, color: cgmath::Vector3<f32>) -> &mut DebugShape {
+        let (min, max) = (*aabb.min(), *aabb.max());
+        let corners = std::array::from_fn(|i| cgmath::Vector3::new(
+            if i & 1 == 0 { min.x } else { max.x },
+            if i & 2 == 0 { min.y } else { max.y },
+            if i & 4 == 0 { min.z } else { max.z }
+        ));
+
+        self.push(box_edges(&corners), color)
+    }
+
+    /// Unit cube centered at the origin, placed by `transform`.
+    pub fn cube(&mut self, transform: &cgmath::Matrix4<f32>, color: cgmath::Vector3<f32>) -> &mut DebugShape {
+        let corners = std::array::from_fn(|i| transform_point(transform, cgmath::Vector3::new(
+            if i & 1 == 0 { -0.5 } else { 0.5 },
+            if i & 2 == 0 { -0.5 } else { 0.5 },
+            if i & 4 == 0 { -0.5 } else { 0.5 }
+        )));
+
+        self.push(box_edges(&corners), color)
+    }
+
+    /// One circle around each axis.
+    pub fn sphere(&mut self, center: cgmath::Vector3<f32>, radius: f32, color: cgmath::Vector3<f32>) -> &mut DebugShape {
+        let mut lines = circle_lines(center, cgmath::Vector3::unit_x(), cgmath::Vector3::unit_y(), radius);
+        lines.extend(circle_lines(center, cgmath::Vector3::unit_y(), cgmath::Vector3::unit_z(), radius));
+        lines.extend(circle_lines(center, cgmath::Vector3::unit_z(), cgmath::Vector3::unit_x(), radius));
+
+        self.push(lines, color)
+    }
+
+    pub fn circle(&mut self, center: cgmath::Vector3<f32>, normal: cgmath::Vector3<f32>, radius: f32, color: cgmath::Vector3<f32>) -> &mut DebugShape {
+        let (u, v) = get_perpendicular_basis(normal);
+        self.push(circle_lines(center, u, v, radius), color)
+    }
+
+    pub fn arrow(&mut self, start: cgmath::Vector3<f32>, end: cgmath::Vector3<f32>, color: cgmath::Vector3<f32>) -> &mut DebugShape {
+        let length = (end - start).magnitude();
+        if length <= f32::EPSILON {
+            return self.push(Vec::new(), color);
+        }
+
+        let direction = (end - start) / length;
+        let (u, v) = get_perpendicular_basis(direction);
+        let head_length = length * 0.2;
+        let head_base = end - direction * head_length;
+
+        let mut lines = vec![(start, end)];
+        lines.extend([u, -u, v, -v].map(|side| (end, head_base + side * head_length * 0.4)));
+
+        self.push(lines, color)
+    }
+
+    /// Camera frustum, `view_projection` maps to wgpu clip space (depth 0 to 1).
+    pub fn frustum(&mut self, view_projection: &cgmath::Matrix4<f32>, color: cgmath::Vector3<f32>) -> &mut DebugShape {
+        let Some(inverse) = view_projection.invert() else {
+            return self.push(Vec::new(), color);
+        };
+
+        let corners = std::array::from_fn(|i| transform_point(&inverse, cgmath::Vector3::new(
+            if i & 1 == 0 { -1.0 } else { 1.0 },
+            if i & 2 == 0 { -1.0 } else { 1.0 },
+            if i & 4 == 0 { 0.0 } else { 1.0 }
+        )));
+
+        self.push(box_edges(&corners), color)
+    }
+}
+
+fn transform_point(matrix: &cgmath::Matrix4<f32>, point: cgmath::Vector3<f32>) -> cgmath::Vector3<f32> {
+    let transformed = matrix * point.extend(1.0);
+    transformed.truncate() / transformed.w
+}
+
+/// Corner `i` has its x, y and z at the max side when bit 0, 1 and 2 are set.
+fn box_edges(corners: &[cgmath::Vector3<f32>; 8]) -> Vec<(cgmath::Vector3<f32>, cgmath::Vector3<f32>)> {
+    let mut edges = Vec::with_capacity(12);
+
+    for i in 0..8 {
+        for bit in [1, 2, 4] {
+            if i & bit == 0 {
+                edges.push((corners[i], corners[i | bit]));
+            }
+        }
+    }
+
+    edges
+}
+
+fn circle_lines(center: cgmath::Vector3<f32>, u: cgmath::Vector3<f32>, v: cgmath::Vector3<f32>, radius: f32) -> Vec<(cgmath::Vector3<f32>, cgmath::Vector3<f32>)> {
+    let point = |i: usize| {
+        let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+        center + (u * angle.cos() + v * angle.sin()) * radius
+    };
+
+    (0..CIRCLE_SEGMENTS).map(|i| (point(i), point(i + 1))).collect()
+}
+
+fn get_perpendicular_basis(direction: cgmath::Vector3<f32>) -> (cgmath::Vector3<f32>, cgmath::Vector3<f32>) {
+    let direction = direction.normalize();
+    let up = if direction.y.abs() < 0.99 { cgmath::Vector3::unit_y() } else { cgmath::Vector3::unit_x() };
+    let u = direction.cross(up).normalize();
+
+    (u, direction.cross(u))
+}
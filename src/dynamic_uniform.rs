@@ -0,0 +1,128 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{u8slice::ToU8Slice, wgpu_context::WgpuContext};
+
+/// One growable uniform buffer holding a `T` per object, bound with a dynamic offset.
+/// Slots of removed objects are handed out again to new ones.
+pub struct DynamicUniform<T> {
+    pub buffer: wgpu::Buffer,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+    stride: usize,
+    capacity: usize,
+    slots: HashMap<usize, usize>,
+    free_slots: Vec<usize>,
+    data: Vec<u8>,
+    _marker: std::marker::PhantomData<T>
+}
+
+impl<T> DynamicUniform<T>
+where T: ToU8Slice {
+    pub fn new(device: &wgpu::Device, capacity: usize) -> Self {
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as usize;
+        let stride = std::mem::size_of::<T>().div_ceil(alignment) * alignment;
+        let capacity = capacity.max(1);
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<T>() as u64),
+                },
+                count: None,
+            }],
+            label: Some(format!("dynamic bind_group_layout for {}", std::any::type_name::<T>()).as_ref()),
+        });
+
+        let buffer = Self::create_buffer(device, stride * capacity);
+        let bind_group = Self::create_bind_group(device, &bind_group_layout, &buffer);
+
+        Self {
+            buffer,
+            bind_group_layout,
+            bind_group,
+            stride,
+            capacity,
+            slots: HashMap::new(),
+            free_slots: Vec::new(),
+            data: vec![0; stride * capacity],
+            _marker: std::marker::PhantomData
+        }
+    }
+
+    fn create_buffer(device: &wgpu::Device, size: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(format!("dynamic buffer for {}", std::any::type_name::<T>()).as_ref()),
+            size: size as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        })
+    }
+
+    fn create_bind_group(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, buffer: &wgpu::Buffer) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(std::mem::size_of::<T>() as u64),
+                }),
+            }],
+            label: Some(format!("dynamic bind_group for {}", std::any::type_name::<T>()).as_ref()),
+        })
+    }
+
+    /// Writes the value of an object into its slot, allocating one if needed.
+    pub fn set(&mut self, id: usize, value: &T) {
+        let slot = match self.slots.get(&id) {
+            Some(slot) => *slot,
+            None => {
+                let slot = self.free_slots.pop().unwrap_or(self.slots.len());
+                self.slots.insert(id, slot);
+                slot
+            }
+        };
+
+        let required = (slot + 1) * self.stride;
+        if required > self.data.len() {
+            self.data.resize(required, 0);
+        }
+
+        let bytes = value.cast_slice();
+        let start = slot * self.stride;
+        self.data[start..start + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// Frees the slots of every object not in `alive_ids`.
+    pub fn retain(&mut self, alive_ids: &HashSet<usize>) {
+        let free_slots = &mut self.free_slots;
+        self.slots.retain(|id, slot| {
+            let alive = alive_ids.contains(id);
+            if !alive {
+                free_slots.push(*slot);
+            }
+            alive
+        });
+    }
+
+    pub fn get_offset(&self, id: usize) -> Option<u32> {
+        self.slots.get(&id).map(|slot| (slot * self.stride) as u32)
+    }
+
+    /// Uploads every slot with a single `write_buffer`, growing the buffer when needed.
+    pub fn update(&mut self, ctx: &WgpuContext) {
+        let slot_count = self.data.len() / self.stride;
+        if slot_count > self.capacity {
+            self.capacity = slot_count.next_power_of_two();
+            self.buffer = Self::create_buffer(&ctx.device, self.capacity * self.stride);
+            self.bind_group = Self::create_bind_group(&ctx.device, &self.bind_group_layout, &self.buffer);
+        }
+
+        ctx.queue.write_buffer(&self.buffer, 0, &self.data);
+    }
+}
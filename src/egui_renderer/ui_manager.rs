@@ -1,6 +1,6 @@
 use egui::TextureId;
 
-use crate::{asset_manager::AssetManager, egui_renderer::windows::scene_hierarchy::SceneHierarchyWindow, wgpu_context::WgpuContext};
+use crate::{asset_manager::AssetManager, command_history::CommandHistory, egui_renderer::windows::{render_settings::RenderSettingsWindow, scene_hierarchy::SceneHierarchyWindow, transform_gizmo::TransformGizmo}, wgpu_context::WgpuContext};
 
 pub struct EguiMaterial {
     pub texture_id: TextureId,
@@ -10,6 +10,9 @@ pub struct EguiMaterial {
 
 pub struct UiManager {
     pub scene_hierarchy_window: SceneHierarchyWindow,
+    pub render_settings_window: RenderSettingsWindow,
+    pub transform_gizmo: TransformGizmo,
+    pub command_history: CommandHistory,
     pub materials: Vec<EguiMaterial>
 }
 
@@ -17,10 +20,20 @@ impl UiManager {
     pub fn new() -> Self {
         Self {
             scene_hierarchy_window: SceneHierarchyWindow::new(),
+            render_settings_window: RenderSettingsWindow::new(),
+            transform_gizmo: TransformGizmo::new(),
+            command_history: CommandHistory::new(),
             materials: Vec::new()
         }
     }
 
+    /// Selections and history refer to objects of the previous level.
+    pub fn reset_level_state(&mut self) {
+        self.scene_hierarchy_window.clear_selection();
+        self.transform_gizmo.reset();
+        self.command_history = CommandHistory::new();
+    }
+
     pub fn register_textures(&mut self, ctx: &WgpuContext, renderer: &mut egui_wgpu::Renderer, asset_manager: &AssetManager) {
         for material in asset_manager.get_all_materials().iter() {
              let material_name = material.name.clone();
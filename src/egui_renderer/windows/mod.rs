@@ -1 +1,3 @@
-pub mod scene_hierarchy;
\ No newline at end of file
+pub mod scene_hierarchy;
+pub mod render_settings;
+pub mod transform_gizmo;
\ No newline at end of file
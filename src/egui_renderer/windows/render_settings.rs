@@ -0,0 +1,220 @@
+use crate::{color_lut::ColorLut, debug_draw::DebugDrawSettings, common::{constants::{MAX_RENDER_SCALE, MIN_RENDER_SCALE}, enums::{AntiAliasing, Tonemapper}}, render_passes::{bloom_pass::BloomSettings, post_effect_pass::{PostEffect, PostEffectKind}, postprocess_pass::PostProcessSettings, ssao_pass::SsaoSettings}};
+
+pub struct RenderSettingsWindow {
+    open: bool
+}
+
+impl RenderSettingsWindow {
+    pub fn new() -> Self {
+        Self {
+            open: true
+        }
+    }
+
+    pub fn draw(&mut self, ui: &egui::Context, render_scale: &mut f32, anti_aliasing: &mut AntiAliasing, taa_feedback: &mut f32, bloom: &mut BloomSettings, post_process: &mut PostProcessSettings, ssao: &mut SsaoSettings, debug_draw: &mut DebugDrawSettings) {
+        egui::Window::new("Render Settings")
+            .open(&mut self.open)
+            .default_width(250.0)
+            .resizable(true)
+            .show(ui, |ui| {
+                ui.collapsing("Resolution", |ui| {
+                    ui.label("Render Scale");
+                    ui.add(egui::Slider::new(render_scale, MIN_RENDER_SCALE..=MAX_RENDER_SCALE).step_by(0.05));
+                });
+
+                ui.collapsing("Anti Aliasing", |ui| {
+                    egui::ComboBox::from_label("Mode")
+                        .selected_text(format!("{:?}", anti_aliasing))
+                        .show_ui(ui, |ui| {
+                            for mode in AntiAliasing::ALL {
+                                ui.selectable_value(anti_aliasing, mode, format!("{:?}", mode));
+                            }
+                        });
+
+                    ui.add_enabled_ui(*anti_aliasing == AntiAliasing::Taa, |ui| {
+                        ui.label("TAA Feedback");
+                        ui.add(egui::Slider::new(taa_feedback, 0.5..=0.98));
+                    });
+                });
+
+                ui.collapsing("Tonemapping", |ui| {
+                    egui::ComboBox::from_label("Tonemapper")
+                        .selected_text(format!("{:?}", post_process.tonemapper))
+                        .show_ui(ui, |ui| {
+                            for tonemapper in Tonemapper::ALL {
+                                ui.selectable_value(&mut post_process.tonemapper, tonemapper, format!("{:?}", tonemapper));
+                            }
+                        });
+
+                    ui.label("Exposure (EV)");
+                    ui.add(egui::Slider::new(&mut post_process.exposure, -10.0..=10.0).step_by(0.1));
+
+                    ui.checkbox(&mut post_process.auto_exposure, "Auto Exposure");
+
+                    ui.add_enabled_ui(post_process.auto_exposure, |ui| {
+                        ui.label("Min Log Luminance");
+                        ui.add(egui::Slider::new(&mut post_process.min_log_luminance, -16.0..=0.0));
+
+                        ui.label("Max Log Luminance");
+                        ui.add(egui::Slider::new(&mut post_process.max_log_luminance, 0.0..=16.0));
+
+                        ui.label("Adaptation Speed");
+                        ui.add(egui::Slider::new(&mut post_process.adaptation_speed, 0.1..=10.0));
+                    });
+                });
+
+                ui.collapsing("Post Effects", |ui| {
+                    Self::draw_post_effects(ui, &mut post_process.effects);
+                });
+
+                ui.collapsing("Bloom", |ui| {
+                    ui.checkbox(&mut bloom.enabled, "Enabled");
+
+                    ui.add_enabled_ui(bloom.enabled, |ui| {
+                        ui.checkbox(&mut bloom.emissive_only, "Emissive Only");
+
+                        ui.label("Threshold");
+                        ui.add(egui::Slider::new(&mut bloom.threshold, 0.0..=10.0));
+
+                        ui.label("Knee");
+                        ui.add(egui::Slider::new(&mut bloom.knee, 0.0..=1.0));
+
+                        ui.label("Intensity");
+                        ui.add(egui::Slider::new(&mut bloom.intensity, 0.0..=5.0));
+
+                        ui.label("Radius");
+                        ui.add(egui::Slider::new(&mut bloom.radius, 0.5..=4.0));
+                    });
+                });
+
+                ui.collapsing("SSAO", |ui| {
+                    ui.checkbox(&mut ssao.enabled, "Enabled");
+
+                    ui.add_enabled_ui(ssao.enabled, |ui| {
+                        ui.label("Radius");
+                        ui.add(egui::Slider::new(&mut ssao.radius, 0.05..=3.0));
+
+                        ui.label("Bias");
+                        ui.add(egui::Slider::new(&mut ssao.bias, 0.0..=0.2));
+
+                        ui.label("Intensity");
+                        ui.add(egui::Slider::new(&mut ssao.intensity, 0.0..=3.0));
+
+                        ui.label("Power");
+                        ui.add(egui::Slider::new(&mut ssao.power, 0.1..=4.0));
+
+                        ui.label("Samples");
+                        ui.add(egui::Slider::new(&mut ssao.sample_count, 1..=32));
+
+                        ui.checkbox(&mut ssao.blur, "Blur");
+                        ui.label("Blur Sharpness");
+                        ui.add(egui::Slider::new(&mut ssao.blur_sharpness, 0.0..=16.0));
+                    });
+                });
+
+                ui.collapsing("Debug Draw", |ui| {
+                    ui.checkbox(&mut debug_draw.mesh_aabbs, "Mesh Bounds");
+                    ui.checkbox(&mut debug_draw.light_radii, "Light Radii");
+                    ui.checkbox(&mut debug_draw.colliders, "Physics Colliders");
+                    ui.checkbox(&mut debug_draw.camera_frusta, "Camera Frusta");
+                });
+            });
+    }
+
+    /// Ordered stack editor, reordering and removal are applied after the list is drawn.
+    fn draw_post_effects(ui: &mut egui::Ui, effects: &mut Vec<PostEffect>) {
+        let mut move_up = None;
+        let mut remove = None;
+        let effect_count = effects.len();
+
+        for (index, effect) in effects.iter_mut().enumerate() {
+            ui.push_id(index, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut effect.enabled, effect.kind.get_name());
+
+                    if ui.add_enabled(index > 0, egui::Button::new("Up")).clicked() {
+                        move_up = Some(index);
+                    }
+                    if ui.add_enabled(index + 1 < effect_count, egui::Button::new("Down")).clicked() {
+                        move_up = Some(index + 1);
+                    }
+                    if ui.button("Remove").clicked() {
+                        remove = Some(index);
+                    }
+                });
+
+                ui.add_enabled_ui(effect.enabled, |ui| {
+                    Self::draw_post_effect_params(ui, &mut effect.kind);
+                });
+            });
+
+            ui.separator();
+        }
+
+        if let Some(index) = move_up {
+            effects.swap(index - 1, index);
+        }
+
+        if let Some(index) = remove {
+            effects.remove(index);
+        }
+
+        egui::ComboBox::from_label("Add Effect")
+            .selected_text("...")
+            .show_ui(ui, |ui| {
+                for kind in PostEffectKind::defaults() {
+                    if ui.selectable_label(false, kind.get_name()).clicked() {
+                        effects.push(PostEffect { enabled: true, kind });
+                    }
+                }
+            });
+    }
+
+    fn draw_post_effect_params(ui: &mut egui::Ui, kind: &mut PostEffectKind) {
+        match kind {
+            PostEffectKind::ColorGrade { lut, strength } => {
+                egui::ComboBox::from_label("LUT")
+                    .selected_text(if lut.is_empty() { "None" } else { lut.as_str() })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(lut, String::new(), "None");
+
+                        for name in ColorLut::list_available() {
+                            ui.selectable_value(lut, name.clone(), name);
+                        }
+                    });
+
+                ui.label("Strength");
+                ui.add(egui::Slider::new(strength, 0.0..=1.0));
+            }
+            PostEffectKind::Vignette { intensity, radius, smoothness } => {
+                ui.label("Intensity");
+                ui.add(egui::Slider::new(intensity, 0.0..=1.0));
+
+                ui.label("Radius");
+                ui.add(egui::Slider::new(radius, 0.0..=1.5));
+
+                ui.label("Smoothness");
+                ui.add(egui::Slider::new(smoothness, 0.01..=1.0));
+            }
+            PostEffectKind::FilmGrain { intensity } => {
+                ui.label("Intensity");
+                ui.add(egui::Slider::new(intensity, 0.0..=0.5));
+            }
+            PostEffectKind::ChromaticAberration { strength } => {
+                ui.label("Strength");
+                ui.add(egui::Slider::new(strength, 0.0..=5.0));
+            }
+            PostEffectKind::LiftGammaGain { lift, gamma, gain } => {
+                for (label, values, range) in [("Lift", lift, -0.5..=0.5), ("Gamma", gamma, 0.1..=3.0), ("Gain", gain, 0.0..=3.0)] {
+                    ui.horizontal(|ui| {
+                        ui.label(label);
+
+                        for value in values.iter_mut() {
+                            ui.add(egui::DragValue::new(value).speed(0.01).range(range.clone()));
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -1,42 +1,74 @@
-use std::collections::{HashMap, HashSet};
+use std::{collections::{HashMap, HashSet}, path::PathBuf};
 
+use cgmath::EuclideanSpace;
 use egui::{Align, Align2, Context, Sense, TextureId, Ui, Vec2, load::SizedTexture};
 
 use crate::{
-    common::{create_info::{GameObjectCreateInfo, LightObjectCreateInfo}, enums::LightType},
+    animation::animation::PlaybackMode,
+    command_history::{CommandHistory, EditorCommand},
+    common::{constants::{MAX_RECENT_LEVELS, PREFAB_PLACE_DISTANCE}, create_info::{AnimatedGameObjectCreateInfo, GameObjectCreateInfo, LightObjectCreateInfo, PrefabInstanceCreateInfo}, enums::{AlphaMode, LevelRequest, LightType, SceneNodeKind}},
     egui_renderer::ui_manager::EguiMaterial,
     engine::GameData,
+    render_passes::picking_pass::PickResult,
+    scene::Scene,
     objects::{
         animated_game_object::{self, AnimatedGameObject},
         game_object::GameObject,
     },
-    utils::json::save_level,
+    utils::json::{get_level_path, get_prefab_path, list_levels, list_prefabs, load_recent_levels, save_level, save_prefab, save_recent_levels},
 };
 
+enum SelectionKind {
+    GameObject,
+    AnimatedGameObject,
+    Light
+}
+
 pub struct SceneHierarchyWindow {
-    selected_game_object_id: isize,
-    selected_light_id: isize,
+    selected_game_object_ids: HashSet<usize>,
+    selected_animated_object_ids: HashSet<usize>,
+    selected_light_ids: HashSet<usize>,
     selected_mesh_index: HashMap<usize, usize>,
 
     add_game_object_selected: bool,
+    add_animated_game_object_selected: bool,
     selected_model_index: usize,
+    selected_animated_model_index: usize,
     selected_material_index: usize,
 
     objects_marked_for_removal: HashSet<usize>,
     scale_uniform: bool,
+
+    save_as_name: String,
+    /// Existing file Save As is waiting for confirmation to overwrite.
+    overwrite_path: Option<PathBuf>,
+    save_error: Option<String>,
+    recent_levels: Vec<PathBuf>,
+
+    selected_prefab: Option<PathBuf>,
+    prefab_name: String,
 }
 
 impl SceneHierarchyWindow {
     pub fn new() -> Self {
         Self {
-            selected_game_object_id: -1,
-            selected_light_id: -1,
+            selected_game_object_ids: HashSet::new(),
+            selected_animated_object_ids: HashSet::new(),
+            selected_light_ids: HashSet::new(),
             selected_mesh_index: HashMap::new(),
             add_game_object_selected: false,
+            add_animated_game_object_selected: false,
             selected_model_index: 0,
+            selected_animated_model_index: 0,
             selected_material_index: 0,
             objects_marked_for_removal: HashSet::new(),
-            scale_uniform: true
+            scale_uniform: true,
+            save_as_name: String::new(),
+            overwrite_path: None,
+            save_error: None,
+            recent_levels: load_recent_levels(),
+            selected_prefab: None,
+            prefab_name: String::new()
         }
     }
 
@@ -45,7 +77,8 @@ impl SceneHierarchyWindow {
         ui: &egui::Context,
         materials: &Vec<EguiMaterial>,
         game_data: &mut GameData,
-        (window_width, window_height): (u32, u32)
+        (window_width, window_height): (u32, u32),
+        command_history: &mut CommandHistory
     ) {
         egui::SidePanel::right("Transforms")
            .resizable(true)
@@ -53,7 +86,7 @@ impl SceneHierarchyWindow {
            .width_range(250.0..=300.0)
             .show(&ui, |ui| {
                 ui.separator();
-                if self.selected_game_object_id != -1 {
+                if !self.selected_game_object_ids.is_empty() {
                      for game_object in game_data.scene.game_objects.iter_mut() {
                     if game_object.is_selected {
                         ui.label("Position X");
@@ -176,6 +209,18 @@ impl SceneHierarchyWindow {
                                 match game_object.get_mesh_nodes_mut().get_mesh_rendering_info_by_mesh_name_mut(&model.meshes[*selected_index].name) {
                                     Some(mesh_node) => {
                                         ui.checkbox(&mut mesh_node.emissive, "");
+
+                                        egui::ComboBox::from_label("Alpha Mode")
+                                            .selected_text(format!("{:?}", mesh_node.alpha_mode))
+                                            .show_ui(ui, |ui| {
+                                                for alpha_mode in AlphaMode::ALL {
+                                                    ui.selectable_value(&mut mesh_node.alpha_mode, alpha_mode, format!("{:?}", alpha_mode));
+                                                }
+                                            });
+
+                                        if mesh_node.alpha_mode == AlphaMode::Mask {
+                                            ui.add(egui::Slider::new(&mut mesh_node.alpha_cutoff, 0.0..=1.0).text("Alpha Cutoff"));
+                                        }
                                     }
                                     _ => {}
                                 }
@@ -194,7 +239,7 @@ impl SceneHierarchyWindow {
                 }
                 }
                
-                self.process_marked_for_removal(game_data);
+                self.process_marked_for_removal(game_data, command_history);
 
                 if self.add_game_object_selected {
                     let models = game_data.asset_manager.get_models();
@@ -221,12 +266,104 @@ impl SceneHierarchyWindow {
 
                     if ui.button("Add").clicked() {
                         game_data.scene.add_game_object(&create_info, &game_data.asset_manager);
+
+                        let index = game_data.scene.game_objects.len() - 1;
+                        let id = game_data.scene.game_objects[index].id;
+                        command_history.push(EditorCommand::AddGameObject { id, index, parent: None, create_info });
+                    }
+                }
+
+                if self.add_animated_game_object_selected {
+                    let animated_models: Vec<&str> = game_data.asset_manager.get_models().iter()
+                        .filter(|model| model.animations.is_some())
+                        .map(|model| model.name.as_str())
+                        .collect();
+
+                    if let Some(model_name) = animated_models.get(self.selected_animated_model_index).or(animated_models.first()).map(|name| name.to_string()) {
+                        egui::ComboBox::from_label("Select Animated Model")
+                            .selected_text(&model_name)
+                            .show_ui(ui, |ui| {
+                                for (index, name) in animated_models.iter().enumerate() {
+                                    ui.selectable_value(&mut self.selected_animated_model_index, index, *name);
+                                }
+                            });
+
+                        let create_info = AnimatedGameObjectCreateInfo {
+                            game_object: GameObjectCreateInfo {
+                                model_name,
+                                position: [1.0, 5.0, 1.0],
+                                rotation: [1.0, 1.0, 1.0],
+                                size: [1.0, 1.0, 1.0],
+                                tex_scale: [1.0, 1.0],
+                                mesh_rendering_info: vec![],
+                            },
+                            animation: None,
+                            playback_mode: PlaybackMode::Loop,
+                            speed: 1.0,
+                            paused: false
+                        };
+
+                        if ui.button("Add").clicked() {
+                            game_data.scene.add_animated_game_object(&create_info, &game_data.asset_manager);
+
+                            let index = game_data.scene.animated_game_objects.len() - 1;
+                            let id = game_data.scene.animated_game_objects[index].object_id;
+                            command_history.push(EditorCommand::AddAnimatedGameObject { id, index, parent: None, create_info });
+                        }
+                    } else {
+                        ui.label("No animated models loaded");
+                    }
+                }
+
+                for animated_game_object in game_data.scene.animated_game_objects.iter_mut() {
+                    if !self.selected_animated_object_ids.contains(&animated_game_object.object_id) {
+                        continue;
+                    }
+
+                    let mut position = animated_game_object.get_position();
+                    ui.label("Position");
+                    ui.horizontal(|ui| {
+                        let changed_x = ui.add(egui::DragValue::new(&mut position.x)).changed();
+                        let changed_y = ui.add(egui::DragValue::new(&mut position.y)).changed();
+                        let changed_z = ui.add(egui::DragValue::new(&mut position.z)).changed();
+
+                        if changed_x || changed_y || changed_z {
+                            animated_game_object.set_position(position);
+                        }
+                    });
+
+                    let animation_names = game_data.asset_manager.get_model_by_name(animated_game_object.get_model_name())
+                        .map(|model| model.get_animation_names())
+                        .unwrap_or_default();
+
+                    egui::ComboBox::from_label("Animation")
+                        .selected_text(animated_game_object.animation.clone().or(animation_names.first().cloned()).unwrap_or_default())
+                        .show_ui(ui, |ui| {
+                            for name in animation_names.iter() {
+                                ui.selectable_value(&mut animated_game_object.animation, Some(name.clone()), name);
+                            }
+                        });
+
+                    egui::ComboBox::from_label("Playback")
+                        .selected_text(format!("{:?}", animated_game_object.playback_mode))
+                        .show_ui(ui, |ui| {
+                            for playback_mode in PlaybackMode::ALL {
+                                ui.selectable_value(&mut animated_game_object.playback_mode, playback_mode, format!("{:?}", playback_mode));
+                            }
+                        });
+
+                    ui.add(egui::Slider::new(&mut animated_game_object.speed, 0.0..=4.0).text("Speed"));
+                    ui.checkbox(&mut animated_game_object.paused, "Paused");
+
+                    ui.separator();
+                    if ui.button("Delete").clicked() {
+                        self.objects_marked_for_removal.insert(animated_game_object.object_id);
                     }
                 }
 
-                if self.selected_light_id > 0 {
+                if !self.selected_light_ids.is_empty() {
                     for light in game_data.scene.lights.iter_mut() {
-                        if light.id as isize == self.selected_light_id {
+                        if self.selected_light_ids.contains(&light.id) {
                             ui.label("Position X");
                             ui.add(egui::DragValue::new(&mut light.position.x));
 
@@ -254,8 +391,42 @@ impl SceneHierarchyWindow {
 
                             ui.label("Radius");
                             ui.add(egui::Slider::new(&mut light.radius, 0.0..=100.0));
+
+                            egui::ComboBox::from_label("Light Type")
+                                .selected_text(format!("{:?}", light.light_type))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut light.light_type, LightType::Point, "Point");
+                                    ui.selectable_value(&mut light.light_type, LightType::Directional, "Directional");
+                                    ui.selectable_value(&mut light.light_type, LightType::Spot, "Spot");
+                                });
+
+                            if light.light_type != LightType::Point {
+                                ui.label("Direction");
+                                ui.horizontal(|ui| {
+                                    ui.add(egui::DragValue::new(&mut light.direction.x).speed(0.01));
+                                    ui.add(egui::DragValue::new(&mut light.direction.y).speed(0.01));
+                                    ui.add(egui::DragValue::new(&mut light.direction.z).speed(0.01));
+                                });
+                            }
+
+                            if light.light_type == LightType::Spot {
+                                ui.label("Inner Cone Angle");
+                                ui.add(egui::Slider::new(&mut light.inner_cone_angle, 0.0..=89.0));
+
+                                ui.label("Outer Cone Angle");
+                                ui.add(egui::Slider::new(&mut light.outer_cone_angle, 0.0..=89.0));
+                            }
+
+                            ui.checkbox(&mut light.casts_shadows, "Casts Shadows");
+
+                            ui.separator();
+                            if ui.button("Delete").clicked() {
+                                self.objects_marked_for_removal.insert(light.id);
+                            }
                         }
                     }
+
+                    self.process_marked_for_removal(game_data, command_history);
                 }
             });
 
@@ -266,20 +437,24 @@ impl SceneHierarchyWindow {
                .show(&ui, |ui| {
                     //ui.set_min_width(200.0);
                     ui.separator();
+                    let additive = ui.input(|input| input.modifiers.shift);
+
+                    egui::CollapsingHeader::new("Hierarchy")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            self.draw_hierarchy(ui, game_data, command_history, additive);
+                        });
+
                     ui.collapsing("Game Objects", |ui| {
                     for (index, game_object) in game_data.scene.game_objects.iter_mut().enumerate() {
-                        let button = ui.button(game_object.get_model_name().to_string() + " (" + &index.to_string() + ")");
+                        let is_selected = self.selected_game_object_ids.contains(&game_object.id);
+                        let button = ui.selectable_label(is_selected, game_object.get_model_name().to_string() + " (" + &index.to_string() + ")");
 
                         if button.clicked() {
-                            self.selected_game_object_id = game_object.id as isize;
-                            self.add_game_object_selected = false;
+                            self.select(SelectionKind::GameObject, game_object.id, additive);
                         }
 
-                        if game_object.id as isize == self.selected_game_object_id {
-                            game_object.set_selected(true);
-                        } else {
-                            game_object.set_selected(false);
-                        }
+                        game_object.set_selected(self.selected_game_object_ids.contains(&game_object.id));
                     }
 
                     ui.separator();
@@ -287,17 +462,19 @@ impl SceneHierarchyWindow {
                         for game_object in game_data.scene.game_objects.iter_mut() {
                             game_object.set_selected(false);
                         }
-                        self.selected_game_object_id = -1;
+                        self.clear_selection();
                         self.add_game_object_selected = true;
-                        self.selected_light_id = -1;
                     }
                 });
 
                 ui.collapsing("Animated Game Objects", |ui| {
                     for (index, animated_game_object) in game_data.scene.animated_game_objects.iter().enumerate() {
-                        ui.label(
-                            animated_game_object.get_model_name().to_string() + &index.to_string(),
-                        );
+                        let is_selected = self.selected_animated_object_ids.contains(&animated_game_object.object_id);
+                        let button = ui.selectable_label(is_selected, animated_game_object.get_model_name().to_string() + &index.to_string());
+
+                        if button.clicked() {
+                            self.select(SelectionKind::AnimatedGameObject, animated_game_object.object_id, additive);
+                        }
 
                         if let Some(model) = game_data.asset_manager.get_model_by_name(animated_game_object.get_model_name()) {
                             if !model.meshes.is_empty() {
@@ -336,16 +513,21 @@ impl SceneHierarchyWindow {
                             }
                         }
                     }
+
+                    ui.separator();
+                    if ui.button("New Animated Object").clicked() {
+                        self.clear_selection();
+                        self.add_animated_game_object_selected = true;
+                    }
                 });
 
                 ui.collapsing("Lights", |ui| {
                     for (index, light) in game_data.scene.lights.iter().enumerate() {
-                        let button = ui.button("Light (".to_string() + &index.to_string() + ")");
+                        let is_selected = self.selected_light_ids.contains(&light.id);
+                        let button = ui.selectable_label(is_selected, "Light (".to_string() + &index.to_string() + ")");
 
                         if button.clicked() {
-                            self.selected_light_id = light.id as isize;
-                            self.add_game_object_selected = false;
-                            self.selected_game_object_id = -1;
+                            self.select(SelectionKind::Light, light.id, additive);
                         }
                     }
 
@@ -357,24 +539,418 @@ impl SceneHierarchyWindow {
                                     position: [2.0, 2.0, 2.0],
                                     radius: 10.0,
                                     strength: 50.0,
-                                    light_type: LightType::Point
+                                    light_type: LightType::Point,
+                                    direction: [0.0, -1.0, 0.0],
+                                    inner_cone_angle: 20.0,
+                                    outer_cone_angle: 30.0,
+                                    casts_shadows: false
                                 };
 
                         game_data.scene.add_light(&create_info);
+
+                        let index = game_data.scene.lights.len() - 1;
+                        let id = game_data.scene.lights[index].id;
+                        command_history.push(EditorCommand::AddLight { id, index, create_info });
                     }
                 });
 
-                ui.collapsing("File", |ui| {
-                    if ui.button("Save Level").clicked() {
-                        save_level(game_data);
+                ui.collapsing("Prefabs", |ui| {
+                    self.draw_prefab_menu(ui, game_data, command_history);
+                });
+
+                ui.collapsing("Environment", |ui| {
+                    let before = game_data.scene.environment.clone();
+
+                    egui::ComboBox::from_label("HDR")
+                        .selected_text(game_data.scene.environment.as_deref().unwrap_or("Default Sky"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut game_data.scene.environment, None, "Default Sky");
+
+                            let Ok(entries) = std::fs::read_dir("res/environments") else {
+                                return;
+                            };
+
+                            for entry in entries.flatten() {
+                                let file_name = entry.file_name().to_string_lossy().to_string();
+                                if file_name.ends_with(".hdr") {
+                                    ui.selectable_value(&mut game_data.scene.environment, Some(file_name.clone()), file_name);
+                                }
+                            }
+                        });
+
+                    if game_data.scene.environment != before {
+                        command_history.push(EditorCommand::EditEnvironment { before, after: game_data.scene.environment.clone() });
                     }
+                });
+
+                ui.collapsing("Edit", |ui| {
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(command_history.can_undo(), egui::Button::new("Undo")).clicked() {
+                            command_history.undo(game_data);
+                        }
+
+                        if ui.add_enabled(command_history.can_redo(), egui::Button::new("Redo")).clicked() {
+                            command_history.redo(game_data);
+                        }
+                    });
+                });
+
+                ui.collapsing("File", |ui| {
+                    self.draw_file_menu(ui, game_data);
                 })
             });
     }
 
-    pub fn process_marked_for_removal(&mut self, game_data: &mut GameData) {
+    /// Tree of the scene transform hierarchy, dropping an object on another one parents it.
+    fn draw_hierarchy(&mut self, ui: &mut Ui, game_data: &mut GameData, command_history: &mut CommandHistory, additive: bool) {
+        let mut reparent: Option<(usize, Option<usize>)> = None;
+
+        for id in game_data.scene.get_children(None) {
+            self.draw_hierarchy_node(ui, &game_data.scene, id, additive, &mut reparent);
+        }
+
+        let (_, payload) = ui.dnd_drop_zone::<usize, ()>(egui::Frame::default().inner_margin(4.0), |ui| {
+            ui.weak("Drop here to unparent");
+        });
+
+        if let Some(id) = payload {
+            reparent = Some((*id, None));
+        }
+
+        for game_object in game_data.scene.game_objects.iter_mut() {
+            game_object.set_selected(self.selected_game_object_ids.contains(&game_object.id));
+        }
+
+        let Some((id, parent)) = reparent else {
+            return;
+        };
+
+        if let Some(command) = Self::reparent(game_data, id, parent) {
+            command_history.push(command);
+        }
+    }
+
+    /// Reparents `id` keeping its world transform, `None` when nothing changed.
+    fn reparent(game_data: &mut GameData, id: usize, parent: Option<usize>) -> Option<EditorCommand> {
+        let before = game_data.scene.get_parent(id);
+        let before_transform = game_data.scene.get_local_matrix(id)?;
+
+        if before == parent || !game_data.scene.reparent(id, parent) {
+            return None;
+        }
+
+        let after_transform = game_data.scene.get_local_matrix(id).unwrap_or(before_transform);
+        Some(EditorCommand::SetParent { id, before, after: parent, before_transform, after_transform })
+    }
+
+    fn draw_hierarchy_node(&mut self, ui: &mut Ui, scene: &Scene, id: usize, additive: bool, reparent: &mut Option<(usize, Option<usize>)>) {
+        let Some((kind, _)) = scene.get_node(id) else {
+            return;
+        };
+
+        let children = scene.get_children(Some(id));
+        let name = scene.get_node_name(id);
+        let is_selected = self.selected_game_object_ids.contains(&id)
+            || self.selected_animated_object_ids.contains(&id)
+            || self.selected_light_ids.contains(&id);
+        let node_id = egui::Id::new(("hierarchy_node", id));
+
+        let mut draw_label = |ui: &mut Ui| {
+            let response = ui.dnd_drag_source(node_id, id, |ui| ui.selectable_label(is_selected, &name));
+
+            if response.inner.clicked() {
+                let selection_kind = match kind {
+                    SceneNodeKind::GameObject => SelectionKind::GameObject,
+                    SceneNodeKind::AnimatedGameObject => SelectionKind::AnimatedGameObject,
+                    SceneNodeKind::Light => SelectionKind::Light
+                };
+
+                self.select(selection_kind, id, additive);
+            }
+
+            if response.response.dnd_hover_payload::<usize>().is_some_and(|payload| *payload != id) {
+                ui.painter().rect_stroke(response.response.rect, 2.0, egui::Stroke::new(1.0, egui::Color32::WHITE), egui::StrokeKind::Outside);
+            }
+
+            if let Some(payload) = response.response.dnd_release_payload::<usize>() {
+                if *payload != id {
+                    *reparent = Some((*payload, Some(id)));
+                }
+            }
+        };
+
+        if children.is_empty() {
+            draw_label(ui);
+            return;
+        }
+
+        egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), node_id.with("open"), true)
+            .show_header(ui, draw_label)
+            .body(|ui| {
+                for child in children {
+                    self.draw_hierarchy_node(ui, scene, child, additive, reparent);
+                }
+            });
+    }
+
+    /// Saving the selection writes its objects and their children to `res/prefabs`.
+    fn draw_prefab_menu(&mut self, ui: &mut Ui, game_data: &mut GameData, command_history: &mut CommandHistory) {
+        let prefabs = list_prefabs();
+        let get_name = |path: &PathBuf| path.file_stem().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+
+        if self.selected_prefab.as_ref().is_none_or(|path| !prefabs.contains(path)) {
+            self.selected_prefab = prefabs.first().cloned();
+        }
+
+        egui::ComboBox::from_label("Prefab")
+            .selected_text(self.selected_prefab.as_ref().map_or("None".to_string(), get_name))
+            .show_ui(ui, |ui| {
+                for path in prefabs.iter() {
+                    ui.selectable_value(&mut self.selected_prefab, Some(path.clone()), get_name(path));
+                }
+            });
+
+        ui.horizontal(|ui| {
+            if ui.add_enabled(self.selected_prefab.is_some(), egui::Button::new("Place")).clicked() {
+                if let Some(path) = &self.selected_prefab {
+                    let camera = game_data.active_camera();
+                    let view = camera.calc_matrix();
+                    let forward = -cgmath::Vector3::new(view.x.z, view.y.z, view.z.z);
+                    let position = camera.position.to_vec() + forward * PREFAB_PLACE_DISTANCE;
+
+                    let create_info = PrefabInstanceCreateInfo {
+                        prefab: get_name(path),
+                        position: position.into(),
+                        rotation: [0.0; 3],
+                        scale: 1.0,
+                        overrides: Vec::new(),
+                        removed: Vec::new()
+                    };
+
+                    let index = game_data.scene.add_prefab_instance(create_info.clone(), &game_data.asset_manager);
+                    let ids = game_data.scene.prefab_instances[index].get_objects();
+                    command_history.push(EditorCommand::AddPrefabInstance { index, create_info, ids });
+                }
+            }
+
+            if ui.button("Reload").clicked() {
+                game_data.level_request = Some(LevelRequest::ReloadPrefabs);
+            }
+        });
+
+        let selected_ids: HashSet<usize> = self.selected_game_object_ids.iter()
+            .chain(self.selected_animated_object_ids.iter())
+            .chain(self.selected_light_ids.iter())
+            .copied()
+            .collect();
+
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.prefab_name).desired_width(120.0));
+
+            if ui.add_enabled(!selected_ids.is_empty() && !self.prefab_name.trim().is_empty(), egui::Button::new("Save Selection")).clicked() {
+                let name = self.prefab_name.trim().to_string();
+                let prefab = game_data.scene.get_prefab_create_info(name.clone(), &selected_ids, &game_data.asset_manager);
+                save_prefab(&prefab, &get_prefab_path(&name));
+            }
+        });
+    }
+
+    /// Level switches are queued on `game_data.level_request` and applied by the engine before the next frame.
+    fn draw_file_menu(&mut self, ui: &mut Ui, game_data: &mut GameData) {
+        if let Some(path) = &game_data.level_path {
+            if self.recent_levels.first() != Some(path) {
+                self.recent_levels.retain(|recent| recent != path);
+                self.recent_levels.insert(0, path.clone());
+                self.recent_levels.truncate(MAX_RECENT_LEVELS);
+                save_recent_levels(&self.recent_levels);
+            }
+        }
+
+        let current = game_data.level_path.as_ref().map_or("Untitled".to_string(), |path| path.display().to_string());
+        ui.label(format!("Level: {current}"));
+
+        ui.horizontal(|ui| {
+            if ui.button("New").clicked() {
+                game_data.level_request = Some(LevelRequest::New);
+            }
+
+            if let Some(path) = game_data.level_path.clone() {
+                if ui.button("Save").clicked() {
+                    self.save_level(game_data, path);
+                }
+            }
+        });
+
+        egui::ComboBox::from_label("Open")
+            .selected_text("...")
+            .show_ui(ui, |ui| {
+                for path in list_levels() {
+                    // file names, a level can be saved both as JSON and binary
+                    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+
+                    if ui.selectable_label(game_data.level_path.as_ref() == Some(&path), name).clicked() {
+                        game_data.level_request = Some(LevelRequest::Open(path));
+                    }
+                }
+            });
+
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.save_as_name).desired_width(120.0));
+
+            if ui.add_enabled(!self.save_as_name.trim().is_empty(), egui::Button::new("Save As")).clicked() {
+                let path = get_level_path(self.save_as_name.trim());
+
+                if path.exists() && game_data.level_path.as_ref() != Some(&path) {
+                    self.overwrite_path = Some(path);
+                } else {
+                    self.save_level(game_data, path);
+                }
+            }
+        });
+
+        if let Some(path) = self.overwrite_path.clone() {
+            ui.label(format!("{} already exists", path.display()));
+
+            ui.horizontal(|ui| {
+                if ui.button("Overwrite").clicked() {
+                    self.overwrite_path = None;
+                    self.save_level(game_data, path);
+                }
+
+                if ui.button("Cancel").clicked() {
+                    self.overwrite_path = None;
+                }
+            });
+        }
+
+        if let Some(error) = &self.save_error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        ui.label("Recent");
+        for path in self.recent_levels.iter() {
+            let is_current = game_data.level_path.as_ref() == Some(path);
+
+            if ui.add_enabled(!is_current, egui::Button::new(path.display().to_string())).clicked() {
+                game_data.level_request = Some(LevelRequest::Open(path.clone()));
+            }
+        }
+    }
+
+    /// Saves the level to `path` and makes it the current level, which stays unchanged when saving fails.
+    fn save_level(&mut self, game_data: &mut GameData, path: PathBuf) {
+        match save_level(game_data, &path) {
+            Ok(_) => {
+                self.save_error = None;
+                game_data.level_path = Some(path);
+            }
+            Err(e) => {
+                println!("SceneHierarchyWindow::save_level() error: could not save {}: {}!!", path.display(), e);
+                self.save_error = Some(format!("Could not save {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    /// Ids are unique across object kinds, so every kind of object shares the removal set.
+    /// Removing several objects is undone as one entry, together with moving their children up a level.
+    pub fn process_marked_for_removal(&mut self, game_data: &mut GameData, command_history: &mut CommandHistory) {
+        let mut commands = Vec::new();
+
         for id in self.objects_marked_for_removal.drain() {
-            game_data.scene.remove_game_object_by_id(id);
+            // recorded before the removal so undoing it parents the children back
+            let parent = game_data.scene.get_parent(id);
+            for child in game_data.scene.get_children(Some(id)) {
+                commands.extend(Self::reparent(game_data, child, parent));
+            }
+
+            if let Some(index) = game_data.scene.game_objects.iter().position(|game_object| game_object.id == id) {
+                let create_info = game_data.scene.game_objects[index].get_create_info(&game_data.asset_manager);
+                let parent = game_data.scene.game_objects[index].parent;
+                commands.push(EditorCommand::RemoveGameObject { id, index, parent, create_info });
+                game_data.scene.remove_game_object_by_id(id);
+            }
+
+            if let Some(index) = game_data.scene.animated_game_objects.iter().position(|animated_game_object| animated_game_object.object_id == id) {
+                let create_info = game_data.scene.animated_game_objects[index].get_create_info(&game_data.asset_manager);
+                let parent = game_data.scene.animated_game_objects[index].parent;
+                commands.push(EditorCommand::RemoveAnimatedGameObject { id, index, parent, create_info });
+                game_data.scene.remove_animated_game_object_by_id(id);
+            }
+
+            if let Some(index) = game_data.scene.lights.iter().position(|light| light.id == id) {
+                let create_info = game_data.scene.lights[index].get_create_info();
+                let parent = game_data.scene.lights[index].parent;
+                commands.push(EditorCommand::RemoveLight { id, index, parent, create_info });
+                game_data.scene.remove_light_by_id(id);
+            }
+
+            self.selected_game_object_ids.remove(&id);
+            self.selected_animated_object_ids.remove(&id);
+            self.selected_light_ids.remove(&id);
+        }
+
+        command_history.push_all(commands);
+    }
+
+    pub fn get_selected_game_object_ids(&self) -> &HashSet<usize> {
+        &self.selected_game_object_ids
+    }
+
+    pub fn get_selected_animated_object_ids(&self) -> &HashSet<usize> {
+        &self.selected_animated_object_ids
+    }
+
+    pub fn get_selected_light_ids(&self) -> &HashSet<usize> {
+        &self.selected_light_ids
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selected_game_object_ids.clear();
+        self.selected_animated_object_ids.clear();
+        self.selected_light_ids.clear();
+    }
+
+    /// Replaces the selection with `id`, or toggles `id` in it when additive (shift held).
+    fn select(&mut self, kind: SelectionKind, id: usize, additive: bool) {
+        if !additive {
+            self.clear_selection();
+        }
+
+        let selection = match kind {
+            SelectionKind::GameObject => &mut self.selected_game_object_ids,
+            SelectionKind::AnimatedGameObject => &mut self.selected_animated_object_ids,
+            SelectionKind::Light => &mut self.selected_light_ids
+        };
+
+        if !selection.remove(&id) {
+            selection.insert(id);
+        }
+
+        self.add_game_object_selected = false;
+        self.add_animated_game_object_selected = false;
+    }
+
+    /// Applies a click in the viewport, clicking empty space clears the selection unless shift is held.
+    pub fn select_picked(&mut self, game_data: &mut GameData, pick: PickResult) {
+        match pick.id {
+            Some(id) if game_data.scene.game_objects.iter().any(|game_object| game_object.id == id) => {
+                self.select(SelectionKind::GameObject, id, pick.additive);
+            }
+            Some(id) if game_data.scene.animated_game_objects.iter().any(|animated_game_object| animated_game_object.object_id == id) => {
+                self.select(SelectionKind::AnimatedGameObject, id, pick.additive);
+            }
+            Some(id) if game_data.scene.lights.iter().any(|light| light.id == id) => {
+                self.select(SelectionKind::Light, id, pick.additive);
+            }
+            _ => {
+                if !pick.additive {
+                    self.clear_selection();
+                }
+            }
+        }
+
+        for game_object in game_data.scene.game_objects.iter_mut() {
+            game_object.set_selected(self.selected_game_object_ids.contains(&game_object.id));
         }
     }
 
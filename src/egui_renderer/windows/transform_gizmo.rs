@@ -0,0 +1,536 @@
+use std::collections::HashSet;
+
+use cgmath::{EuclideanSpace, InnerSpace, Rotation3, SquareMatrix};
+use egui::{Color32, Id, LayerId, Order, Pos2, Rect, Sense, Shape, Stroke, Vec2};
+
+use crate::{common::{constants::{DEFAULT_ANGLE_SNAP, DEFAULT_GRID_SNAP, DEFAULT_SCALE_SNAP, GIZMO_PICK_RADIUS, GIZMO_SCREEN_SCALE}, enums::{GizmoMode, GizmoSpace}}, engine::GameData, objects::game_object::GameObject};
+
+const AXIS_COLORS: [Color32; 3] = [Color32::from_rgb(230, 60, 60), Color32::from_rgb(70, 200, 70), Color32::from_rgb(60, 110, 235)];
+const ACTIVE_COLOR: Color32 = Color32::from_rgb(255, 215, 40);
+const RING_SEGMENTS: usize = 48;
+
+/// `GameObject::get_model_matrix` scales its local y axis by `size.z` and z by `size.y`.
+const SIZE_COMPONENT_BY_AXIS: [usize; 3] = [0, 2, 1];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GizmoHandle {
+    Axis(usize),
+    /// Plane spanned by the two axes other than the given one.
+    Plane(usize),
+    /// Uniform scale.
+    Center
+}
+
+/// Selected object by id, looked up again every frame since an undo during the drag can remove or reorder it.
+#[derive(Clone, Copy)]
+enum GizmoTarget {
+    GameObject(usize),
+    Light(usize)
+}
+
+/// Local transform of a target when the drag started, with the parent transform it is relative to.
+struct TargetStart {
+    target: GizmoTarget,
+    position: cgmath::Vector3<f32>,
+    rotation: cgmath::Quaternion<f32>,
+    size: cgmath::Vector3<f32>,
+    parent_inverse: cgmath::Matrix4<f32>,
+    parent_rotation: cgmath::Quaternion<f32>
+}
+
+/// Handle grabbed on mouse press, every frame the targets are set from their start transforms.
+struct GizmoDrag {
+    handle: GizmoHandle,
+    pivot: cgmath::Vector3<f32>,
+    axes: [cgmath::Vector3<f32>; 3],
+    grab_point: cgmath::Vector3<f32>,
+    grab_pointer: Pos2,
+    targets: Vec<TargetStart>
+}
+
+/// Maps between world space and egui screen points, the scene covers the whole window.
+struct GizmoView {
+    view_projection: cgmath::Matrix4<f32>,
+    inverse: cgmath::Matrix4<f32>,
+    rect: Rect
+}
+
+impl GizmoView {
+    fn project(&self, point: cgmath::Vector3<f32>) -> Option<Pos2> {
+        let clip = self.view_projection * point.extend(1.0);
+        if clip.w <= 1e-4 {
+            return None;
+        }
+
+        let ndc = clip.truncate() / clip.w;
+        Some(Pos2::new(
+            self.rect.left() + (ndc.x * 0.5 + 0.5) * self.rect.width(),
+            self.rect.top() + (0.5 - ndc.y * 0.5) * self.rect.height()
+        ))
+    }
+
+    /// World space ray through the screen point, as origin and direction.
+    fn ray(&self, pointer: Pos2) -> (cgmath::Vector3<f32>, cgmath::Vector3<f32>) {
+        let x = (pointer.x - self.rect.left()) / self.rect.width() * 2.0 - 1.0;
+        let y = 1.0 - (pointer.y - self.rect.top()) / self.rect.height() * 2.0;
+
+        let unproject = |depth: f32| {
+            let point = self.inverse * cgmath::Vector4::new(x, y, depth, 1.0);
+            point.truncate() / point.w
+        };
+
+        let near = unproject(0.0);
+        (near, (unproject(1.0) - near).normalize())
+    }
+}
+
+/// Translate, rotate and scale manipulator drawn over the selection in the editor viewport.
+/// Rotation and scale apply to each selected game object around its own origin, lights can only be moved.
+pub struct TransformGizmo {
+    pub mode: GizmoMode,
+    pub space: GizmoSpace,
+    /// Holding ctrl inverts it while dragging.
+    pub snap: bool,
+    pub grid_snap: f32,
+    pub angle_snap: f32,
+    pub scale_snap: f32,
+    hovered: Option<GizmoHandle>,
+    drag: Option<GizmoDrag>,
+    open: bool
+}
+
+impl TransformGizmo {
+    pub fn new() -> Self {
+        Self {
+            mode: GizmoMode::Translate,
+            space: GizmoSpace::World,
+            snap: false,
+            grid_snap: DEFAULT_GRID_SNAP,
+            angle_snap: DEFAULT_ANGLE_SNAP,
+            scale_snap: DEFAULT_SCALE_SNAP,
+            hovered: None,
+            drag: None,
+            open: true
+        }
+    }
+
+    /// Drops the drag in progress, its targets belong to the current scene.
+    pub fn reset(&mut self) {
+        self.hovered = None;
+        self.drag = None;
+    }
+
+    pub fn draw(&mut self, ctx: &egui::Context, game_data: &mut GameData, selected_game_object_ids: &HashSet<usize>, selected_light_ids: &HashSet<usize>) {
+        self.draw_settings(ctx);
+
+        let mut targets: Vec<GizmoTarget> = game_data.scene.game_objects.iter()
+            .filter(|game_object| selected_game_object_ids.contains(&game_object.id))
+            .map(|game_object| GizmoTarget::GameObject(game_object.id))
+            .collect();
+
+        if self.mode == GizmoMode::Translate {
+            targets.extend(game_data.scene.lights.iter()
+                .filter(|light| selected_light_ids.contains(&light.id))
+                .map(|light| GizmoTarget::Light(light.id)));
+        }
+
+        let Some(&primary) = targets.first() else {
+            self.hovered = None;
+            self.drag = None;
+            return;
+        };
+
+        let view_projection = game_data.camera.get_projection().calc_matrix() * game_data.camera.calc_matrix();
+        let Some(inverse) = view_projection.invert() else {
+            return;
+        };
+        let view = GizmoView { view_projection, inverse, rect: ctx.screen_rect() };
+
+        // the targets were just collected from the scene
+        let (pivot, rotation) = match primary {
+            GizmoTarget::GameObject(id) => {
+                let model_matrix = game_data.scene.game_objects.iter().find(|game_object| game_object.id == id).unwrap().get_model_matrix();
+                (model_matrix.w.truncate(), get_matrix_rotation(&model_matrix))
+            }
+            GizmoTarget::Light(id) => {
+                let light = game_data.scene.lights.iter().find(|light| light.id == id).unwrap();
+                (light.get_world_position(), get_matrix_rotation(&game_data.scene.get_parent_matrix(id)))
+            }
+        };
+
+        let axes = if self.space == GizmoSpace::Local || self.mode == GizmoMode::Scale {
+            let matrix = cgmath::Matrix3::from(rotation);
+            [matrix.x, matrix.y, matrix.z]
+        } else {
+            [cgmath::Vector3::unit_x(), cgmath::Vector3::unit_y(), cgmath::Vector3::unit_z()]
+        };
+
+        // constant size on screen
+        let size = (game_data.camera.position.to_vec() - pivot).magnitude() * GIZMO_SCREEN_SCALE;
+
+        let (pointer, pressed, down, ctrl) = ctx.input(|input| (input.pointer.latest_pos(), input.pointer.primary_pressed(), input.pointer.primary_down(), input.modifiers.ctrl));
+        let snapping = self.snap != ctrl;
+        let area_id = Id::new("transform_gizmo");
+
+        if let Some(drag) = &self.drag {
+            match pointer.filter(|_| down) {
+                Some(pointer) => self.apply_drag(drag, &view, pointer, snapping, game_data),
+                None => self.drag = None
+            }
+        } else {
+            // windows and panels over the viewport take the pointer first
+            let viewport = ctx.available_rect();
+            self.hovered = pointer
+                .filter(|pointer| viewport.contains(*pointer))
+                .filter(|pointer| ctx.layer_id_at(*pointer).is_none_or(|layer| layer.id == area_id))
+                .and_then(|pointer| self.hit_test(&view, pivot, &axes, size, pointer));
+
+            if let (Some(handle), Some(pointer), true) = (self.hovered, pointer, pressed) {
+                let (origin, direction) = view.ray(pointer);
+                let grab_point = Self::get_drag_point(self.mode, handle, pivot, &axes, origin, direction).unwrap_or(pivot);
+
+                self.drag = Some(GizmoDrag {
+                    handle,
+                    pivot,
+                    axes,
+                    grab_point,
+                    grab_pointer: pointer,
+                    targets: targets.iter().filter_map(|&target| Self::get_target_start(game_data, target)).collect()
+                });
+            }
+        }
+
+        // keeps the viewport click from also picking the object behind the handle
+        if let (Some(pointer), true) = (pointer, self.hovered.is_some() || self.drag.is_some()) {
+            let extent = Vec2::splat(GIZMO_PICK_RADIUS * 2.0);
+
+            egui::Area::new(area_id)
+                .order(Order::Foreground)
+                .fixed_pos(pointer - extent * 0.5)
+                .show(ctx, |ui| {
+                    ui.allocate_exact_size(extent, Sense::drag());
+                });
+        }
+
+        let active = self.drag.as_ref().map(|drag| drag.handle).or(self.hovered);
+        let painter = ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("transform_gizmo_painter"))).with_clip_rect(ctx.available_rect());
+        let (pivot, axes) = self.drag.as_ref().map_or((pivot, axes), |drag| (drag.pivot, drag.axes));
+
+        self.paint(&painter, &view, pivot, &axes, size, active);
+    }
+
+    fn draw_settings(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Gizmo")
+            .open(&mut self.open)
+            .default_width(200.0)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for mode in GizmoMode::ALL {
+                        ui.selectable_value(&mut self.mode, mode, format!("{:?}", mode));
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.space, GizmoSpace::World, "World");
+                    ui.selectable_value(&mut self.space, GizmoSpace::Local, "Local");
+                });
+
+                ui.checkbox(&mut self.snap, "Snap (Ctrl)");
+
+                ui.add_enabled_ui(self.snap, |ui| {
+                    ui.label("Grid");
+                    ui.add(egui::DragValue::new(&mut self.grid_snap).speed(0.05).range(0.01..=100.0));
+
+                    ui.label("Angle");
+                    ui.add(egui::DragValue::new(&mut self.angle_snap).speed(1.0).range(1.0..=180.0).suffix("°"));
+
+                    ui.label("Scale");
+                    ui.add(egui::DragValue::new(&mut self.scale_snap).speed(0.01).range(0.01..=10.0));
+                });
+            });
+    }
+
+    fn get_target_start(game_data: &GameData, target: GizmoTarget) -> Option<TargetStart> {
+        let (id, position, rotation, size) = match target {
+            GizmoTarget::GameObject(id) => {
+                let game_object = game_data.scene.game_objects.iter().find(|game_object| game_object.id == id)?;
+                (id, game_object.get_position(), game_object.get_rotation_quaternion(), game_object.get_size())
+            }
+            GizmoTarget::Light(id) => {
+                let light = game_data.scene.lights.iter().find(|light| light.id == id)?;
+                (id, light.position, cgmath::Quaternion::from_angle_x(cgmath::Deg(0.0)), cgmath::Vector3::new(1.0, 1.0, 1.0))
+            }
+        };
+
+        let parent_matrix = game_data.scene.get_parent_matrix(id);
+
+        Some(TargetStart {
+            target,
+            position,
+            rotation,
+            size,
+            parent_inverse: parent_matrix.invert().unwrap_or(cgmath::Matrix4::identity()),
+            parent_rotation: get_matrix_rotation(&parent_matrix)
+        })
+    }
+
+    /// World point under the ray that drives the handle, on the axis line or on the handle plane.
+    fn get_drag_point(mode: GizmoMode, handle: GizmoHandle, pivot: cgmath::Vector3<f32>, axes: &[cgmath::Vector3<f32>; 3], origin: cgmath::Vector3<f32>, direction: cgmath::Vector3<f32>) -> Option<cgmath::Vector3<f32>> {
+        match (mode, handle) {
+            (GizmoMode::Rotate, GizmoHandle::Axis(axis)) | (_, GizmoHandle::Plane(axis)) => {
+                let normal = axes[axis];
+                let denominator = direction.dot(normal);
+                if denominator.abs() < 1e-4 {
+                    return None;
+                }
+
+                let distance = (pivot - origin).dot(normal) / denominator;
+                Some(origin + direction * distance)
+            }
+            (_, GizmoHandle::Axis(axis)) => {
+                // closest point on the axis line to the ray
+                let axis = axes[axis];
+                let offset = pivot - origin;
+                let cos = axis.dot(direction);
+                let denominator = 1.0 - cos * cos;
+                if denominator < 1e-4 {
+                    return None;
+                }
+
+                let along = (cos * direction.dot(offset) - axis.dot(offset)) / denominator;
+                Some(pivot + axis * along)
+            }
+            (_, GizmoHandle::Center) => None
+        }
+    }
+
+    fn apply_drag(&self, drag: &GizmoDrag, view: &GizmoView, pointer: Pos2, snapping: bool, game_data: &mut GameData) {
+        let snap = |value: f32, step: f32| if snapping && step > 0.0 { (value / step).round() * step } else { value };
+
+        let (origin, direction) = view.ray(pointer);
+        let point = Self::get_drag_point(self.mode, drag.handle, drag.pivot, &drag.axes, origin, direction);
+
+        match self.mode {
+            GizmoMode::Translate => {
+                let Some(point) = point else {
+                    return;
+                };
+
+                let delta = point - drag.grab_point;
+                let delta = drag.axes.iter().fold(cgmath::Vector3::new(0.0, 0.0, 0.0), |sum, axis| sum + axis * snap(delta.dot(*axis), self.grid_snap));
+
+                for start in drag.targets.iter() {
+                    let position = start.position + (start.parent_inverse * delta.extend(0.0)).truncate();
+
+                    match start.target {
+                        GizmoTarget::GameObject(id) => {
+                            if let Some(game_object) = game_data.scene.game_objects.iter_mut().find(|game_object| game_object.id == id) {
+                                game_object.set_position(position);
+                            }
+                        }
+                        GizmoTarget::Light(id) => {
+                            if let Some(light) = game_data.scene.lights.iter_mut().find(|light| light.id == id) {
+                                light.position = position;
+                            }
+                        }
+                    }
+                }
+            }
+            GizmoMode::Rotate => {
+                let (Some(point), GizmoHandle::Axis(axis)) = (point, drag.handle) else {
+                    return;
+                };
+
+                let normal = drag.axes[axis];
+                let (from, to) = (drag.grab_point - drag.pivot, point - drag.pivot);
+                let angle = snap(from.cross(to).dot(normal).atan2(from.dot(to)).to_degrees(), self.angle_snap);
+                let delta = cgmath::Quaternion::from_axis_angle(normal, cgmath::Deg(angle));
+
+                for start in drag.targets.iter() {
+                    if let Some(game_object) = get_target_game_object_mut(game_data, start.target) {
+                        // the world space delta, brought into the parent's space
+                        let rotation = start.parent_rotation.conjugate() * delta * start.parent_rotation * start.rotation;
+                        game_object.set_rotation_quaternion(rotation);
+                    }
+                }
+            }
+            GizmoMode::Scale => {
+                let ratio = |from: f32, to: f32| if from.abs() > 1e-4 { to / from } else { 1.0 };
+
+                let (factor, components) = match (drag.handle, point) {
+                    (GizmoHandle::Axis(axis), Some(point)) => {
+                        let factor = ratio((drag.grab_point - drag.pivot).dot(drag.axes[axis]), (point - drag.pivot).dot(drag.axes[axis]));
+                        (factor, vec![SIZE_COMPONENT_BY_AXIS[axis]])
+                    }
+                    (GizmoHandle::Plane(axis), Some(point)) => {
+                        let factor = ratio((drag.grab_point - drag.pivot).magnitude(), (point - drag.pivot).magnitude());
+                        (factor, (0..3).filter(|&other| other != axis).map(|other| SIZE_COMPONENT_BY_AXIS[other]).collect())
+                    }
+                    (GizmoHandle::Center, _) => {
+                        let Some(center) = view.project(drag.pivot) else {
+                            return;
+                        };
+
+                        (ratio(drag.grab_pointer.distance(center), pointer.distance(center)), vec![0, 1, 2])
+                    }
+                    _ => return
+                };
+
+                for start in drag.targets.iter() {
+                    if let Some(game_object) = get_target_game_object_mut(game_data, start.target) {
+                        let mut size = start.size;
+                        for &component in components.iter() {
+                            size[component] = snap(start.size[component] * factor, self.scale_snap).max(0.01);
+                        }
+
+                        game_object.set_size(size);
+                    }
+                }
+            }
+        }
+    }
+
+    fn get_ring_points(view: &GizmoView, pivot: cgmath::Vector3<f32>, axes: &[cgmath::Vector3<f32>; 3], size: f32, axis: usize) -> Vec<Pos2> {
+        let (u, v) = (axes[(axis + 1) % 3], axes[(axis + 2) % 3]);
+
+        (0..=RING_SEGMENTS)
+            .filter_map(|i| {
+                let angle = i as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                view.project(pivot + (u * angle.cos() + v * angle.sin()) * size)
+            })
+            .collect()
+    }
+
+    fn get_plane_points(view: &GizmoView, pivot: cgmath::Vector3<f32>, axes: &[cgmath::Vector3<f32>; 3], size: f32, axis: usize) -> Option<Vec<Pos2>> {
+        let (u, v) = (axes[(axis + 1) % 3] * size, axes[(axis + 2) % 3] * size);
+
+        [(0.2, 0.2), (0.45, 0.2), (0.45, 0.45), (0.2, 0.45)]
+            .iter()
+            .map(|(a, b)| view.project(pivot + u * *a + v * *b))
+            .collect()
+    }
+
+    fn hit_test(&self, view: &GizmoView, pivot: cgmath::Vector3<f32>, axes: &[cgmath::Vector3<f32>; 3], size: f32, pointer: Pos2) -> Option<GizmoHandle> {
+        let center = view.project(pivot)?;
+        let mut closest: Option<(f32, GizmoHandle)> = None;
+        let mut consider = |distance: f32, handle: GizmoHandle| {
+            if distance < GIZMO_PICK_RADIUS && closest.is_none_or(|(closest_distance, _)| distance < closest_distance) {
+                closest = Some((distance, handle));
+            }
+        };
+
+        match self.mode {
+            GizmoMode::Rotate => {
+                for axis in 0..3 {
+                    let points = Self::get_ring_points(view, pivot, axes, size, axis);
+                    let distance = points.windows(2).map(|segment| distance_to_segment(pointer, segment[0], segment[1])).fold(f32::MAX, f32::min);
+                    consider(distance, GizmoHandle::Axis(axis));
+                }
+            }
+            GizmoMode::Translate | GizmoMode::Scale => {
+                if self.mode == GizmoMode::Scale {
+                    consider(pointer.distance(center) * 0.5, GizmoHandle::Center);
+                }
+
+                for axis in 0..3 {
+                    if let Some(end) = view.project(pivot + axes[axis] * size) {
+                        consider(distance_to_segment(pointer, center, end), GizmoHandle::Axis(axis));
+                    }
+
+                    // planes sit between the axes, inside them counts as a direct hit
+                    if let Some(points) = Self::get_plane_points(view, pivot, axes, size, axis) {
+                        if is_inside_convex(pointer, &points) {
+                            consider(0.0, GizmoHandle::Plane(axis));
+                        }
+                    }
+                }
+            }
+        }
+
+        closest.map(|(_, handle)| handle)
+    }
+
+    fn paint(&self, painter: &egui::Painter, view: &GizmoView, pivot: cgmath::Vector3<f32>, axes: &[cgmath::Vector3<f32>; 3], size: f32, active: Option<GizmoHandle>) {
+        let Some(center) = view.project(pivot) else {
+            return;
+        };
+        let color = |handle: GizmoHandle, axis: usize| if active == Some(handle) { ACTIVE_COLOR } else { AXIS_COLORS[axis] };
+
+        match self.mode {
+            GizmoMode::Rotate => {
+                for axis in 0..3 {
+                    let points = Self::get_ring_points(view, pivot, axes, size, axis);
+                    painter.add(Shape::line(points, Stroke::new(2.0, color(GizmoHandle::Axis(axis), axis))));
+                }
+            }
+            GizmoMode::Translate | GizmoMode::Scale => {
+                for axis in 0..3 {
+                    if let Some(points) = Self::get_plane_points(view, pivot, axes, size, axis) {
+                        let handle = GizmoHandle::Plane(axis);
+                        let fill = color(handle, axis).gamma_multiply(if active == Some(handle) { 0.6 } else { 0.3 });
+                        painter.add(Shape::convex_polygon(points, fill, Stroke::NONE));
+                    }
+
+                    let Some(end) = view.project(pivot + axes[axis] * size) else {
+                        continue;
+                    };
+                    let stroke = Stroke::new(2.5, color(GizmoHandle::Axis(axis), axis));
+
+                    if self.mode == GizmoMode::Translate {
+                        painter.arrow(center, end - center, stroke);
+                    } else {
+                        painter.line_segment([center, end], stroke);
+                        painter.rect_filled(Rect::from_center_size(end, Vec2::splat(8.0)), 0.0, stroke.color);
+                    }
+                }
+
+                if self.mode == GizmoMode::Scale {
+                    let center_color = if active == Some(GizmoHandle::Center) { ACTIVE_COLOR } else { Color32::WHITE };
+                    painter.circle_filled(center, 5.0, center_color);
+                }
+            }
+        }
+    }
+}
+
+/// Game object a target points at, `None` for lights and objects removed since the drag started.
+fn get_target_game_object_mut(game_data: &mut GameData, target: GizmoTarget) -> Option<&mut GameObject> {
+    match target {
+        GizmoTarget::GameObject(id) => game_data.scene.game_objects.iter_mut().find(|game_object| game_object.id == id),
+        GizmoTarget::Light(_) => None
+    }
+}
+
+/// Rotation part of a transform, with the scale of each axis removed.
+fn get_matrix_rotation(matrix: &cgmath::Matrix4<f32>) -> cgmath::Quaternion<f32> {
+    let axis = |column: cgmath::Vector4<f32>, fallback: cgmath::Vector3<f32>| {
+        let column = column.truncate();
+        if column.magnitude2() > 1e-8 { column.normalize() } else { fallback }
+    };
+
+    cgmath::Quaternion::from(cgmath::Matrix3::from_cols(
+        axis(matrix.x, cgmath::Vector3::unit_x()),
+        axis(matrix.y, cgmath::Vector3::unit_y()),
+        axis(matrix.z, cgmath::Vector3::unit_z())
+    ))
+}
+
+fn distance_to_segment(point: Pos2, start: Pos2, end: Pos2) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.length_sq();
+    if length_squared <= f32::EPSILON {
+        return point.distance(start);
+    }
+
+    let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+    point.distance(start + segment * t)
+}
+
+fn is_inside_convex(point: Pos2, polygon: &[Pos2]) -> bool {
+    let sides: Vec<f32> = polygon.iter().zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| (*b - *a).x * (point - *a).y - (*b - *a).y * (point - *a).x)
+        .collect();
+
+    sides.iter().all(|side| *side >= 0.0) || sides.iter().all(|side| *side <= 0.0)
+}
@@ -1,9 +1,10 @@
-use std::sync::Arc;
+use std::{path::{Path, PathBuf}, sync::Arc};
 
+use cgmath::EuclideanSpace;
 use winit::{event::{DeviceEvent, WindowEvent}, keyboard::KeyCode, window::{CursorGrabMode, Window}};
 use yhwh_audio::audio_manager::AudioManager;
 
-use crate::{asset_manager::AssetManager, camera::{Camera, CameraController}, common::{enums::GameState}, input::{input::Input}, objects::{animated_game_object::AnimatedGameObject, game_object::GameObject}, physics::physics::Physics, player::Player, scene::Scene, utils::json::load_level, wgpu_renderer::WgpuRenderer};
+use crate::{asset_manager::AssetManager, camera::{Camera, CameraController}, common::{constants::LIGHT_ICON_SIZE, enums::{GameState, LevelRequest, LightType}, errors::LoadLevelError}, debug_draw::DebugDraw, frame_capture::save_png, input::{input::Input, yhwh_keys::YHWHMouseButton}, objects::{animated_game_object::AnimatedGameObject, game_object::GameObject}, physics::physics::Physics, player::Player, render_passes::picking_pass::PickRequest, scene::Scene, utils::json::{get_level_path, load_level}, wgpu_context::WgpuContext, wgpu_renderer::WgpuRenderer};
 
 pub struct CameraManager {
     
@@ -19,7 +20,11 @@ pub struct GameData {
     pub fps_accum: Vec<f64>,
     pub avg_fps: f64,
     pub game_state: GameState,
-    pub player: Player
+    pub player: Player,
+    pub debug_draw: DebugDraw,
+    /// File of the current level, `None` until a new level is saved.
+    pub level_path: Option<PathBuf>,
+    pub level_request: Option<LevelRequest>
 }
 
 pub struct Engine {
@@ -33,38 +38,18 @@ pub struct Engine {
 }
 
 impl Engine {
-    pub async fn new(window: Arc<Window>) -> Self {
+    /// `level` is a level name from `res/scenes` or a path to a level file.
+    pub async fn new(window: Arc<Window>, level: &str) -> Self {
         // window config
         let show_cursor = false;
         window.set_cursor_visible(show_cursor);
         let _res = window.set_cursor_grab(CursorGrabMode::Confined).or_else(|_e| window.set_cursor_grab(CursorGrabMode::Locked));
 
-        // load camera
-        let camera = Camera::new((0.0, 5.0, 10.0), cgmath::Deg(-90.0), cgmath::Deg(-20.0));
-        let camera_controller = CameraController::new(8.0, 0.4);
-
         // load physics
 
         // load resources
         let wgpu_context = WgpuRenderer::create_context(&window).await;
-        let mut asset_manager = AssetManager::new(&wgpu_context);
-        asset_manager.build_materials(&wgpu_context.device);
-    
-        // load scene
-        let scene = Scene::new(&asset_manager);
-
-        let game_data = GameData {
-            asset_manager,
-            scene,
-            camera,
-            camera_controller,
-            avg_fps: 0.0,
-            fps_accum: Default::default(),
-            delta_time: std::time::Duration::new(0, 0),
-            last_redraw: std::time::Instant::now(),
-            game_state: GameState::Playing,
-            player: Player::new()
-        };
+        let game_data = GameData::new(&wgpu_context, &get_level_path(level));
 
         // load wgpu
         let wgpu_renderer = WgpuRenderer::new(&window, wgpu_context, &game_data);
@@ -91,17 +76,31 @@ impl Engine {
         // update game
         self.game_data.update(&self.input, &mut self.audio_manager);
 
-        self.window.set_title(&format!("FPS: {:.1}", self.game_data.avg_fps));
+        if self.game_data.game_state == GameState::Editor && self.game_data.debug_draw.settings.colliders {
+            self.physics.draw_colliders(&mut self.game_data.debug_draw);
+        }
+
+        let culling_stats = self.wgpu_renderer.get_culling_stats();
+        self.window.set_title(&format!("FPS: {:.1} | Visible: {} | Culled: {}", self.game_data.avg_fps, culling_stats.visible_objects, culling_stats.culled_objects));
         self.toggle_cursor();
 
         self.handle_dev_tools();
+        self.handle_picking();
+
+        if let Some(request) = self.game_data.level_request.take() {
+            match request {
+                LevelRequest::Open(path) => self.load_level(&path),
+                LevelRequest::New => self.new_level(),
+                LevelRequest::ReloadPrefabs => self.reload_prefabs()
+            }
+        }
 
         // update wgpu renderer
         match self.wgpu_renderer.render(&self.window, &mut self.game_data) {
             Ok(_) => {},
             Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
                 let size = self.window.inner_size();
-                self.wgpu_renderer.resize_ctx(size.width, size.height);
+                self.resize(size.width, size.height);
             }
             Err(e) => {
                 println!("Engine::update() error: Unable to render {}", e);
@@ -112,6 +111,24 @@ impl Engine {
         self.input.mouse.end_frame();
     }
 
+    /// Replaces the current scene with the level at `path`, keeps the current one if it can't be loaded.
+    pub fn load_level(&mut self, path: &Path) {
+        match self.game_data.load_level(path) {
+            Ok(_) => self.wgpu_renderer.reset_level_state(&self.game_data),
+            Err(e) => println!("Engine::load_level() error: {}!!", e)
+        }
+    }
+
+    pub fn new_level(&mut self) {
+        self.game_data.new_level();
+        self.wgpu_renderer.reset_level_state(&self.game_data);
+    }
+
+    pub fn reload_prefabs(&mut self) {
+        self.game_data.reload_prefabs();
+        self.wgpu_renderer.reset_level_state(&self.game_data);
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         self.game_data.camera.get_projection_mut().resize(width, height);
         self.wgpu_renderer.resize(width, height);
@@ -121,8 +138,10 @@ impl Engine {
         //self.game_data.camera_controller.handle_keyboard(&event);
         self.input.keyboard.handle_event(&event);
         self.input.mouse.handle_window_event(&event);
-        self.wgpu_renderer.egui_renderer.handle_input(&self.window, &event);
-        self.wgpu_renderer.egui_renderer.set_cursor_visible(self.show_cursor);
+        if let Some(egui_renderer) = &mut self.wgpu_renderer.egui_renderer {
+          egui_renderer.handle_input(&self.window, &event);
+          egui_renderer.set_cursor_visible(self.show_cursor);
+        }
     }
 
     pub fn handle_device_events(&mut self, event: &DeviceEvent) {
@@ -152,32 +171,136 @@ impl Engine {
         }
     }
 
+    /// Left click in the viewport selects the object under the cursor, shift adds to the selection.
+    pub fn handle_picking(&mut self) {
+        if self.game_data.game_state != GameState::Editor || !self.input.mouse.button_just_pressed(&YHWHMouseButton::Left) {
+            return;
+        }
+
+        let over_ui = self.wgpu_renderer.egui_renderer.as_ref().is_some_and(|egui_renderer| egui_renderer.context.is_pointer_over_area());
+        if over_ui {
+            return;
+        }
+
+        let (x, y) = self.input.mouse.position;
+        let additive = self.input.keyboard.key_pressed(KeyCode::ShiftLeft) || self.input.keyboard.key_pressed(KeyCode::ShiftRight);
+
+        self.wgpu_renderer.request_pick(PickRequest { x, y, additive });
+    }
+
     pub fn handle_dev_tools(&mut self) {
         if self.input.keyboard.key_just_pressed(KeyCode::Digit2) {
           self.wgpu_renderer.hot_load_shaders();
         }
 
-        if let Some(glb_model) = self.game_data.asset_manager.get_model_by_name_mut("glock") {
-            let anim_len = glb_model.animations.as_ref().unwrap().animations().len();
-            if self.input.keyboard.key_just_pressed(KeyCode::KeyR) {
-                let play_back_state = glb_model.get_animation_playback_state().unwrap();
-                let mut current_anim = play_back_state.current;
+        if self.input.keyboard.key_just_pressed(KeyCode::F12) {
+          let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or_default();
+          self.wgpu_renderer.request_screenshot(PathBuf::from(format!("screenshots/screenshot_{}.png", timestamp)));
+        }
+
+        // cycles the clips of the animated objects
+        if self.input.keyboard.key_just_pressed(KeyCode::KeyR) {
+            for animated_game_object in self.game_data.scene.animated_game_objects.iter_mut() {
+                let Some(model) = self.game_data.asset_manager.get_model_by_name(animated_game_object.get_model_name()) else {
+                    continue;
+                };
 
-                if current_anim + 1 < anim_len {
-                  current_anim += 1;
-                } else {
-                  current_anim = 0;
+                let animation_names = model.get_animation_names();
+                if animation_names.is_empty() {
+                    continue;
                 }
 
-                glb_model.set_current_animation(current_anim);
+                let current = animated_game_object.animation.as_ref()
+                    .and_then(|name| animation_names.iter().position(|animation_name| animation_name == name))
+                    .unwrap_or(0);
+
+                animated_game_object.animation = Some(animation_names[(current + 1) % animation_names.len()].clone());
             }
         }
     }
 }
 
+/// Renders the first frame of a level without a window and saves it as a PNG, so a level can be
+/// compared against a reference image.
+pub fn render_level(level_path: &Path, output: &Path, width: u32, height: u32) -> Result<(), String> {
+    let context = pollster::block_on(WgpuContext::new_headless(width, height)).map_err(|e| format!("{:?}", e))?;
+
+    let mut game_data = GameData::new(&context, level_path);
+    // an empty level would render without complaint
+    if game_data.level_path.is_none() {
+        return Err(format!("couldn't load {}", level_path.display()));
+    }
+
+    let mut renderer = WgpuRenderer::new_headless(context, &game_data);
+    let image = renderer.render_headless(&mut game_data).map_err(|e| format!("{:?}", e))?;
+
+    save_png(&image, output).map_err(|e| format!("{:?}", e))
+}
+
 impl GameData {
+    /// Loads the assets and the level, also used by headless renderers.
+    /// Starts with an empty level when the level at `level_path` can't be loaded.
+    pub fn new(ctx: &WgpuContext, level_path: &Path) -> Self {
+        // load camera
+        let camera = Camera::new((0.0, 5.0, 10.0), cgmath::Deg(-90.0), cgmath::Deg(-20.0));
+        let camera_controller = CameraController::new(8.0, 0.4);
+
+        let mut asset_manager = AssetManager::new(ctx);
+        asset_manager.build_materials(&ctx.device);
+
+        // load scene
+        let (scene, level_path) = match load_level(level_path, &asset_manager) {
+            Ok(level) => (Scene::new(level, &asset_manager), Some(level_path.to_path_buf())),
+            Err(e) => {
+                println!("GameData::new() error: {}!!", e);
+                (Scene::new_empty(&asset_manager), None)
+            }
+        };
+
+        Self {
+            asset_manager,
+            scene,
+            camera,
+            camera_controller,
+            avg_fps: 0.0,
+            fps_accum: Default::default(),
+            delta_time: std::time::Duration::new(0, 0),
+            last_redraw: std::time::Instant::now(),
+            game_state: GameState::Playing,
+            player: Player::new(),
+            debug_draw: DebugDraw::new(),
+            level_path,
+            level_request: None
+        }
+    }
+
+    /// Unloads the current scene and loads the level at `path` in its place.
+    pub fn load_level(&mut self, path: &Path) -> Result<(), LoadLevelError> {
+        let level = load_level(path, &self.asset_manager)?;
+
+        self.scene = Scene::new(level, &self.asset_manager);
+        self.level_path = Some(path.to_path_buf());
+        self.debug_draw.clear();
+
+        Ok(())
+    }
+
+    pub fn new_level(&mut self) {
+        self.scene = Scene::new_empty(&self.asset_manager);
+        self.level_path = None;
+        self.debug_draw.clear();
+    }
+
+    /// Respawns the scene from its own level data with the prefab files read again, unsaved edits are kept.
+    pub fn reload_prefabs(&mut self) {
+        let level = self.scene.get_level_create_info(String::new(), &self.asset_manager);
+        self.scene = Scene::new(level, &self.asset_manager);
+    }
+
     pub fn update(&mut self, input: &Input, audio_manager: &mut AudioManager) {
         self.update_fps();
+        self.scene.update_transforms();
+        self.debug_draw.update(self.delta_time.as_secs_f32());
     
         match self.game_state {
             GameState::Playing => {
@@ -186,6 +309,7 @@ impl GameData {
             GameState::Editor => {
                 self.camera_controller.update_movement_editor(&input);
                 self.camera_controller.update_camera(&mut self.camera, self.delta_time);
+                self.draw_debug_overlays();
             }
         }
         // if self.game_state == GameState::Playing {
@@ -224,6 +348,73 @@ impl GameData {
         self.avg_fps = self.fps_accum.iter().sum::<f64>() / self.fps_accum.len() as f64;
     }
 
+    /// Editor visualizations enabled in the render settings.
+    fn draw_debug_overlays(&mut self) {
+        let settings = &self.debug_draw.settings;
+        let (mesh_aabbs, light_radii, camera_frusta) = (settings.mesh_aabbs, settings.light_radii, settings.camera_frusta);
+
+        if mesh_aabbs {
+            let color = cgmath::Vector3::new(1.0, 0.8, 0.1);
+
+            for game_object in self.scene.game_objects.iter() {
+                let Some(model) = self.asset_manager.get_model_by_name(game_object.get_model_name()) else {
+                    continue;
+                };
+
+                let model_matrix = game_object.get_model_matrix();
+                for mesh in model.meshes.iter() {
+                    self.debug_draw.aabb(&mesh.aabb.transform(&model_matrix), color);
+                }
+            }
+
+            for animated_game_object in self.scene.animated_game_objects.iter() {
+                let Some(model) = self.asset_manager.get_model_by_name(animated_game_object.get_model_name()) else {
+                    continue;
+                };
+
+                let model_matrix = animated_game_object.get_model_matrix();
+                for mesh in model.meshes.iter() {
+                    self.debug_draw.aabb(&mesh.aabb.transform(&model_matrix), color);
+                }
+            }
+        }
+
+        // the icons lights are picked by
+        for light in self.scene.lights.iter() {
+            let position = light.get_world_position();
+            self.debug_draw.circle(position, self.camera.position.to_vec() - position, LIGHT_ICON_SIZE, light.color).on_top();
+        }
+
+        if light_radii {
+            for light in self.scene.lights.iter() {
+                let position = light.get_world_position();
+
+                match light.light_type {
+                    LightType::Point => {
+                        self.debug_draw.sphere(position, light.radius, light.color);
+                    }
+                    LightType::Spot => {
+                        let direction = light.get_direction();
+                        let end = position + direction * light.radius;
+                        let cone_radius = light.radius * light.outer_cone_angle.to_radians().tan();
+
+                        self.debug_draw.arrow(position, end, light.color);
+                        self.debug_draw.circle(end, direction, cone_radius, light.color);
+                    }
+                    LightType::Directional => {
+                        self.debug_draw.arrow(position, position + light.get_direction() * 2.0, light.color);
+                    }
+                }
+            }
+        }
+
+        if camera_frusta {
+            let camera = &self.player.camera;
+            let view_projection = camera.get_projection().calc_matrix() * camera.calc_matrix();
+            self.debug_draw.frustum(&view_projection, cgmath::Vector3::new(1.0, 1.0, 1.0));
+        }
+    }
+
     pub fn active_camera(&self) -> &Camera {
         match self.game_state {
             GameState::Playing => &self.player.camera,
@@ -0,0 +1,339 @@
+use crate::{asset_manager::AssetManager, bind_group_manager::{BindGroupManager, TL}, common::constants::{BRDF_LUT_FORMAT, BRDF_LUT_SIZE, ENVIRONMENT_MAP_FORMAT, ENVIRONMENT_MAP_SIZE, IRRADIANCE_MAP_SIZE, PREFILTER_MAP_SIZE, PREFILTER_MIP_LEVELS}, cube_map::CubeMap, pipeline_builder::PipelineBuilder, texture::{Texture, TextureHelpers}, wgpu_context::WgpuContext};
+
+const ENVIRONMENTS_PATH: &str = "res/environments/";
+
+/// Image based lighting data baked from an equirectangular HDR,
+/// or from the default sky cube map when the level has no environment.
+pub struct EnvironmentMap {
+    pub name: Option<String>,
+    pub skybox_bind_group_layout: wgpu::BindGroupLayout,
+    pub skybox_bind_group: wgpu::BindGroup,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+    equirect_bind_group_layout: wgpu::BindGroupLayout,
+    equirect_pipeline: wgpu::RenderPipeline,
+    irradiance_pipeline: wgpu::RenderPipeline,
+    prefilter_pipeline: wgpu::RenderPipeline,
+    environment: Texture,
+    irradiance: Texture,
+    prefiltered: Texture,
+    brdf_lut: Texture
+}
+
+impl EnvironmentMap {
+    pub fn new(ctx: &WgpuContext, asset_manager: &AssetManager, name: Option<&str>) -> Self {
+        let skybox_bind_group_layout = BindGroupManager::create_texture_bind_group_layout(&ctx.device, [TL::Cube]).unwrap();
+        let bind_group_layout = BindGroupManager::create_texture_bind_group_layout(&ctx.device, [TL::Cube, TL::Cube, TL::Float]).unwrap();
+
+        let equirect_bind_group_layout = ctx.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                },
+                count: None,
+            }],
+            label: Some("Equirect_Bind_Group_Layout"),
+        });
+
+        let equirect_pipeline = Self::create_pipeline(ctx, "equirect to cube pipeline", "res/shaders/equirect_to_cube.wgsl", &[&equirect_bind_group_layout], ENVIRONMENT_MAP_FORMAT);
+        let irradiance_pipeline = Self::create_pipeline(ctx, "irradiance pipeline", "res/shaders/irradiance.wgsl", &[&skybox_bind_group_layout], ENVIRONMENT_MAP_FORMAT);
+        let prefilter_pipeline = Self::create_pipeline(ctx, "prefilter pipeline", "res/shaders/prefilter.wgsl", &[&skybox_bind_group_layout], ENVIRONMENT_MAP_FORMAT);
+
+        // the lut doesn't depend on the environment so it is only baked once
+        let brdf_lut = Texture::create_fbo(&ctx.device, (BRDF_LUT_SIZE, BRDF_LUT_SIZE), BRDF_LUT_FORMAT, wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING, 1);
+        let brdf_lut = Texture {
+            sampler: create_linear_sampler(&ctx.device),
+            ..brdf_lut
+        };
+        Self::bake_brdf_lut(ctx, &brdf_lut);
+
+        let (environment, skybox_bind_group) = Self::load_environment(ctx, asset_manager, name, &equirect_bind_group_layout, &equirect_pipeline, &skybox_bind_group_layout);
+        let irradiance = create_cube_texture(&ctx.device, IRRADIANCE_MAP_SIZE, 1, "Irradiance_Map");
+        let prefiltered = create_cube_texture(&ctx.device, PREFILTER_MAP_SIZE, PREFILTER_MIP_LEVELS, "Prefiltered_Map");
+
+        Self::bake_convolutions(ctx, &irradiance_pipeline, &prefilter_pipeline, &skybox_bind_group, &irradiance, &prefiltered);
+
+        let bind_group = BindGroupManager::create_multi_texture_bind_group(&ctx.device, &bind_group_layout, &[&irradiance, &prefiltered, &brdf_lut]).unwrap();
+
+        Self {
+            name: name.map(|name| name.to_string()),
+            skybox_bind_group_layout,
+            skybox_bind_group,
+            bind_group_layout,
+            bind_group,
+            equirect_bind_group_layout,
+            equirect_pipeline,
+            irradiance_pipeline,
+            prefilter_pipeline,
+            environment,
+            irradiance,
+            prefiltered,
+            brdf_lut
+        }
+    }
+
+    /// Swaps the environment at runtime. The bind group layouts stay the same
+    /// so pipelines built against them keep working.
+    pub fn load(&mut self, ctx: &WgpuContext, asset_manager: &AssetManager, name: Option<&str>) {
+        let (environment, skybox_bind_group) = Self::load_environment(ctx, asset_manager, name, &self.equirect_bind_group_layout, &self.equirect_pipeline, &self.skybox_bind_group_layout);
+
+        Self::bake_convolutions(ctx, &self.irradiance_pipeline, &self.prefilter_pipeline, &skybox_bind_group, &self.irradiance, &self.prefiltered);
+
+        self.environment = environment;
+        self.skybox_bind_group = skybox_bind_group;
+        self.bind_group = BindGroupManager::create_multi_texture_bind_group(&ctx.device, &self.bind_group_layout, &[&self.irradiance, &self.prefiltered, &self.brdf_lut]).unwrap();
+        self.name = name.map(|name| name.to_string());
+    }
+
+    fn create_pipeline(ctx: &WgpuContext, label: &'static str, path: &str, layouts: &[&wgpu::BindGroupLayout], format: wgpu::TextureFormat) -> wgpu::RenderPipeline {
+        let shader_code = std::fs::read_to_string(path).unwrap();
+        let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(shader_code.into()),
+        });
+
+        PipelineBuilder::new(
+            label,
+            layouts,
+            &[],
+            &shader_module,
+            [format],
+        )
+        .with_blend(wgpu::BlendState::REPLACE)
+        .build(&ctx.device)
+    }
+
+    /// Returns the environment cube and its skybox bind group, falling back to the sky textures
+    /// when there is no environment or the HDR could not be loaded.
+    fn load_environment(ctx: &WgpuContext, asset_manager: &AssetManager, name: Option<&str>, equirect_bind_group_layout: &wgpu::BindGroupLayout, equirect_pipeline: &wgpu::RenderPipeline, skybox_bind_group_layout: &wgpu::BindGroupLayout) -> (Texture, wgpu::BindGroup) {
+        let environment = match name {
+            Some(name) => match Self::convert_equirect(ctx, name, equirect_bind_group_layout, equirect_pipeline) {
+                Ok(environment) => environment,
+                Err(err) => {
+                    println!("EnvironmentMap::load_environment() error: could not load environment {name}: {err}");
+                    Self::create_sky_cube(ctx, asset_manager)
+                }
+            },
+            None => Self::create_sky_cube(ctx, asset_manager)
+        };
+
+        let bind_group = BindGroupManager::create_texture_bind_group(&ctx.device, skybox_bind_group_layout, &environment).unwrap();
+
+        (environment, bind_group)
+    }
+
+    fn create_sky_cube(ctx: &WgpuContext, asset_manager: &AssetManager) -> Texture {
+        let flipped_right = asset_manager.get_texture_by_name("SkyRight.jpg").unwrap().flip_horizontal();
+        let cubemap = CubeMap::new(&ctx.device, &ctx.queue, asset_manager.get_texture_by_name("SkyRight.jpg").unwrap().dimensions, [
+            &flipped_right.pixel_data,
+            &asset_manager.get_texture_by_name("SkyLeft.jpg").unwrap().pixel_data,
+            &asset_manager.get_texture_by_name("SkyTop.jpg").unwrap().pixel_data,
+            &asset_manager.get_texture_by_name("SkyBottom.jpg").unwrap().pixel_data,
+            &asset_manager.get_texture_by_name("SkyFront.jpg").unwrap().pixel_data,
+            &asset_manager.get_texture_by_name("SkyBack.jpg").unwrap().pixel_data,
+        ]);
+
+        Texture {
+            texture: cubemap.texture,
+            view: cubemap.view,
+            sampler: cubemap.sampler,
+            dimensions: Default::default(),
+            pixel_data: Default::default()
+        }
+    }
+
+    fn convert_equirect(ctx: &WgpuContext, name: &str, equirect_bind_group_layout: &wgpu::BindGroupLayout, equirect_pipeline: &wgpu::RenderPipeline) -> anyhow::Result<Texture> {
+        let image = image::open(String::from(ENVIRONMENTS_PATH) + name)?.to_rgba32f();
+        let (width, height) = image.dimensions();
+
+        let equirect_texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            label: Some("Equirect_Texture"),
+            view_formats: &[],
+        });
+
+        ctx.queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &equirect_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(image.as_raw()),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(16 * width),
+                rows_per_image: Some(height),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        let equirect_view = equirect_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: equirect_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&equirect_view),
+            }],
+            label: Some("Equirect_Bind_Group"),
+        });
+
+        let environment = create_cube_texture(&ctx.device, ENVIRONMENT_MAP_SIZE, 1, "Environment_Map");
+
+        let mut encoder = ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Equirect_Encoder"),
+        });
+
+        for face in 0..6 {
+            let face_view = create_face_view(&environment, face, 0);
+            let mut render_pass = begin_face_pass(&mut encoder, &face_view, "Equirect_Pass");
+
+            render_pass.set_pipeline(equirect_pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, face..face + 1);
+        }
+
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+
+        Ok(environment)
+    }
+
+    fn bake_convolutions(ctx: &WgpuContext, irradiance_pipeline: &wgpu::RenderPipeline, prefilter_pipeline: &wgpu::RenderPipeline, environment_bind_group: &wgpu::BindGroup, irradiance: &Texture, prefiltered: &Texture) {
+        let mut encoder = ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Environment_Convolution_Encoder"),
+        });
+
+        for face in 0..6 {
+            let face_view = create_face_view(irradiance, face, 0);
+            let mut render_pass = begin_face_pass(&mut encoder, &face_view, "Irradiance_Pass");
+
+            render_pass.set_pipeline(irradiance_pipeline);
+            render_pass.set_bind_group(0, environment_bind_group, &[]);
+            render_pass.draw(0..3, face..face + 1);
+        }
+
+        // one roughness level per mip, the instance index encodes mip * 6 + face
+        for mip in 0..PREFILTER_MIP_LEVELS {
+            for face in 0..6 {
+                let face_view = create_face_view(prefiltered, face, mip);
+                let mut render_pass = begin_face_pass(&mut encoder, &face_view, "Prefilter_Pass");
+
+                let instance = mip * 6 + face;
+                render_pass.set_pipeline(prefilter_pipeline);
+                render_pass.set_bind_group(0, environment_bind_group, &[]);
+                render_pass.draw(0..3, instance..instance + 1);
+            }
+        }
+
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    fn bake_brdf_lut(ctx: &WgpuContext, brdf_lut: &Texture) {
+        let pipeline = Self::create_pipeline(ctx, "brdf lut pipeline", "res/shaders/brdf_lut.wgsl", &[], BRDF_LUT_FORMAT);
+
+        let mut encoder = ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Brdf_Lut_Encoder"),
+        });
+
+        {
+            let mut render_pass = begin_face_pass(&mut encoder, &brdf_lut.view, "Brdf_Lut_Pass");
+
+            render_pass.set_pipeline(&pipeline);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        ctx.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    pub fn get_environment_texture(&self) -> &Texture {
+        &self.environment
+    }
+}
+
+fn create_linear_sampler(device: &wgpu::Device) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    })
+}
+
+fn create_cube_texture(device: &wgpu::Device, size: u32, mip_level_count: u32, label: &str) -> Texture {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 6,
+        },
+        mip_level_count,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: ENVIRONMENT_MAP_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        label: Some(label),
+        view_formats: &[],
+    });
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::Cube),
+        array_layer_count: Some(6),
+        ..Default::default()
+    });
+
+    Texture {
+        texture,
+        view,
+        sampler: create_linear_sampler(device),
+        dimensions: (size, size),
+        pixel_data: Default::default()
+    }
+}
+
+fn create_face_view(cube: &Texture, face: u32, mip: u32) -> wgpu::TextureView {
+    cube.texture.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::D2),
+        base_array_layer: face,
+        array_layer_count: Some(1),
+        base_mip_level: mip,
+        mip_level_count: Some(1),
+        ..Default::default()
+    })
+}
+
+fn begin_face_pass<'a>(encoder: &'a mut wgpu::CommandEncoder, view: &'a wgpu::TextureView, label: &'static str) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    })
+}
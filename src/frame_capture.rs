@@ -0,0 +1,116 @@
+use std::path::Path;
+
+use crate::{common::errors::FrameCaptureError, wgpu_context::WgpuContext};
+
+/// Copy of a rendered frame waiting in a mappable buffer. Record it before the frame is submitted,
+/// read it back after.
+pub struct FrameCapture {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    swap_red_blue: bool
+}
+
+impl FrameCapture {
+    pub fn record(device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) -> Result<Self, FrameCaptureError> {
+        if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            return Err(FrameCaptureError::MissingCopySource);
+        }
+
+        let swap_red_blue = match texture.format() {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => return Err(FrameCaptureError::UnsupportedFormat(format))
+        };
+
+        let width = texture.width();
+        let height = texture.height();
+
+        // rows in a texture to buffer copy have to be aligned to 256 bytes
+        let unpadded_bytes_per_row = width * 4;
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(alignment) * alignment;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame_Capture_Buffer"),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+
+        Ok(Self {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            swap_red_blue
+        })
+    }
+
+    /// Blocks until the copy is done and returns the frame as RGBA8.
+    pub fn read(self, ctx: &WgpuContext) -> Result<image::RgbaImage, FrameCaptureError> {
+        let slice = self.buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = ctx.device.poll(wgpu::Maintain::Wait);
+
+        receiver.recv()
+            .unwrap_or(Err(wgpu::BufferAsyncError))
+            .map_err(FrameCaptureError::MapFailed)?;
+
+        let row_bytes = (self.width * 4) as usize;
+        let mut pixels: Vec<u8> = Vec::with_capacity(row_bytes * self.height as usize);
+
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(self.padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..row_bytes]);
+            }
+        }
+
+        self.buffer.unmap();
+
+        if self.swap_red_blue {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        // the swapchain isn't guaranteed to have an opaque alpha channel
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel[3] = 255;
+        }
+
+        Ok(image::RgbaImage::from_raw(self.width, self.height, pixels).expect("FrameCapture::read() error: buffer size mismatch!!"))
+    }
+}
+
+pub fn save_png(image: &image::RgbaImage, path: &Path) -> Result<(), FrameCaptureError> {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+
+    image.save_with_format(path, image::ImageFormat::Png).map_err(FrameCaptureError::Image)
+}
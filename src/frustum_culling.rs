@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use yhwh_core::math::{aabb::Aabb, frustum::Frustum};
+
+use crate::{asset_manager::AssetManager, camera::Camera, model::Model, scene::Scene};
+
+// skinned meshes can leave their bind pose bounds, so their boxes get some extra room
+const ANIMATED_BOUNDS_PADDING: f32 = 1.5;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CullingStats {
+    pub visible_objects: usize,
+    pub culled_objects: usize,
+    pub visible_meshes: usize,
+    pub culled_meshes: usize,
+}
+
+pub struct FrustumCuller {
+    pub enabled: bool,
+    visible_meshes: HashMap<usize, Vec<bool>>,
+    stats: CullingStats,
+}
+
+impl FrustumCuller {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            visible_meshes: HashMap::new(),
+            stats: CullingStats::default(),
+        }
+    }
+
+    pub fn update(&mut self, camera: &Camera, scene: &Scene, asset_manager: &AssetManager) {
+        let frustum = Frustum::from_matrix(camera.get_projection().calc_matrix() * camera.calc_matrix());
+
+        self.visible_meshes.clear();
+        self.stats = CullingStats::default();
+
+        for game_object in scene.game_objects.iter() {
+            if let Some(model) = asset_manager.get_model_by_name(game_object.get_model_name()) {
+                self.cull_object(&frustum, game_object.id, model, &game_object.get_model_matrix(), 1.0);
+            }
+        }
+
+        for animated_game_object in scene.animated_game_objects.iter() {
+            if let Some(model) = asset_manager.get_model_by_name(animated_game_object.get_model_name()) {
+                self.cull_object(&frustum, animated_game_object.object_id, model, &animated_game_object.get_model_matrix(), ANIMATED_BOUNDS_PADDING);
+            }
+        }
+    }
+
+    fn cull_object(&mut self, frustum: &Frustum<f32>, id: usize, model: &Model, model_matrix: &cgmath::Matrix4<f32>, padding: f32) {
+        let world_aabbs: Vec<Aabb<f32>> = model.meshes.iter()
+            .map(|mesh| pad_aabb(mesh.aabb.transform(model_matrix), padding))
+            .collect();
+
+        let object_visible = !self.enabled || Aabb::union(&world_aabbs).map_or(true, |aabb| frustum.intersects_aabb(&aabb));
+
+        let mesh_visibility: Vec<bool> = world_aabbs.iter()
+            .map(|aabb| object_visible && (!self.enabled || frustum.intersects_aabb(aabb)))
+            .collect();
+
+        let visible_meshes = mesh_visibility.iter().filter(|visible| **visible).count();
+
+        if object_visible {
+            self.stats.visible_objects += 1;
+        } else {
+            self.stats.culled_objects += 1;
+        }
+        self.stats.visible_meshes += visible_meshes;
+        self.stats.culled_meshes += mesh_visibility.len() - visible_meshes;
+
+        self.visible_meshes.insert(id, mesh_visibility);
+    }
+
+    /// Objects that were never culled (added after the last update) count as visible.
+    pub fn is_object_visible(&self, id: usize) -> bool {
+        self.visible_meshes.get(&id).map_or(true, |meshes| meshes.iter().any(|visible| *visible))
+    }
+
+    pub fn is_mesh_visible(&self, id: usize, mesh_index: usize) -> bool {
+        self.visible_meshes.get(&id).and_then(|meshes| meshes.get(mesh_index).copied()).unwrap_or(true)
+    }
+
+    pub fn get_stats(&self) -> &CullingStats {
+        &self.stats
+    }
+}
+
+fn pad_aabb(aabb: Aabb<f32>, padding: f32) -> Aabb<f32> {
+    if padding == 1.0 {
+        return aabb;
+    }
+
+    let center = aabb.get_center();
+    let half_extent = (aabb.max() - aabb.min()) * (padding * 0.5);
+
+    Aabb::new(center - half_extent, center + half_extent)
+}
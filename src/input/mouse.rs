@@ -5,6 +5,8 @@ use crate::input::yhwh_keys::YHWHMouseButton;
 pub struct Mouse {
     pub delta_x: f64,
     pub delta_y: f64,
+    /// Cursor position in window pixels.
+    pub position: (f64, f64),
     scroll_dx: f32,
     scroll_dy: f32,
     buttons: HashMap<YHWHMouseButton, bool>,
@@ -16,6 +18,7 @@ impl Mouse {
         Self {
             delta_x: 0.0,
             delta_y: 0.0,
+            position: (0.0, 0.0),
             scroll_dx: 0.0,
             scroll_dy: 0.0,
             buttons: HashMap::new(),
@@ -33,6 +36,9 @@ impl Mouse {
                 self.buttons.insert(mapped_button.clone(), is_pressed);
                 self.buttons_changed.insert(mapped_button, prev_state != is_pressed);
             }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.position = (position.x, position.y);
+            }
             _ => {}
         }
     }
@@ -1,5 +1,7 @@
 use cgmath::prelude::*;
 
+use crate::{common::enums::AlphaMode, wgpu_context::WgpuContext};
+
 const NUM_INSTANCES_PER_ROW: u32 = 10;
 const INSTANCE_DISPLACEMENT: cgmath::Vector3<f32> = cgmath::Vector3::new(NUM_INSTANCES_PER_ROW as f32 * 0.5, 0.0, NUM_INSTANCES_PER_ROW as f32 * 0.5);
 
@@ -12,53 +14,103 @@ pub struct Instance {
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct InstanceUniform {
    pub model: [[f32; 4]; 4],
+   pub normal: [[f32; 4]; 3],
+   pub tex_scale: [f32; 2],
+   /// Cutoff below which fragments are discarded and whether the alpha is written out.
+   pub alpha: [f32; 2]
 }
 
 impl InstanceUniform {
+   pub fn new(matrix: &cgmath::Matrix4<f32>, tex_scale: &cgmath::Vector2<f32>) -> Self {
+        let upper3x3 = cgmath::Matrix3::from_cols(
+            matrix.x.truncate(),
+            matrix.y.truncate(),
+            matrix.z.truncate(),
+        );
+        let normal = upper3x3.invert().map(|n| n.transpose()).unwrap_or(cgmath::Matrix3::identity());
+
+        Self {
+            model: (*matrix).into(),
+            normal: [
+                [normal.x.x, normal.x.y, normal.x.z, 0.0],
+                [normal.y.x, normal.y.y, normal.y.z, 0.0],
+                [normal.z.x, normal.z.y, normal.z.z, 0.0],
+            ],
+            tex_scale: (*tex_scale).into(),
+            alpha: [0.0, 0.0]
+        }
+   }
+
+   pub fn with_alpha_mode(mut self, alpha_mode: AlphaMode, alpha_cutoff: f32) -> Self {
+        self.alpha = match alpha_mode {
+            AlphaMode::Opaque => [0.0, 0.0],
+            AlphaMode::Mask => [alpha_cutoff, 0.0],
+            AlphaMode::Blend => [0.0, 1.0]
+        };
+        self
+   }
+
    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
-        use std::mem;
+        const ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+            // model matrix
+            7 => Float32x4,
+            8 => Float32x4,
+            9 => Float32x4,
+            10 => Float32x4,
+            // normal matrix
+            11 => Float32x4,
+            12 => Float32x4,
+            13 => Float32x4,
+            // tex scale + alpha cutoff + alpha blend
+            14 => Float32x4
+        ];
+
         wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<InstanceUniform>() as wgpu::BufferAddress,
-            // We need to switch from using a step mode of Vertex to Instance
-            // This means that our shaders will only change to use the next
-            // instance when the shader starts processing a new instance
+            array_stride: std::mem::size_of::<InstanceUniform>() as wgpu::BufferAddress,
             step_mode: wgpu::VertexStepMode::Instance,
-            attributes: &[
-                // A mat4 takes up 4 vertex slots as it is technically 4 vec4s. We need to define a slot
-                // for each vec4. We'll have to reassemble the mat4 in the shader.
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    // While our vertex shader only uses locations 0, and 1 now, in later tutorials, we'll
-                    // be using 2, 3, and 4, for Vertex. We'll start at slot 5, not conflict with them later
-                    shader_location: 7,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
-                    shader_location: 8,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
-                    shader_location: 9,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-                wgpu::VertexAttribute {
-                    offset: mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
-                    shader_location: 10,
-                    format: wgpu::VertexFormat::Float32x4,
-                },
-            ],
+            attributes: &ATTRIBUTES,
         }
     }
 }
 
+/// Growable per-frame vertex buffer holding the instance data of every batch.
+pub struct InstanceBuffer {
+    pub buffer: wgpu::Buffer,
+    capacity: usize
+}
+
+impl InstanceBuffer {
+    pub fn new(device: &wgpu::Device, capacity: usize) -> Self {
+        Self {
+            buffer: Self::create_buffer(device, capacity.max(1)),
+            capacity: capacity.max(1)
+        }
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance_Buffer"),
+            size: (capacity * std::mem::size_of::<InstanceUniform>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    pub fn update(&mut self, ctx: &WgpuContext, instances: &[InstanceUniform]) {
+        if instances.len() > self.capacity {
+            self.capacity = instances.len().next_power_of_two();
+            self.buffer = Self::create_buffer(&ctx.device, self.capacity);
+        }
+
+        if !instances.is_empty() {
+            ctx.queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(instances));
+        }
+    }
+}
 
 impl Instance {
    pub fn to_raw(&self) -> InstanceUniform {
-        InstanceUniform {
-            model: (cgmath::Matrix4::from_translation(self.position) * cgmath::Matrix4::from(self.rotation)).into(),
-        }
+        InstanceUniform::new(&(cgmath::Matrix4::from_translation(self.position) * cgmath::Matrix4::from(self.rotation)), &cgmath::Vector2::new(1.0, 1.0))
     }
 
     pub fn get_instances() -> Vec<Instance> {
@@ -27,4 +27,13 @@ pub mod player;
 pub mod scene;
 pub mod ssbo;
 pub mod u8slice;
-pub mod pipeline_builder;
\ No newline at end of file
+pub mod pipeline_builder;
+pub mod frustum_culling;
+pub mod dynamic_uniform;
+pub mod environment_map;
+pub mod render_graph;
+pub mod frame_capture;
+pub mod color_lut;
+pub mod debug_draw;
+pub mod command_history;
+pub mod prefab;
\ No newline at end of file
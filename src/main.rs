@@ -1,16 +1,18 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use winit::{application::ApplicationHandler, error::EventLoopError, event::{DeviceEvent, WindowEvent}, event_loop::{ActiveEventLoop, EventLoop}, window::Window};
-use yhwh::{common::constants::{WINDOW_HEIGHT, WINDOW_WIDTH}, engine::Engine};
+use yhwh::{common::constants::{DEFAULT_LEVEL, WINDOW_HEIGHT, WINDOW_WIDTH}, engine::{render_level, Engine}, utils::json::{convert_level, get_level_path}};
 
 pub struct App {
     engine: Option<Engine>,
+    level: String
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(level: String) -> Self {
         return Self {
             engine: None,
+            level
         };
     }
 }
@@ -22,7 +24,7 @@ impl ApplicationHandler<Engine> for App {
 
         let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
 
-        self.engine = Some(pollster::block_on(Engine::new(window)));
+        self.engine = Some(pollster::block_on(Engine::new(window, &self.level)));
     }
 
     fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: Engine) {
@@ -61,13 +63,71 @@ impl App {
 
       let event_loop = EventLoop::with_user_event().build()?;
 
-      let mut app = App::new();
+      // e.g. `yhwh test2` or `yhwh path/to/level.lvl`
+      let level = std::env::args().nth(1).unwrap_or_else(|| DEFAULT_LEVEL.to_string());
+
+      let mut app = App::new(level);
       event_loop.run_app(&mut app)?;
 
       Ok(())
    }
 }
 
+/// `yhwh convert <input> <output>` rewrites a level between the JSON and binary formats, picked by
+/// the file extensions, without opening a window.
+fn convert(args: &[String]) {
+    let [input, output] = args else {
+        eprintln!("usage: yhwh convert <input> <output>");
+        std::process::exit(2);
+    };
+
+    match convert_level(Path::new(input), Path::new(output)) {
+        Ok(_) => println!("Converted {input} to {output}!"),
+        Err(e) => {
+            eprintln!("convert error: {e}!!");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `yhwh render <level> <output.png> [width height]` renders the first frame of a level without a
+/// window, defaults to the window size.
+fn render(args: &[String]) {
+    let usage = || {
+        eprintln!("usage: yhwh render <level> <output.png> [width height]");
+        std::process::exit(2);
+    };
+
+    let (level, output, width, height) = match args {
+        [level, output] => (level, output, WINDOW_WIDTH as u32, WINDOW_HEIGHT as u32),
+        [level, output, width, height] => match (width.parse(), height.parse()) {
+            (Ok(width), Ok(height)) if width > 0 && height > 0 => (level, output, width, height),
+            _ => usage()
+        },
+        _ => usage()
+    };
+
+    match render_level(&get_level_path(level), Path::new(output), width, height) {
+        Ok(_) => println!("Rendered {level} to {output}!"),
+        Err(e) => {
+            eprintln!("render error: {e}!!");
+            std::process::exit(1);
+        }
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.get(1).is_some_and(|command| command == "convert") {
+        convert(&args[2..]);
+        return;
+    }
+
+    if args.get(1).is_some_and(|command| command == "render") {
+        render(&args[2..]);
+        return;
+    }
+
     App::run().unwrap();
 }
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::{asset_manager::AssetManager, common::{create_info::MeshNodeCreateInfo, types::MeshRenderingInfo}};
+use crate::{asset_manager::AssetManager, common::{constants::DEFAULT_ALPHA_CUTOFF, create_info::MeshNodeCreateInfo, enums::AlphaMode, types::MeshRenderingInfo}};
 
 pub struct MeshNodes {
     model_name: String,
@@ -21,14 +21,19 @@ impl MeshNodes {
                  mesh_rendering_info.push(MeshRenderingInfo {
                  mesh_index,
                  material_index,
-                 emissive: false
+                 emissive: false,
+                 alpha_mode: mesh.alpha_mode,
+                 alpha_cutoff: mesh.alpha_cutoff
                 });
                 mesh_rendering_info_index_map.insert(mesh.name.clone(), mesh_rendering_info.len() - 1);
             }
           }
         } else {
             for info in create_info.iter() {
-            let mesh = asset_manager.get_mesh_by_name(&info.mesh_name).expect("GameObject::new() error: Mesh {info.name} not found in model meshes");
+            let Some(mesh) = asset_manager.get_mesh_by_name(&info.mesh_name) else {
+                println!("MeshNodes::new() error: mesh {} not found in model {model_name}!!", info.mesh_name);
+                continue;
+            };
             
             let mesh_index = asset_manager.get_mesh_index_by_name(&info.mesh_name);
             let material_index = asset_manager.get_material_index_by_name(&info.material_name);
@@ -36,7 +41,9 @@ impl MeshNodes {
             mesh_rendering_info.push(MeshRenderingInfo {
                 mesh_index,
                 material_index,
-                emissive: info.emissive
+                emissive: info.emissive,
+                alpha_mode: info.alpha_mode.unwrap_or(mesh.alpha_mode),
+                alpha_cutoff: info.alpha_cutoff.unwrap_or(mesh.alpha_cutoff)
             });
             mesh_rendering_info_index_map.insert(mesh.name.clone(), mesh_rendering_info.len() - 1);
            }
@@ -82,7 +89,9 @@ impl MeshNodes {
             &MeshRenderingInfo { 
                 mesh_index: 0,
                 material_index: 0,
-                emissive: false
+                emissive: false,
+                alpha_mode: AlphaMode::Opaque,
+                alpha_cutoff: DEFAULT_ALPHA_CUTOFF
             }
         }
     }
@@ -98,4 +107,25 @@ impl MeshNodes {
     pub fn get_mesh_rendering_infos(&self) -> &Vec<MeshRenderingInfo> {
         &self.mesh_rendering_info
     }
+
+    pub fn get_create_infos(&self, asset_manager: &AssetManager) -> Vec<MeshNodeCreateInfo> {
+        let mut mesh_nodes_create_infos: Vec<MeshNodeCreateInfo> = Vec::new();
+
+        for mesh_node in self.mesh_rendering_info.iter() {
+          if let Some((mesh, material)) = asset_manager.get_mesh_by_index(mesh_node.mesh_index).zip(asset_manager.get_material_by_index(mesh_node.material_index)) {
+            let create_info = MeshNodeCreateInfo {
+                material_name: material.name.clone(),
+                mesh_name: mesh.name.clone(),
+                emissive: mesh_node.emissive,
+                // only values changed from the imported ones are saved, so reimported assets still apply
+                alpha_mode: (mesh_node.alpha_mode != mesh.alpha_mode).then_some(mesh_node.alpha_mode),
+                alpha_cutoff: (mesh_node.alpha_cutoff != mesh.alpha_cutoff).then_some(mesh_node.alpha_cutoff)
+            };
+
+            mesh_nodes_create_infos.push(create_info);
+          }
+        }
+
+        mesh_nodes_create_infos
+    }
 }
@@ -12,6 +12,7 @@ use crate::animation::animation::{load_animations, Animations, PlaybackMode, Ani
 use crate::animation::node::Nodes;
 use crate::animation::skin::{create_skins_from_gltf, Skin};
 
+use crate::common::{constants::DEFAULT_ALPHA_CUTOFF, enums::AlphaMode};
 use crate::utils::file::load_file_string_from_dir;
 use crate::{
     renderer_common::{CUBE_INDICES, CUBE_VERTICES, PLANE_INDICES, PLANE_VERTICES},
@@ -24,7 +25,10 @@ pub struct Mesh {
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub num_elements: u32,
-    pub aabb: Aabb<f32>
+    pub aabb: Aabb<f32>,
+    /// From the glTF material, mesh nodes start with it.
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32
 }
 
 pub struct Model {
@@ -129,7 +133,9 @@ pub fn load_obj_model_sync(
                 vertex_buffer,
                 index_buffer,
                 num_elements: m.mesh.indices.len() as u32,
-                aabb: Aabb::new(cgmath::Vector3::zero(), cgmath::Vector3::zero())
+                aabb: compute_vertices_aabb(&vertices),
+                alpha_mode: AlphaMode::Opaque,
+                alpha_cutoff: DEFAULT_ALPHA_CUTOFF
                 //material: m.mesh.material_id.unwrap_or(0),
             }
         })
@@ -170,7 +176,9 @@ pub fn load_cube(device: &wgpu::Device, name: &str) -> anyhow::Result<Model> {
         vertex_buffer,
         index_buffer,
         num_elements: indices.len() as u32,
-        aabb: Aabb::new(cgmath::Vector3::zero(), cgmath::Vector3::zero())
+        aabb: compute_vertices_aabb(&vertices),
+        alpha_mode: AlphaMode::Opaque,
+        alpha_cutoff: DEFAULT_ALPHA_CUTOFF
     };
 
     meshes.push(cube_mesh);
@@ -210,7 +218,9 @@ pub fn load_plane(device: &wgpu::Device, name: &str) -> anyhow::Result<Model> {
         vertex_buffer,
         index_buffer,
         num_elements: indices.len() as u32,
-        aabb: Aabb::new(cgmath::Vector3::zero(), cgmath::Vector3::zero())
+        aabb: compute_vertices_aabb(&vertices),
+        alpha_mode: AlphaMode::Opaque,
+        alpha_cutoff: DEFAULT_ALPHA_CUTOFF
     };
 
     meshes.push(plane_mesh);
@@ -284,6 +294,7 @@ fn visit_node(node: &gltf::Node, data: &[Data], meshes: &mut Vec<Mesh>, device:
                     let reader = primitive.reader(|buffer| Some(&data[buffer.index()]));
 
                     let aabb = get_aabb(&primitive.bounding_box());
+                    let material = primitive.material();
 
                     // position attribute
                     if let Some(iter) = reader.read_positions() {
@@ -377,7 +388,9 @@ fn visit_node(node: &gltf::Node, data: &[Data], meshes: &mut Vec<Mesh>, device:
                         vertex_buffer,
                         index_buffer,
                         num_elements: indices.len() as u32,
-                        aabb
+                        aabb,
+                        alpha_mode: AlphaMode::from_gltf(material.alpha_mode()),
+                        alpha_cutoff: material.alpha_cutoff().unwrap_or(DEFAULT_ALPHA_CUTOFF)
                     });
                 }
             }
@@ -396,18 +409,37 @@ impl Model {
         };
 
         if updated {
-            self.nodes.transform(Some(self.global_transform));
-            self.nodes
-                .get_skins_transform()
-                .iter()
-                .for_each(|(index, transform)| {
-                    let skin = &mut self.skins[*index];
-                    skin.compute_joints_matrices(*transform, self.nodes.nodes());
-                });
+            self.update_skins();
         }
 
         updated
     }
+
+    /// Poses the model for one object's playback state, the skins then hold that object's joints.
+    pub fn update_with_state(&mut self, state: &mut AnimationState, delta_time: f32) -> bool {
+        let updated = if let Some(animations) = self.animations.as_mut() {
+            animations.update_state(&mut self.nodes, state, delta_time)
+        } else {
+            false
+        };
+
+        if updated {
+            self.update_skins();
+        }
+
+        updated
+    }
+
+    fn update_skins(&mut self) {
+        self.nodes.transform(Some(self.global_transform));
+        self.nodes
+            .get_skins_transform()
+            .iter()
+            .for_each(|(index, transform)| {
+                let skin = &mut self.skins[*index];
+                skin.compute_joints_matrices(*transform, self.nodes.nodes());
+            });
+    }
 }
 
 // animations stuff
@@ -431,6 +463,29 @@ impl Model {
         }
     }
 
+    pub fn set_animation_speed(&mut self, speed: f32) {
+        if let Some(animations) = self.animations.as_mut() {
+            animations.set_speed(speed);
+        }
+    }
+
+    pub fn set_animation_paused(&mut self, paused: bool) {
+        if let Some(animations) = self.animations.as_mut() {
+            animations.set_paused(paused);
+        }
+    }
+
+    pub fn has_mesh(&self, name: &str) -> bool {
+        self.meshes.iter().any(|mesh| mesh.name == name)
+    }
+
+    pub fn get_animation_names(&self) -> Vec<String> {
+        self.animations
+            .as_ref()
+            .map(|animations| animations.animations().iter().map(|animation| animation.get_name().clone()).collect())
+            .unwrap_or_default()
+    }
+
     pub fn toggle_animation(&mut self) {
         if let Some(animations) = self.animations.as_mut() {
             animations.toggle();
@@ -475,6 +530,11 @@ fn compute_unit_cube_at_origin_transform(aabb: Aabb<f32>) -> cgmath::Matrix4<f32
     translation * scale
 }
 
+fn compute_vertices_aabb(vertices: &[Vertex]) -> Aabb<f32> {
+    Aabb::from_points(vertices.iter().map(|vertex| cgmath::Vector3::from(vertex.position)))
+        .unwrap_or(Aabb::new(cgmath::Vector3::zero(), cgmath::Vector3::zero()))
+}
+
 fn get_aabb(bounds: &Bounds<[f32; 3]>) -> Aabb<f32> {
     let min = bounds.min;
     let min = cgmath::Vector3::new(min[0], min[1], min[2]);
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
-use crate::{asset_manager::AssetManager, common::{create_info::GameObjectCreateInfo, types::MeshRenderingInfo}, mesh_nodes::MeshNodes, utils::unique_id};
-use cgmath::Rotation3;
+use crate::{animation::animation::{AnimationState, PlaybackMode}, asset_manager::AssetManager, common::{create_info::{AnimatedGameObjectCreateInfo, GameObjectCreateInfo}, types::MeshRenderingInfo}, mesh_nodes::MeshNodes, model::Model, utils::unique_id};
+use cgmath::{Rotation3, SquareMatrix};
 
 pub struct AnimatedGameObject {
     pub object_id: usize,
@@ -10,20 +10,46 @@ pub struct AnimatedGameObject {
     size: cgmath::Vector3<f32>,
     euler_rotation: cgmath::Vector3<f32>,
     pub tex_scale: cgmath::Vector2<f32>,
-    mesh_nodes: MeshNodes
+    mesh_nodes: MeshNodes,
+    /// Id of the object this one's transform is relative to, see `Scene::update_transforms`.
+    pub parent: Option<usize>,
+    world_matrix: cgmath::Matrix4<f32>,
+    /// Clip name, `None` plays the first clip of the model.
+    pub animation: Option<String>,
+    pub playback_mode: PlaybackMode,
+    pub speed: f32,
+    pub paused: bool,
+    /// Own playback on the shared model, `None` until the first update.
+    animation_state: Option<AnimationState>
 }
 
 impl AnimatedGameObject {
-    pub fn new(create_info: &GameObjectCreateInfo, asset_manager: &AssetManager) -> Self {
-        Self { 
+    pub fn new(create_info: &AnimatedGameObjectCreateInfo, asset_manager: &AssetManager) -> Self {
+        let animation = create_info.animation.clone();
+        let playback_mode = create_info.playback_mode;
+        let speed = create_info.speed;
+        let paused = create_info.paused;
+        let create_info = &create_info.game_object;
+
+        let mut animated_game_object = Self { 
             model_name: create_info.model_name.clone(),
             position: cgmath::Vector3::new(create_info.position[0], create_info.position[1], create_info.position[2]),
             euler_rotation: cgmath::Vector3::new(create_info.rotation[0], create_info.rotation[1], create_info.rotation[2]),
             size: cgmath::Vector3::new(create_info.size[0], create_info.size[1], create_info.size[2]),
             tex_scale: cgmath::Vector2::new(create_info.tex_scale[0], create_info.tex_scale[1]),
             object_id: unique_id::next_id(),
-            mesh_nodes: MeshNodes::new(&create_info.model_name, &create_info.mesh_rendering_info, asset_manager)
-        }
+            mesh_nodes: MeshNodes::new(&create_info.model_name, &create_info.mesh_rendering_info, asset_manager),
+            parent: None,
+            world_matrix: cgmath::Matrix4::identity(),
+            animation,
+            playback_mode,
+            speed,
+            paused,
+            animation_state: None
+        };
+
+        animated_game_object.world_matrix = animated_game_object.get_local_matrix();
+        animated_game_object
     }
 
     pub fn get_model_name(&self) -> &str {
@@ -42,7 +68,20 @@ impl AnimatedGameObject {
         self.size
     }
 
-    pub fn get_model_matrix(&self) -> cgmath::Matrix4<f32> {
+    pub fn set_position(&mut self, position: cgmath::Vector3<f32>) {
+        self.position = position;
+    }
+
+    pub fn set_size(&mut self, size: cgmath::Vector3<f32>) {
+        self.size = size;
+    }
+
+    pub fn set_rotation(&mut self, rotation: cgmath::Vector3<f32>) {
+        self.euler_rotation = rotation;
+    }
+
+    /// Transform relative to the parent, built from the position, rotation and size.
+    pub fn get_local_matrix(&self) -> cgmath::Matrix4<f32> {
         // let model_matrix = cgmath::Matrix4::from_translation(self.position)
         //  * self.rotation 
         //  * cgmath::Matrix4::from_nonuniform_scale(self.size.x, self.size.y, self.size.z);
@@ -60,6 +99,15 @@ impl AnimatedGameObject {
         translation * rotation * scale
     }
 
+    /// World transform, propagated from the parents once per frame.
+    pub fn get_model_matrix(&self) -> cgmath::Matrix4<f32> {
+        self.world_matrix
+    }
+
+    pub fn set_world_matrix(&mut self, world_matrix: cgmath::Matrix4<f32>) {
+        self.world_matrix = world_matrix;
+    }
+
     pub fn get_mesh_nodes(&self) -> &MeshNodes {
         &self.mesh_nodes
     }
@@ -67,4 +115,41 @@ impl AnimatedGameObject {
     pub fn get_mesh_nodes_mut(&mut self) -> &mut MeshNodes {
         &mut self.mesh_nodes
     }
+
+    /// Advances this object's playback and poses the shared model with it, the model's skins hold
+    /// this object's joints until the next object is updated.
+    pub fn update_animation(&mut self, model: &mut Model, delta_time: f32) {
+        let Some(animations) = model.animations.as_ref() else {
+            return;
+        };
+
+        let index = self.animation.as_ref()
+            .and_then(|name| animations.animations().iter().position(|animation| animation.get_name() == name))
+            .unwrap_or(0);
+
+        let state = self.animation_state.get_or_insert(*animations.get_playback_state());
+        animations.set_state_current(state, index);
+        state.playback_mode = self.playback_mode;
+        state.speed = self.speed;
+        state.paused = self.paused;
+
+        model.update_with_state(state, delta_time);
+    }
+
+    pub fn get_create_info(&self, asset_manager: &AssetManager) -> AnimatedGameObjectCreateInfo {
+        AnimatedGameObjectCreateInfo {
+            game_object: GameObjectCreateInfo {
+                size: self.get_size().into(),
+                position: self.get_position().into(),
+                rotation: self.get_rotation().into(),
+                tex_scale: self.tex_scale.into(),
+                mesh_rendering_info: self.mesh_nodes.get_create_infos(asset_manager),
+                model_name: self.get_model_name().to_string()
+            },
+            animation: self.animation.clone(),
+            playback_mode: self.playback_mode,
+            speed: self.speed,
+            paused: self.paused
+        }
+    }
 }
\ No newline at end of file
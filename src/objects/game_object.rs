@@ -1,5 +1,5 @@
-use crate::{asset_manager::AssetManager, common::{create_info::{GameObjectCreateInfo, MeshNodeCreateInfo}, types::MeshRenderingInfo}, mesh_nodes::MeshNodes, utils::unique_id};
-use cgmath::Rotation3;
+use crate::{asset_manager::AssetManager, common::{create_info::GameObjectCreateInfo, types::MeshRenderingInfo}, mesh_nodes::MeshNodes, utils::{math::{euler_to_quaternion, quaternion_to_euler}, unique_id}};
+use cgmath::SquareMatrix;
 
 pub struct GameObject {
     model_name: String,
@@ -9,12 +9,15 @@ pub struct GameObject {
     pub tex_scale: cgmath::Vector2<f32>,
     pub is_selected: bool,
     pub id: usize,
+    /// Id of the object this one's transform is relative to, see `Scene::update_transforms`.
+    pub parent: Option<usize>,
+    world_matrix: cgmath::Matrix4<f32>,
     mesh_nodes: MeshNodes,
 }
 
 impl GameObject {
     pub fn new(create_info: &GameObjectCreateInfo, asset_manager: &AssetManager) -> Self {
-        Self { 
+        let mut game_object = Self { 
             model_name: create_info.model_name.clone(),
             position: cgmath::Vector3::new(create_info.position[0], create_info.position[1], create_info.position[2]),
             euler_rotation: cgmath::Vector3::new(create_info.rotation[0], create_info.rotation[1], create_info.rotation[2]),
@@ -22,8 +25,13 @@ impl GameObject {
             tex_scale: cgmath::Vector2::new(create_info.tex_scale[0], create_info.tex_scale[1]),
             is_selected: false,
             id: unique_id::next_id(),
+            parent: None,
+            world_matrix: cgmath::Matrix4::identity(),
             mesh_nodes: MeshNodes::new(&create_info.model_name.clone(), &create_info.mesh_rendering_info, asset_manager),
-        }
+        };
+
+        game_object.world_matrix = game_object.get_local_matrix();
+        game_object
     }
 
     pub fn get_model_name(&self) -> &str {
@@ -42,6 +50,10 @@ impl GameObject {
         self.euler_rotation
     }
 
+    pub fn get_rotation_quaternion(&self) -> cgmath::Quaternion<f32> {
+        euler_to_quaternion(self.euler_rotation)
+    }
+
     pub fn get_size(&self) -> cgmath::Vector3<f32> {
         self.size
     }
@@ -50,18 +62,24 @@ impl GameObject {
         &mut self.size
     }
 
-    pub fn get_model_matrix(&self) -> cgmath::Matrix4<f32> {
+    /// Transform relative to the parent, built from the position, rotation and size.
+    pub fn get_local_matrix(&self) -> cgmath::Matrix4<f32> {
         let translation = cgmath::Matrix4::from_translation(self.position);
-        let rotation = cgmath::Matrix4::from(
-            cgmath::Quaternion::from_angle_x(cgmath::Deg(self.euler_rotation.x))
-            * cgmath::Quaternion::from_angle_y(cgmath::Deg(self.euler_rotation.y))
-            * cgmath::Quaternion::from_angle_z(cgmath::Deg(self.euler_rotation.z))
-        );
+        let rotation = cgmath::Matrix4::from(self.get_rotation_quaternion());
         let scale = cgmath::Matrix4::from_nonuniform_scale(self.size.x, self.size.z, self.size.y);
 
         translation * rotation * scale
     }
 
+    /// World transform, propagated from the parents once per frame.
+    pub fn get_model_matrix(&self) -> cgmath::Matrix4<f32> {
+        self.world_matrix
+    }
+
+    pub fn set_world_matrix(&mut self, world_matrix: cgmath::Matrix4<f32>) {
+        self.world_matrix = world_matrix;
+    }
+
     pub fn get_mesh_nodes(&self) -> &MeshNodes {
         &self.mesh_nodes
     }
@@ -85,31 +103,21 @@ impl GameObject {
     pub fn set_rotation(&mut self, rotation: cgmath::Vector3<f32>) {
         self.euler_rotation = rotation;
     }
+
+    /// Stores `rotation` back as x, y, z euler degrees in 0-360, the order `get_rotation_quaternion` composes them in.
+    pub fn set_rotation_quaternion(&mut self, rotation: cgmath::Quaternion<f32>) {
+        self.euler_rotation = quaternion_to_euler(rotation);
+    }
 }
 
 impl GameObject {
     pub fn get_create_info(&self, asset_manager: &AssetManager) -> GameObjectCreateInfo {
-        let mut mesh_nodes_create_infos: Vec<MeshNodeCreateInfo> = Vec::new();
-
-        for mesh_node in self.get_mesh_nodes().get_mesh_rendering_infos() {
-          if let Some((mesh, material)) = asset_manager.get_mesh_by_index(mesh_node.mesh_index).zip(asset_manager.get_material_by_index(mesh_node.material_index)) {
-            let create_info = MeshNodeCreateInfo {
-                material_name: material.name.clone(),
-                mesh_name: mesh.name.clone(),
-                emissive: mesh_node.emissive
-            };
-
-            mesh_nodes_create_infos.push(create_info);
-          }
-          
-        }
-
         let create_info = GameObjectCreateInfo {
             size: self.get_size().into(),
             position: self.get_position().into(),
             rotation: self.get_rotation().into(),
             tex_scale: self.tex_scale.into(),
-            mesh_rendering_info: mesh_nodes_create_infos,
+            mesh_rendering_info: self.mesh_nodes.get_create_infos(asset_manager),
             model_name: self.get_model_name().to_string()
         };
 
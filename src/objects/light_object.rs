@@ -1,12 +1,30 @@
-use crate::{common::{create_info::LightObjectCreateInfo, enums::LightType}, utils::unique_id};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use cgmath::{EuclideanSpace, InnerSpace, SquareMatrix, Transform};
+
+use crate::{camera::{Camera, OPENGL_TO_WGPU_MATRIX}, common::{constants::{CASCADE_SHADOW_MAP_SIZE, SHADOW_CASCADE_COUNT}, create_info::LightObjectCreateInfo, enums::LightType}, utils::unique_id};
+
+const SHADOW_NEAR_PLANE: f32 = 0.1;
+const CASCADE_SPLIT_LAMBDA: f32 = 0.5;
+const CASCADE_CASTER_DISTANCE: f32 = 50.0;
+
+// set once the non-invertible camera error was printed, it would repeat every frame otherwise
+static CASCADE_ERROR_LOGGED: AtomicBool = AtomicBool::new(false);
 
 pub struct LightObject {
     pub color: cgmath::Vector3<f32>,
     pub position: cgmath::Vector3<f32>,
+    pub direction: cgmath::Vector3<f32>,
+    pub inner_cone_angle: f32,
+    pub outer_cone_angle: f32,
     pub strength: f32,
     pub radius: f32,
     pub light_type: LightType,
-    pub id: usize
+    pub casts_shadows: bool,
+    pub id: usize,
+    /// Id of the object this light's position and direction are relative to.
+    pub parent: Option<usize>,
+    world_matrix: cgmath::Matrix4<f32>
 }
 
 impl LightObject {
@@ -14,23 +32,171 @@ impl LightObject {
         Self {
             color: cgmath::Vector3::new(create_info.color[0], create_info.color[1], create_info.color[2]),
             position: cgmath::Vector3::new(create_info.position[0], create_info.position[1], create_info.position[2]),
+            direction: cgmath::Vector3::new(create_info.direction[0], create_info.direction[1], create_info.direction[2]),
+            inner_cone_angle: create_info.inner_cone_angle,
+            outer_cone_angle: create_info.outer_cone_angle,
             radius: create_info.radius,
             strength: create_info.strength,
             light_type: create_info.light_type.clone(),
-            id: unique_id::next_id()
+            casts_shadows: create_info.casts_shadows,
+            id: unique_id::next_id(),
+            parent: None,
+            world_matrix: cgmath::Matrix4::from_translation(cgmath::Vector3::new(create_info.position[0], create_info.position[1], create_info.position[2]))
         }
     }
 
      pub fn get_create_info(&self) -> LightObjectCreateInfo {
-        let create_info = LightObjectCreateInfo { 
+        let create_info = LightObjectCreateInfo {
             position: [self.position.x, self.position.y, self.position.z],
             color: [self.color.x, self.color.y, self.color.z],
+            direction: [self.direction.x, self.direction.y, self.direction.z],
+            inner_cone_angle: self.inner_cone_angle,
+            outer_cone_angle: self.outer_cone_angle,
             radius: self.radius,
             strength: self.strength,
-            light_type: self.light_type.clone()
+            light_type: self.light_type.clone(),
+            casts_shadows: self.casts_shadows
         };
 
         create_info
     }
+
+    /// Lights are not rotated or scaled themselves, only their parents are.
+    pub fn get_local_matrix(&self) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::from_translation(self.position)
+    }
+
+    pub fn set_world_matrix(&mut self, world_matrix: cgmath::Matrix4<f32>) {
+        self.world_matrix = world_matrix;
+    }
+
+    pub fn get_world_matrix(&self) -> cgmath::Matrix4<f32> {
+        self.world_matrix
+    }
+
+    pub fn get_world_position(&self) -> cgmath::Vector3<f32> {
+        self.world_matrix.w.truncate()
+    }
+
+    /// World space direction, rotated by the parents.
+    pub fn get_direction(&self) -> cgmath::Vector3<f32> {
+        let direction = self.world_matrix.transform_vector(self.direction);
+
+        if direction.magnitude2() > 0.0 {
+            direction.normalize()
+        } else {
+            -cgmath::Vector3::unit_y()
+        }
+    }
+
+    /// One view projection per cube face, in +X, -X, +Y, -Y, +Z, -Z order.
+    pub fn get_point_shadow_matrices(&self) -> [cgmath::Matrix4<f32>; 6] {
+        let eye = cgmath::Point3::from_vec(self.get_world_position());
+        let projection = OPENGL_TO_WGPU_MATRIX * cgmath::perspective(cgmath::Deg(90.0), 1.0, SHADOW_NEAR_PLANE, self.radius.max(SHADOW_NEAR_PLANE * 2.0));
+
+        let faces = [
+            (cgmath::Vector3::unit_x(), -cgmath::Vector3::unit_y()),
+            (-cgmath::Vector3::unit_x(), -cgmath::Vector3::unit_y()),
+            (cgmath::Vector3::unit_y(), cgmath::Vector3::unit_z()),
+            (-cgmath::Vector3::unit_y(), -cgmath::Vector3::unit_z()),
+            (cgmath::Vector3::unit_z(), -cgmath::Vector3::unit_y()),
+            (-cgmath::Vector3::unit_z(), -cgmath::Vector3::unit_y()),
+        ];
+
+        faces.map(|(direction, up)| projection * cgmath::Matrix4::look_to_rh(eye, direction, up))
+    }
+
+    /// Cosines of the inner and outer cone angles, the inner one clamped so it never exceeds the outer.
+    pub fn get_cone_cosines(&self) -> (f32, f32) {
+        let outer = self.outer_cone_angle.clamp(0.0, 89.0);
+        let inner = self.inner_cone_angle.clamp(0.0, outer);
+
+        (inner.to_radians().cos(), outer.to_radians().cos())
+    }
+
+    pub fn get_spot_shadow_matrix(&self) -> cgmath::Matrix4<f32> {
+        let eye = cgmath::Point3::from_vec(self.get_world_position());
+        let direction = self.get_direction();
+        let fovy = (self.outer_cone_angle.clamp(1.0, 89.0) * 2.0).min(179.0);
+        let projection = OPENGL_TO_WGPU_MATRIX * cgmath::perspective(cgmath::Deg(fovy), 1.0, SHADOW_NEAR_PLANE, self.radius.max(SHADOW_NEAR_PLANE * 2.0));
+
+        projection * cgmath::Matrix4::look_to_rh(eye, direction, shadow_up_vector(direction))
+    }
+
+    /// Fits one orthographic shadow camera around each slice of the camera frustum.
+    /// Returns the matrices together with the view space far distance of every cascade.
+    /// A slice that can't be fitted reuses the previous cascade's matrix, and when the first one
+    /// can't be fitted there is nothing to fall back to so the light casts no shadow this frame.
+    pub fn get_cascade_shadow_matrices(&self, camera: &Camera) -> Option<([cgmath::Matrix4<f32>; SHADOW_CASCADE_COUNT], [f32; SHADOW_CASCADE_COUNT])> {
+        let projection = camera.get_projection();
+        let near = projection.get_znear();
+        let far = projection.get_zfar();
+        let view = camera.calc_matrix();
+        let direction = self.get_direction();
+        let up = shadow_up_vector(direction);
+
+        let mut matrices = [cgmath::Matrix4::identity(); SHADOW_CASCADE_COUNT];
+        let mut splits = [far; SHADOW_CASCADE_COUNT];
+        let mut previous_split = near;
+
+        for i in 0..SHADOW_CASCADE_COUNT {
+            let p = (i + 1) as f32 / SHADOW_CASCADE_COUNT as f32;
+            let log_split = near * (far / near).powf(p);
+            let uniform_split = near + (far - near) * p;
+            let split = CASCADE_SPLIT_LAMBDA * log_split + (1.0 - CASCADE_SPLIT_LAMBDA) * uniform_split;
+
+            let Some(inverse) = (projection.calc_matrix_with_range(previous_split, split) * view).invert() else {
+                if !CASCADE_ERROR_LOGGED.swap(true, Ordering::Relaxed) {
+                    println!("LightObject::get_cascade_shadow_matrices() error: camera matrix is not invertible!!");
+                }
+
+                if i == 0 {
+                    return None;
+                }
+
+                matrices[i] = matrices[i - 1];
+                splits[i] = split;
+                previous_split = split;
+                continue;
+            };
+
+            let mut corners = Vec::with_capacity(8);
+            for x in [-1.0, 1.0] {
+                for y in [-1.0, 1.0] {
+                    for z in [0.0, 1.0] {
+                        let corner = inverse * cgmath::Vector4::new(x, y, z, 1.0);
+                        corners.push(corner.truncate() / corner.w);
+                    }
+                }
+            }
+
+            let center = corners.iter().fold(cgmath::Vector3::new(0.0, 0.0, 0.0), |sum, corner| sum + corner) / corners.len() as f32;
+            let radius = corners.iter().map(|corner| (corner - center).magnitude()).fold(0.0_f32, f32::max);
+            let radius = (radius * 16.0).ceil() / 16.0;
+
+            let light_view = cgmath::Matrix4::look_to_rh(cgmath::Point3::from_vec(center - direction * (radius + CASCADE_CASTER_DISTANCE)), direction, up);
+            let light_projection = OPENGL_TO_WGPU_MATRIX * cgmath::ortho(-radius, radius, -radius, radius, 0.0, radius * 2.0 + CASCADE_CASTER_DISTANCE);
+
+            // snap to whole texels so the cascades don't shimmer when the camera moves
+            let mut shadow_matrix = light_projection * light_view;
+            let texel_scale = CASCADE_SHADOW_MAP_SIZE as f32 / 2.0;
+            let origin = shadow_matrix * cgmath::Vector4::new(0.0, 0.0, 0.0, 1.0) * texel_scale;
+            let offset = cgmath::Vector4::new(origin.x.round() - origin.x, origin.y.round() - origin.y, 0.0, 0.0) / texel_scale;
+            shadow_matrix = cgmath::Matrix4::from_translation(offset.truncate()) * shadow_matrix;
+
+            matrices[i] = shadow_matrix;
+            splits[i] = split;
+            previous_split = split;
+        }
+
+        Some((matrices, splits))
+    }
 }
 
+fn shadow_up_vector(direction: cgmath::Vector3<f32>) -> cgmath::Vector3<f32> {
+    if direction.y.abs() > 0.99 {
+        cgmath::Vector3::unit_z()
+    } else {
+        cgmath::Vector3::unit_y()
+    }
+}
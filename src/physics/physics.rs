@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 
 use rapier3d::{control::KinematicCharacterController, prelude::*};
+use yhwh_core::math::aabb::Aabb;
 
-use crate::{common::{constants::FIXED_DELTA_TIME, errors::CharacterControllerError}, utils::unique_id};
+use crate::{common::{constants::FIXED_DELTA_TIME, errors::CharacterControllerError}, debug_draw::DebugDraw, utils::unique_id};
 
 struct CharacterController {
     shape: SharedShape,
@@ -130,4 +131,32 @@ impl Physics {
 
         Ok(())
      }
-}
\ No newline at end of file
+
+     /// Outlines every collider, balls and cuboids with their shape and the rest with their bounds.
+     pub fn draw_colliders(&self, debug_draw: &mut DebugDraw) {
+        let color = cgmath::Vector3::new(0.2, 1.0, 0.2);
+
+        for (_, collider) in self.collider_set.iter() {
+          let position = collider.position();
+          let translation = cgmath::Vector3::new(position.translation.x, position.translation.y, position.translation.z);
+
+          if let Some(ball) = collider.shape().as_ball() {
+            debug_draw.sphere(translation, ball.radius, color);
+          } else if let Some(cuboid) = collider.shape().as_cuboid() {
+            let isometry: [[f32; 4]; 4] = position.to_homogeneous().into();
+            let size = cuboid.half_extents * 2.0;
+            let transform = cgmath::Matrix4::from(isometry) * cgmath::Matrix4::from_nonuniform_scale(size.x, size.y, size.z);
+
+            debug_draw.cube(&transform, color);
+          } else {
+            let aabb = collider.compute_aabb();
+            debug_draw.aabb(&Aabb::new(cgmath::Vector3::new(aabb.mins.x, aabb.mins.y, aabb.mins.z), cgmath::Vector3::new(aabb.maxs.x, aabb.maxs.y, aabb.maxs.z)), color);
+          }
+        }
+
+        for character_controller in self.character_controllers.values() {
+          let aabb = character_controller.shape.compute_aabb(&character_controller.position);
+          debug_draw.aabb(&Aabb::new(cgmath::Vector3::new(aabb.mins.x, aabb.mins.y, aabb.mins.z), cgmath::Vector3::new(aabb.maxs.x, aabb.maxs.y, aabb.maxs.z)), color);
+        }
+     }
+}
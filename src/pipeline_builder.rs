@@ -8,8 +8,13 @@ pub struct PipelineBuilder<'a> {
     pub depth_write: bool,
     pub depth_compare: wgpu::CompareFunction,
     pub cull_mode: Option<wgpu::Face>,
-    pub blend: wgpu::BlendState,
-    pub stencil_state: wgpu::StencilState
+    pub blend: Option<wgpu::BlendState>,
+    pub stencil_state: wgpu::StencilState,
+    pub depth_bias: wgpu::DepthBiasState,
+    pub sample_count: u32,
+    pub topology: wgpu::PrimitiveTopology,
+    pub vertex_entry_point: &'static str,
+    pub fragment_entry_point: Option<&'static str>
 }
 
 impl<'a> PipelineBuilder<'a> {
@@ -26,12 +31,17 @@ impl<'a> PipelineBuilder<'a> {
             vertex_buffers,
             shader_module,
             target_formats: target_formats.into_iter().collect(),
-            blend: wgpu::BlendState::ALPHA_BLENDING,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
             depth_write: false,
             depth_compare: wgpu::CompareFunction::LessEqual,
             depth_format: None,
             cull_mode: None,
-            stencil_state: Default::default()
+            stencil_state: Default::default(),
+            depth_bias: Default::default(),
+            sample_count: 1,
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            vertex_entry_point: "vs_main",
+            fragment_entry_point: None
         }
     }
 
@@ -46,13 +56,55 @@ impl<'a> PipelineBuilder<'a> {
         self
     }
 
+    pub fn with_depth_compare(mut self, compare: wgpu::CompareFunction) -> Self {
+        self.depth_compare = compare;
+        self
+    }
+
+    /// For shaders with more than one vertex stage, `vs_main` otherwise.
+    pub fn with_vertex_entry_point(mut self, entry_point: &'static str) -> Self {
+        self.vertex_entry_point = entry_point;
+        self
+    }
+
+    /// Runs this fragment stage even without color targets, for alpha tested depth only pipelines.
+    pub fn with_fragment_entry_point(mut self, entry_point: &'static str) -> Self {
+        self.fragment_entry_point = Some(entry_point);
+        self
+    }
+
+    pub fn with_topology(mut self, topology: wgpu::PrimitiveTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_depth_bias(mut self, constant: i32, slope_scale: f32) -> Self {
+        self.depth_bias = wgpu::DepthBiasState {
+            constant,
+            slope_scale,
+            clamp: 0.0
+        };
+        self
+    }
+
     pub fn with_cull_mode(mut self, face: wgpu::Face) -> Self {
         self.cull_mode = Some(face);
         self
     }
 
+    pub fn with_sample_count(mut self, count: u32) -> Self {
+        self.sample_count = count;
+        self
+    }
+
     pub fn with_blend(mut self, blend: wgpu::BlendState) -> Self {
-        self.blend = blend;
+        self.blend = Some(blend);
+        self
+    }
+
+    /// Integer targets can't be blended.
+    pub fn without_blend(mut self) -> Self {
+        self.blend = None;
         self
     }
 
@@ -97,7 +149,7 @@ impl<'a> PipelineBuilder<'a> {
         let targets: Vec<Option<wgpu::ColorTargetState>> = self.target_formats.into_iter().map(|format| {
                 Some(wgpu::ColorTargetState {
                     format,
-                    blend: Some(self.blend),
+                    blend: self.blend,
                     write_mask: wgpu::ColorWrites::ALL,
                 })
             }).collect();
@@ -113,18 +165,23 @@ impl<'a> PipelineBuilder<'a> {
             layout: Some(&pipeline_layout),
             vertex: wgpu::VertexState {
                 module: self.shader_module,
-                entry_point: Some("vs_main"),
+                entry_point: Some(self.vertex_entry_point),
                 buffers: self.vertex_buffers,
                 compilation_options: Default::default(),
             },
-            fragment: Some(wgpu::FragmentState {
-                module: self.shader_module,
-                entry_point: Some("fs_main"),
-                targets: &targets,
-                compilation_options: Default::default(),
-            }),
+            // depth only pipelines (no color targets) skip the fragment stage unless they discard
+            fragment: if targets.is_empty() && self.fragment_entry_point.is_none() {
+                None
+            } else {
+                Some(wgpu::FragmentState {
+                    module: self.shader_module,
+                    entry_point: Some(self.fragment_entry_point.unwrap_or("fs_main")),
+                    targets: &targets,
+                    compilation_options: Default::default(),
+                })
+            },
             primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
+                topology: self.topology,
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
                 cull_mode: self.cull_mode,
@@ -137,10 +194,10 @@ impl<'a> PipelineBuilder<'a> {
                 depth_write_enabled: self.depth_write,
                 depth_compare: self.depth_compare,
                 stencil: self.stencil_state,
-                bias: Default::default(),
+                bias: self.depth_bias,
             }),
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: self.sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
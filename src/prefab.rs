@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+
+use cgmath::Rotation;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{common::{create_info::{PrefabCreateInfo, PrefabInstanceCreateInfo, PrefabOverrideCreateInfo, SceneNodeRef}, enums::SceneNodeKind}, utils::math::{euler_to_quaternion, quaternion_to_euler}};
+
+/// Prefab placed in the scene. Its objects are regular scene objects, remembered by id so the
+/// level can be saved as a reference to the prefab plus what differs from it.
+pub struct PrefabInstance {
+    pub create_info: PrefabInstanceCreateInfo,
+    /// Prefab the objects were spawned from, `None` when its file couldn't be loaded.
+    pub prefab: Option<PrefabCreateInfo>,
+    /// Scene ids of the prefab objects in prefab order, `None` for the removed ones.
+    pub game_object_ids: Vec<Option<usize>>,
+    pub animated_game_object_ids: Vec<Option<usize>>,
+    pub light_ids: Vec<Option<usize>>
+}
+
+impl PrefabInstance {
+    pub fn new(create_info: PrefabInstanceCreateInfo) -> Self {
+        Self {
+            create_info,
+            prefab: None,
+            game_object_ids: Vec::new(),
+            animated_game_object_ids: Vec::new(),
+            light_ids: Vec::new()
+        }
+    }
+
+    /// Scene id of a prefab object, `node` indexes the prefab lists.
+    pub fn get_id(&self, node: SceneNodeRef) -> Option<usize> {
+        let ids = match node.kind {
+            SceneNodeKind::GameObject => &self.game_object_ids,
+            SceneNodeKind::AnimatedGameObject => &self.animated_game_object_ids,
+            SceneNodeKind::Light => &self.light_ids
+        };
+
+        ids.get(node.index).copied().flatten()
+    }
+
+    /// Spawned objects as their prefab reference and scene id.
+    pub fn get_objects(&self) -> Vec<(SceneNodeRef, usize)> {
+        let kinds = [
+            (SceneNodeKind::GameObject, &self.game_object_ids),
+            (SceneNodeKind::AnimatedGameObject, &self.animated_game_object_ids),
+            (SceneNodeKind::Light, &self.light_ids)
+        ];
+
+        kinds.into_iter()
+            .flat_map(|(kind, ids)| ids.iter().enumerate().filter_map(move |(index, id)| id.map(|id| (SceneNodeRef::new(kind, index), id))))
+            .collect()
+    }
+}
+
+/// Copy of the prefab with the instance transform applied to its roots, objects whose parent
+/// was removed from the instance count as roots.
+pub fn get_placed_prefab(prefab: &PrefabCreateInfo, instance: &PrefabInstanceCreateInfo) -> PrefabCreateInfo {
+    let removed: HashSet<SceneNodeRef> = instance.removed.iter().copied().collect();
+    let is_root = |node: SceneNodeRef| !prefab.parents.iter().any(|link| link.child == node && !removed.contains(&link.parent));
+
+    let position = cgmath::Vector3::from(instance.position);
+    let rotation = euler_to_quaternion(instance.rotation.into());
+    let is_rotated = instance.rotation != [0.0; 3];
+
+    let place_point = |point: [f32; 3]| -> [f32; 3] { (position + rotation.rotate_vector(cgmath::Vector3::from(point) * instance.scale)).into() };
+    // unrotated instances keep the prefab's angles as written
+    let place_rotation = |euler: [f32; 3]| -> [f32; 3] {
+        if is_rotated { quaternion_to_euler(rotation * euler_to_quaternion(euler.into())).into() } else { euler }
+    };
+
+    let mut placed = prefab.clone();
+
+    for (index, create_info) in placed.game_objects.iter_mut().enumerate() {
+        if is_root(SceneNodeRef::new(SceneNodeKind::GameObject, index)) {
+            create_info.position = place_point(create_info.position);
+            create_info.rotation = place_rotation(create_info.rotation);
+            create_info.size = create_info.size.map(|size| size * instance.scale);
+        }
+    }
+
+    for (index, create_info) in placed.animated_game_objects.iter_mut().enumerate() {
+        if is_root(SceneNodeRef::new(SceneNodeKind::AnimatedGameObject, index)) {
+            let create_info = &mut create_info.game_object;
+            create_info.position = place_point(create_info.position);
+            create_info.rotation = place_rotation(create_info.rotation);
+            create_info.size = create_info.size.map(|size| size * instance.scale);
+        }
+    }
+
+    for (index, create_info) in placed.lights.iter_mut().enumerate() {
+        if is_root(SceneNodeRef::new(SceneNodeKind::Light, index)) {
+            create_info.position = place_point(create_info.position);
+            create_info.direction = rotation.rotate_vector(create_info.direction.into()).into();
+        }
+    }
+
+    placed
+}
+
+/// `create_info` with the overridden fields of `node` replaced, overrides that don't fit the
+/// create info are skipped.
+pub fn apply_overrides<T: Clone + Serialize + DeserializeOwned>(create_info: &T, overrides: &[PrefabOverrideCreateInfo], node: SceneNodeRef) -> T {
+    let Some(prefab_override) = overrides.iter().find(|prefab_override| prefab_override.object == node) else {
+        return create_info.clone();
+    };
+
+    match apply_override(create_info, &prefab_override.values) {
+        Ok(create_info) => create_info,
+        Err(e) => {
+            println!("prefab::apply_overrides() error: invalid override for {:?}: {}!!", node, e);
+            create_info.clone()
+        }
+    }
+}
+
+/// `create_info` with `values` replacing its fields, also used to validate the overrides of a level.
+pub fn apply_override<T: Serialize + DeserializeOwned>(create_info: &T, values: &serde_json::Map<String, serde_json::Value>) -> Result<T, serde_json::Error> {
+    let mut fields = match serde_json::to_value(create_info)? {
+        serde_json::Value::Object(fields) => fields,
+        _ => serde_json::Map::new()
+    };
+
+    fields.extend(values.clone());
+    serde_json::from_value(serde_json::Value::Object(fields))
+}
+
+/// Fields of `create_info` that differ from `base`, empty when the object matches the prefab.
+pub fn get_overrides<T: Serialize>(base: &T, create_info: &T) -> serde_json::Map<String, serde_json::Value> {
+    let (Ok(serde_json::Value::Object(base)), Ok(serde_json::Value::Object(create_info))) = (serde_json::to_value(base), serde_json::to_value(create_info)) else {
+        return serde_json::Map::new();
+    };
+
+    create_info.into_iter()
+        .filter(|(field, value)| base.get(field) != Some(value))
+        .collect()
+}
@@ -0,0 +1,421 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{bind_group_manager::BindGroupManager, common::{constants::{MAX_RENDER_SCALE, MIN_RENDER_SCALE}, enums::RenderGraphPass, errors::RenderGraphError}, texture::Texture};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureSize {
+    /// Follows the surface scaled by the render scale, reallocated on every resize.
+    Surface,
+    /// The render size halved n times, for mip chains.
+    Downscaled(u32),
+    Fixed(u32, u32)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TransientTextureDesc {
+    pub format: wgpu::TextureFormat,
+    pub usage: wgpu::TextureUsages,
+    pub size: TextureSize,
+    pub sample_count: u32
+}
+
+impl TransientTextureDesc {
+    /// A surface sized target that can be rendered to and sampled afterwards.
+    pub fn render_target(format: wgpu::TextureFormat) -> Self {
+        Self {
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            size: TextureSize::Surface,
+            sample_count: 1
+        }
+    }
+
+    pub fn with_size(mut self, size: TextureSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_usage(mut self, usage: wgpu::TextureUsages) -> Self {
+        self.usage |= usage;
+        self
+    }
+
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+}
+
+enum GraphResource {
+    /// Owned by the graph and sized from its descriptor.
+    Transient { desc: TransientTextureDesc, texture: Texture },
+    /// Owned elsewhere (swapchain, shadow maps..), only used to order the passes.
+    Imported
+}
+
+struct GraphPassNode {
+    id: RenderGraphPass,
+    reads: Vec<&'static str>,
+    writes: Vec<&'static str>
+}
+
+/// Passes declare the resources they read and write and the graph works out the execution order,
+/// drops passes whose results are never used and owns the transient textures between them.
+///
+/// Writers of the same resource run in the order they were added, pure readers run after every writer.
+/// All passes are recorded into a single encoder so wgpu inserts the barriers between them.
+pub struct RenderGraph {
+    resources: HashMap<&'static str, GraphResource>,
+    passes: Vec<GraphPassNode>,
+    order: Vec<usize>,
+    surface_size: (u32, u32),
+    render_scale: f32,
+    max_texture_size: u32,
+    generation: u64
+}
+
+impl RenderGraph {
+    pub fn new(device: &wgpu::Device, surface_size: (u32, u32)) -> Self {
+        Self {
+            resources: HashMap::new(),
+            passes: Vec::new(),
+            order: Vec::new(),
+            surface_size: (surface_size.0.max(1), surface_size.1.max(1)),
+            render_scale: 1.0,
+            max_texture_size: device.limits().max_texture_dimension_2d,
+            generation: 0
+        }
+    }
+
+    /// Adding a texture under an existing name replaces it, bind groups over it are rebuilt.
+    pub fn add_texture(&mut self, device: &wgpu::Device, name: &'static str, desc: TransientTextureDesc) {
+        let texture = Self::allocate(device, name, &desc, self.get_render_size());
+
+        if self.resources.insert(name, GraphResource::Transient { desc, texture }).is_some() {
+            self.generation += 1;
+        }
+    }
+
+    /// Only for textures that no pass declares, the graph has to be recompiled otherwise.
+    pub fn remove_texture(&mut self, name: &str) {
+        if self.resources.remove(name).is_some() {
+            self.generation += 1;
+        }
+    }
+
+    pub fn has_texture(&self, name: &str) -> bool {
+        matches!(self.resources.get(name), Some(GraphResource::Transient { .. }))
+    }
+
+    pub fn import(&mut self, name: &'static str) {
+        self.resources.insert(name, GraphResource::Imported);
+    }
+
+    pub fn add_pass(&mut self, id: RenderGraphPass, reads: &[&'static str], writes: &[&'static str]) {
+        self.passes.push(GraphPassNode {
+            id,
+            reads: reads.to_vec(),
+            writes: writes.to_vec()
+        });
+    }
+
+    /// Resolves the dependencies between the declared passes into an execution order.
+    pub fn compile(&mut self) -> Result<(), RenderGraphError> {
+        let imported: HashMap<&'static str, bool> = self.resources.iter()
+            .map(|(name, resource)| (*name, matches!(resource, GraphResource::Imported)))
+            .collect();
+
+        self.order = resolve_order(&self.passes, &imported)?;
+
+        Ok(())
+    }
+
+    pub fn get_execution_order(&self) -> Vec<RenderGraphPass> {
+        self.order.iter().map(|index| self.passes[*index].id).collect()
+    }
+
+    /// Reallocates every surface sized texture. Bind groups created from them have to be rebuilt,
+    /// which `GraphBindGroup` does by watching the generation.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if width == 0 || height == 0 || (width, height) == self.surface_size {
+            return;
+        }
+
+        self.surface_size = (width, height);
+        self.reallocate(device);
+    }
+
+    /// Renders the surface sized targets at a fraction or multiple of the surface, the post process pass
+    /// filters the result back to the swapchain.
+    pub fn set_render_scale(&mut self, device: &wgpu::Device, scale: f32) {
+        let scale = scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE);
+
+        if scale == self.render_scale {
+            return;
+        }
+
+        self.render_scale = scale;
+        self.reallocate(device);
+    }
+
+    fn reallocate(&mut self, device: &wgpu::Device) {
+        let render_size = self.get_render_size();
+
+        for (name, resource) in self.resources.iter_mut() {
+            if let GraphResource::Transient { desc, texture } = resource {
+                if !matches!(desc.size, TextureSize::Fixed(..)) {
+                    *texture = Self::allocate(device, name, desc, render_size);
+                }
+            }
+        }
+
+        self.generation += 1;
+    }
+
+    pub fn get_texture(&self, name: &str) -> &Texture {
+        match self.resources.get(name) {
+            Some(GraphResource::Transient { texture, .. }) => texture,
+            _ => panic!("RenderGraph::get_texture() error: {name} is not a transient texture!!")
+        }
+    }
+
+    pub fn get_view(&self, name: &str) -> &wgpu::TextureView {
+        &self.get_texture(name).view
+    }
+
+    pub fn get_surface_size(&self) -> (u32, u32) {
+        self.surface_size
+    }
+
+    pub fn get_render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Size the surface sized targets are allocated with. The scaled surface is shrunk to the
+    /// device's texture size limit, keeping its aspect ratio.
+    pub fn get_render_size(&self) -> (u32, u32) {
+        let width = self.surface_size.0 as f32 * self.render_scale;
+        let height = self.surface_size.1 as f32 * self.render_scale;
+        let fit = (self.max_texture_size as f32 / width.max(height)).min(1.0);
+
+        (
+            ((width * fit).round() as u32).clamp(1, self.max_texture_size),
+            ((height * fit).round() as u32).clamp(1, self.max_texture_size)
+        )
+    }
+
+    pub fn get_generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn allocate(device: &wgpu::Device, name: &str, desc: &TransientTextureDesc, render_size: (u32, u32)) -> Texture {
+        let size = match desc.size {
+            TextureSize::Surface => render_size,
+            TextureSize::Downscaled(n) => ((render_size.0 >> n).max(1), (render_size.1 >> n).max(1)),
+            TextureSize::Fixed(width, height) => (width, height)
+        };
+
+        if desc.format.is_depth_stencil_format() {
+            Texture::create_depth_texture(device, size, name, desc.format, desc.sample_count)
+        } else {
+            Texture::create_fbo(device, size, desc.format, desc.usage, desc.sample_count)
+        }
+    }
+}
+
+/// The dependency resolution behind `RenderGraph::compile`, kept apart from the textures so it runs without a device.
+/// `resources` maps every declared resource to whether it is imported. Returns the indices of the passes to run, in order.
+fn resolve_order(passes: &[GraphPassNode], resources: &HashMap<&'static str, bool>) -> Result<Vec<usize>, RenderGraphError> {
+    let mut dependencies: Vec<HashSet<usize>> = vec![HashSet::new(); passes.len()];
+
+    for pass in passes.iter() {
+        for resource in pass.reads.iter().chain(pass.writes.iter()) {
+            if !resources.contains_key(resource) {
+                return Err(RenderGraphError::UnknownResource { pass: format!("{:?}", pass.id), resource: resource.to_string() });
+            }
+        }
+    }
+
+    for (name, imported) in resources.iter() {
+        let writers: Vec<usize> = passes.iter().enumerate()
+            .filter(|(_, pass)| pass.writes.contains(name))
+            .map(|(index, _)| index)
+            .collect();
+
+        for pair in writers.windows(2) {
+            dependencies[pair[1]].insert(pair[0]);
+        }
+
+        for (index, pass) in passes.iter().enumerate() {
+            if !pass.reads.contains(name) || writers.contains(&index) {
+                continue;
+            }
+
+            if writers.is_empty() && !imported {
+                return Err(RenderGraphError::MissingWriter { pass: format!("{:?}", pass.id), resource: name.to_string() });
+            }
+
+            dependencies[index].extend(writers.iter().copied());
+        }
+    }
+
+    // passes that write outside the graph are kept, together with everything they depend on
+    let mut alive = vec![false; passes.len()];
+    let mut stack: Vec<usize> = passes.iter().enumerate()
+        .filter(|(_, pass)| pass.writes.iter().any(|name| resources.get(name) == Some(&true)))
+        .map(|(index, _)| index)
+        .collect();
+
+    while let Some(index) = stack.pop() {
+        if alive[index] {
+            continue;
+        }
+
+        alive[index] = true;
+        stack.extend(dependencies[index].iter().copied());
+    }
+
+    for (index, pass) in passes.iter().enumerate() {
+        if !alive[index] {
+            println!("RenderGraph::compile() warning: pass {:?} is never used and was culled", pass.id);
+        }
+    }
+
+    // kahn's algorithm, ties broken by declaration order so the result is stable
+    let mut remaining: Vec<usize> = dependencies.iter().map(|deps| deps.len()).collect();
+    let mut ready: BTreeSet<usize> = (0..passes.len()).filter(|index| remaining[*index] == 0).collect();
+    let mut order = Vec::with_capacity(passes.len());
+
+    while let Some(index) = ready.pop_first() {
+        order.push(index);
+
+        for (dependent, deps) in dependencies.iter().enumerate() {
+            if deps.contains(&index) {
+                remaining[dependent] -= 1;
+                if remaining[dependent] == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+    }
+
+    if order.len() != passes.len() {
+        return Err(RenderGraphError::Cycle);
+    }
+
+    Ok(order.into_iter().filter(|index| alive[*index]).collect())
+}
+
+/// Bind group over graph textures that is recreated whenever the graph reallocates them.
+pub struct GraphBindGroup {
+    generation: u64,
+    bind_group: Option<wgpu::BindGroup>
+}
+
+impl GraphBindGroup {
+    pub fn new() -> Self {
+        Self {
+            generation: 0,
+            bind_group: None
+        }
+    }
+
+    pub fn get(&mut self, device: &wgpu::Device, graph: &RenderGraph, layout: &wgpu::BindGroupLayout, textures: &[&str]) -> &wgpu::BindGroup {
+        self.get_with(graph, |graph| {
+            let textures: Vec<&Texture> = textures.iter().map(|name| graph.get_texture(name)).collect();
+            BindGroupManager::create_multi_texture_bind_group(device, layout, &textures).unwrap()
+        })
+    }
+
+    /// For bind groups that need more than the default view and sampler of the textures.
+    pub fn get_with(&mut self, graph: &RenderGraph, create: impl FnOnce(&RenderGraph) -> wgpu::BindGroup) -> &wgpu::BindGroup {
+        if self.generation != graph.get_generation() {
+            self.bind_group = None;
+        }
+
+        self.generation = graph.get_generation();
+        self.bind_group.get_or_insert_with(|| create(graph))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pass(id: RenderGraphPass, reads: &[&'static str], writes: &[&'static str]) -> GraphPassNode {
+        GraphPassNode {
+            id,
+            reads: reads.to_vec(),
+            writes: writes.to_vec()
+        }
+    }
+
+    fn resources(transient: &[&'static str], imported: &[&'static str]) -> HashMap<&'static str, bool> {
+        transient.iter().map(|name| (*name, false))
+            .chain(imported.iter().map(|name| (*name, true)))
+            .collect()
+    }
+
+    #[test]
+    fn orders_readers_after_writers() {
+        let passes = [
+            pass(RenderGraphPass::Forward, &["shadow_maps", "ssao"], &["swapchain"]),
+            pass(RenderGraphPass::Ssao, &["depth"], &["ssao"]),
+            pass(RenderGraphPass::Shadows, &[], &["shadow_maps", "depth"])
+        ];
+        let resources = resources(&["shadow_maps", "ssao", "depth"], &["swapchain"]);
+
+        assert_eq!(resolve_order(&passes, &resources).unwrap(), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn keeps_writers_in_declaration_order() {
+        let passes = [
+            pass(RenderGraphPass::PostProcess, &["hdr"], &["swapchain"]),
+            pass(RenderGraphPass::Forward, &[], &["hdr"]),
+            pass(RenderGraphPass::Outline, &[], &["hdr"])
+        ];
+        let resources = resources(&["hdr"], &["swapchain"]);
+
+        assert_eq!(resolve_order(&passes, &resources).unwrap(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn reports_cycle() {
+        let passes = [
+            pass(RenderGraphPass::Bloom, &["a"], &["b"]),
+            pass(RenderGraphPass::Exposure, &["b"], &["a", "swapchain"])
+        ];
+        let resources = resources(&["a", "b"], &["swapchain"]);
+
+        match resolve_order(&passes, &resources) {
+            Err(RenderGraphError::Cycle) => {}
+            other => panic!("expected Cycle, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn culls_unread_pass() {
+        let passes = [
+            pass(RenderGraphPass::Forward, &[], &["hdr"]),
+            pass(RenderGraphPass::Bloom, &["hdr"], &["bloom"]),
+            pass(RenderGraphPass::PostProcess, &["hdr"], &["swapchain"])
+        ];
+        let resources = resources(&["hdr", "bloom"], &["swapchain"]);
+
+        assert_eq!(resolve_order(&passes, &resources).unwrap(), vec![0, 2]);
+    }
+
+    #[test]
+    fn reports_missing_writer() {
+        let passes = [pass(RenderGraphPass::PostProcess, &["hdr"], &["swapchain"])];
+        let resources = resources(&["hdr"], &["swapchain"]);
+
+        match resolve_order(&passes, &resources) {
+            Err(RenderGraphError::MissingWriter { pass, resource }) => {
+                assert_eq!(pass, "PostProcess");
+                assert_eq!(resource, "hdr");
+            }
+            other => panic!("expected MissingWriter, got {:?}", other)
+        }
+    }
+}
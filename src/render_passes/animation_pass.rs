@@ -1,38 +1,36 @@
 use cgmath::SquareMatrix;
 use wgpu::util::DeviceExt;
 
-use crate::{animation::skin::MAX_JOINTS_PER_MESH, asset_manager::AssetManager, bind_group_manager::BindGroupManager, common::constants::{DEPTH_TEXTURE_STENCIL_FORMAT, HDR_TEX_FORMAT}, model::Model, objects::animated_game_object::AnimatedGameObject, pipeline_builder::PipelineBuilder, pipeline_manager::PipelineManager, uniform_manager::UniformManager, vertex::Vertex, wgpu_context::WgpuContext};
+use crate::{animation::skin::MAX_JOINTS_PER_MESH, asset_manager::AssetManager, frustum_culling::FrustumCuller, bind_group_manager::BindGroupManager, common::constants::{DEPTH_TEXTURE_STENCIL_FORMAT, HDR_TEX_FORMAT}, model::Model, objects::animated_game_object::AnimatedGameObject, pipeline_builder::PipelineBuilder, pipeline_manager::PipelineManager, uniform_manager::UniformManager, vertex::Vertex, wgpu_context::WgpuContext};
 
 pub struct AnimationPass {
     pipeline: wgpu::RenderPipeline,
+    texture_bg_layout: wgpu::BindGroupLayout
 }
 
 impl AnimationPass {
-    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager, asset_manager: &AssetManager) -> Self {
+    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager, asset_manager: &AssetManager, sample_count: u32) -> Self {
+        let texture_bind_group_layout = asset_manager.get_phong_bind_group_layout().expect("No bind group layout for Phong!");
+        let pipeline = Self::create_pipeline(ctx, uniforms, &texture_bind_group_layout, sample_count);
+
+        Self {
+          pipeline,
+          texture_bg_layout: texture_bind_group_layout.clone()
+        }
+    }
+
+    fn create_pipeline(ctx: &WgpuContext, uniforms: &UniformManager, texture_bind_group_layout: &wgpu::BindGroupLayout, sample_count: u32) -> wgpu::RenderPipeline {
         let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Instance_Shader"),
             source: wgpu::ShaderSource::Wgsl(include_str!("../../res/shaders/animation.wgsl").into()),
         });
 
-        let texture_bind_group_layout = asset_manager.get_phong_bind_group_layout().expect("No bind group layout for Phong!");
-
-        // let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        //     label: Some("animation_pipeline_layout"),
-        //     bind_group_layouts: &[
-        //         &texture_bind_group_layout,
-        //         &wgpu_uniforms.camera.bind_group_layout,
-        //         &wgpu_uniforms.bind_group_layout,
-        //         &wgpu_uniforms.animation.bind_group_layout
-        //     ],
-        //     push_constant_ranges: &[],
-        // });
-
-         let pipeline = PipelineBuilder::new(
+        PipelineBuilder::new(
             "animation pipeline",
             &[
-              &texture_bind_group_layout,
+              texture_bind_group_layout,
               &uniforms.camera.bind_group_layout,
-              &uniforms.bind_group_layout,
+              &uniforms.models.bind_group_layout,
               &uniforms.animation.bind_group_layout
             ],
             &[Vertex::desc()],
@@ -41,28 +39,36 @@ impl AnimationPass {
         )
         .with_depth(DEPTH_TEXTURE_STENCIL_FORMAT)
         .with_depth_write()
-        .build(&ctx.device);
-
+        .with_sample_count(sample_count)
+        .build(&ctx.device)
+    }
 
-        Self {
-          pipeline
-        }
+    pub fn set_sample_count(&mut self, ctx: &WgpuContext, uniforms: &UniformManager, sample_count: u32) {
+        self.pipeline = Self::create_pipeline(ctx, uniforms, &self.texture_bg_layout, sample_count);
     }
 
-    pub fn render(&self, render_pass: &mut wgpu::RenderPass, uniforms: &UniformManager, asset_manager: &AssetManager, animated_game_objects: &Vec<AnimatedGameObject>) {
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass, uniforms: &UniformManager, asset_manager: &AssetManager, animated_game_objects: &Vec<AnimatedGameObject>, culler: &FrustumCuller) {
         render_pass.set_pipeline(&self.pipeline);
 
         for animated_game_object in animated_game_objects.iter() {
-          let Some(model_uniform) = uniforms.models.get(&animated_game_object.object_id) else {
+          if !culler.is_object_visible(animated_game_object.object_id) {
+            continue;
+          }
+
+          let Some((model_offset, skin_offset)) = uniforms.models.get_offset(animated_game_object.object_id).zip(uniforms.animation.get_offset(animated_game_object.object_id)) else {
             println!("No model bind group for object {:?}, skipping draw", &animated_game_object.object_id);
-            return
+            continue;
           };
           render_pass.set_bind_group(1, &uniforms.camera.bind_group, &[]);
-          render_pass.set_bind_group(2, &model_uniform.bind_group, &[]);
-          render_pass.set_bind_group(3, &uniforms.animation.bind_group, &[]);
+          render_pass.set_bind_group(2, &uniforms.models.bind_group, &[model_offset]);
+          render_pass.set_bind_group(3, &uniforms.animation.bind_group, &[skin_offset]);
 
           if let Some(model) = asset_manager.get_model_by_name(&animated_game_object.get_model_name()) {
-           for mesh in &model.meshes {
+           for (mesh_index, mesh) in model.meshes.iter().enumerate() {
+             if !culler.is_mesh_visible(animated_game_object.object_id, mesh_index) {
+               continue;
+             }
+
              let mesh_material_index = animated_game_object.get_mesh_nodes().get_mesh_material_index_by_mesh_name(&mesh.name);
              let mesh_material = asset_manager.get_material_by_index(mesh_material_index);
 
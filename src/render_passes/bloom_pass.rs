@@ -0,0 +1,172 @@
+use crate::{bind_group_manager::{BindGroupManager, TL}, common::constants::{BLOOM_MIP_COUNT, GRAPH_BLOOM_MIPS, GRAPH_EMISSIVE, GRAPH_HDR, HDR_TEX_FORMAT}, pipeline_builder::PipelineBuilder, render_graph::{GraphBindGroup, RenderGraph, TextureSize, TransientTextureDesc}, uniform_manager::UniformManager, wgpu_context::WgpuContext};
+
+/// Runtime tunable bloom parameters, edited from the render settings window.
+#[derive(Debug, Clone, Copy)]
+pub struct BloomSettings {
+    pub enabled: bool,
+    /// Blooms only the meshes flagged emissive instead of everything above the threshold.
+    pub emissive_only: bool,
+    pub threshold: f32,
+    /// Fraction of the threshold over which the cutoff fades in.
+    pub knee: f32,
+    pub intensity: f32,
+    /// Upsample filter radius in texels of each mip.
+    pub radius: f32
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            emissive_only: false,
+            threshold: 1.0,
+            knee: 0.5,
+            intensity: 1.0,
+            radius: 1.0
+        }
+    }
+}
+
+const ADDITIVE_BLENDING: wgpu::BlendState = wgpu::BlendState {
+    color: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    },
+    alpha: wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    },
+};
+
+/// Downsamples the HDR (or emissive) buffer through a chain of half sized targets and upsamples back,
+/// adding every level on the way up. The result ends up in the first mip which the post process pass composites.
+pub struct BloomPass {
+    pub settings: BloomSettings,
+    downsample_pipeline: wgpu::RenderPipeline,
+    upsample_pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    hdr_bind_group: GraphBindGroup,
+    emissive_bind_group: GraphBindGroup,
+    mip_bind_groups: Vec<GraphBindGroup>
+}
+
+impl BloomPass {
+    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager) -> Self {
+        let bind_group_layout = BindGroupManager::create_texture_bind_group_layout(&ctx.device, [TL::Float]).unwrap();
+        let (downsample_pipeline, upsample_pipeline) = Self::create_pipelines(ctx, uniforms, &bind_group_layout);
+
+        Self {
+            settings: BloomSettings::default(),
+            downsample_pipeline,
+            upsample_pipeline,
+            bind_group_layout,
+            hdr_bind_group: GraphBindGroup::new(),
+            emissive_bind_group: GraphBindGroup::new(),
+            mip_bind_groups: (0..BLOOM_MIP_COUNT).map(|_| GraphBindGroup::new()).collect()
+        }
+    }
+
+    pub fn register_textures(ctx: &WgpuContext, graph: &mut RenderGraph) {
+        for (index, name) in GRAPH_BLOOM_MIPS.iter().enumerate() {
+            graph.add_texture(&ctx.device, name, TransientTextureDesc::render_target(HDR_TEX_FORMAT).with_size(TextureSize::Downscaled(index as u32 + 1)));
+        }
+    }
+
+    pub fn get_final_texture(&self) -> &'static str {
+        GRAPH_BLOOM_MIPS[0]
+    }
+
+    fn create_pipelines(ctx: &WgpuContext, uniforms: &UniformManager, bind_group_layout: &wgpu::BindGroupLayout) -> (wgpu::RenderPipeline, wgpu::RenderPipeline) {
+        let shader_code = std::fs::read_to_string("res/shaders/bloom_chain.wgsl").unwrap();
+        let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Bloom_Chain_Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_code.into()),
+        });
+
+        let downsample_pipeline = PipelineBuilder::new(
+            "bloom downsample pipeline",
+            &[bind_group_layout, &uniforms.bloom.bind_group_layout],
+            &[],
+            &shader_module,
+            [HDR_TEX_FORMAT],
+        )
+        .with_blend(wgpu::BlendState::REPLACE)
+        .build(&ctx.device);
+
+        let upsample_pipeline = PipelineBuilder::new(
+            "bloom upsample pipeline",
+            &[bind_group_layout, &uniforms.bloom.bind_group_layout],
+            &[],
+            &shader_module,
+            [HDR_TEX_FORMAT],
+        )
+        .with_blend(ADDITIVE_BLENDING)
+        .build(&ctx.device);
+
+        (downsample_pipeline, upsample_pipeline)
+    }
+
+    pub fn render(&mut self, ctx: &WgpuContext, encoder: &mut wgpu::CommandEncoder, graph: &RenderGraph, uniforms: &UniformManager) {
+        // the post process pass scales the bloom by a zero intensity while disabled
+        if !self.settings.enabled {
+            return;
+        }
+
+        // the instance index picks the filter, 0 prefilters the source, 1 downsamples and 2 upsamples
+        for mip in 0..BLOOM_MIP_COUNT {
+            let (source_bind_group, mode) = if mip == 0 {
+                if self.settings.emissive_only {
+                    (self.emissive_bind_group.get(&ctx.device, graph, &self.bind_group_layout, &[GRAPH_EMISSIVE]), 0)
+                } else {
+                    (self.hdr_bind_group.get(&ctx.device, graph, &self.bind_group_layout, &[GRAPH_HDR]), 0)
+                }
+            } else {
+                (self.mip_bind_groups[mip - 1].get(&ctx.device, graph, &self.bind_group_layout, &[GRAPH_BLOOM_MIPS[mip - 1]]), 1)
+            };
+
+            let mut render_pass = begin_bloom_pass(encoder, graph.get_view(GRAPH_BLOOM_MIPS[mip]), wgpu::LoadOp::Clear(wgpu::Color::BLACK), "Bloom_Downsample_Pass");
+
+            render_pass.set_pipeline(&self.downsample_pipeline);
+            render_pass.set_bind_group(0, source_bind_group, &[]);
+            render_pass.set_bind_group(1, &uniforms.bloom.bind_group, &[]);
+            render_pass.draw(0..3, mode..mode + 1);
+        }
+
+        for mip in (0..BLOOM_MIP_COUNT - 1).rev() {
+            let source_bind_group = self.mip_bind_groups[mip + 1].get(&ctx.device, graph, &self.bind_group_layout, &[GRAPH_BLOOM_MIPS[mip + 1]]);
+
+            let mut render_pass = begin_bloom_pass(encoder, graph.get_view(GRAPH_BLOOM_MIPS[mip]), wgpu::LoadOp::Load, "Bloom_Upsample_Pass");
+
+            render_pass.set_pipeline(&self.upsample_pipeline);
+            render_pass.set_bind_group(0, source_bind_group, &[]);
+            render_pass.set_bind_group(1, &uniforms.bloom.bind_group, &[]);
+            render_pass.draw(0..3, 2..3);
+        }
+    }
+
+    pub fn hotload_shader(&mut self, ctx: &WgpuContext, uniforms: &UniformManager) {
+        let (downsample_pipeline, upsample_pipeline) = Self::create_pipelines(ctx, uniforms, &self.bind_group_layout);
+
+        self.downsample_pipeline = downsample_pipeline;
+        self.upsample_pipeline = upsample_pipeline;
+    }
+}
+
+fn begin_bloom_pass<'a>(encoder: &'a mut wgpu::CommandEncoder, view: &'a wgpu::TextureView, load: wgpu::LoadOp<wgpu::Color>, label: &'static str) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    })
+}
@@ -0,0 +1,126 @@
+use std::ops::Range;
+
+use crate::{common::constants::{DEPTH_TEXTURE_STENCIL_FORMAT, HDR_TEX_FORMAT}, debug_draw::{DebugDraw, DebugVertex}, pipeline_builder::PipelineBuilder, uniform_manager::UniformManager, wgpu_context::WgpuContext};
+
+/// Renders the lines queued in the debug draw into the HDR target, inside the forward pass.
+pub struct DebugPass {
+    pipeline: wgpu::RenderPipeline,
+    on_top_pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    capacity: usize,
+    depth_tested: Range<u32>,
+    on_top: Range<u32>,
+    sample_count: u32
+}
+
+impl DebugPass {
+    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager, sample_count: u32) -> Self {
+        let (pipeline, on_top_pipeline) = Self::create_pipelines(ctx, uniforms, sample_count);
+        let capacity = 1024;
+
+        Self {
+            pipeline,
+            on_top_pipeline,
+            vertex_buffer: Self::create_buffer(&ctx.device, capacity),
+            capacity,
+            depth_tested: 0..0,
+            on_top: 0..0,
+            sample_count
+        }
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug_Vertex_Buffer"),
+            size: (capacity * std::mem::size_of::<DebugVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_pipelines(ctx: &WgpuContext, uniforms: &UniformManager, sample_count: u32) -> (wgpu::RenderPipeline, wgpu::RenderPipeline) {
+        let shader_code = std::fs::read_to_string("res/shaders/debug.wgsl").unwrap();
+        let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Debug_Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_code.into()),
+        });
+
+        let layouts = [&uniforms.camera.bind_group_layout];
+        let vertex_buffers = [DebugVertex::desc()];
+
+        let pipeline = PipelineBuilder::new(
+            "debug pipeline",
+            &layouts,
+            &vertex_buffers,
+            &shader_module,
+            [HDR_TEX_FORMAT, HDR_TEX_FORMAT],
+        )
+        .with_depth(DEPTH_TEXTURE_STENCIL_FORMAT)
+        .with_topology(wgpu::PrimitiveTopology::LineList)
+        .with_sample_count(sample_count)
+        .build(&ctx.device);
+
+        let on_top_pipeline = PipelineBuilder::new(
+            "debug on top pipeline",
+            &layouts,
+            &vertex_buffers,
+            &shader_module,
+            [HDR_TEX_FORMAT, HDR_TEX_FORMAT],
+        )
+        .with_depth(DEPTH_TEXTURE_STENCIL_FORMAT)
+        .with_depth_compare(wgpu::CompareFunction::Always)
+        .with_topology(wgpu::PrimitiveTopology::LineList)
+        .with_sample_count(sample_count)
+        .build(&ctx.device);
+
+        (pipeline, on_top_pipeline)
+    }
+
+    /// Uploads the lines queued this frame.
+    pub fn prepare(&mut self, ctx: &WgpuContext, debug_draw: &DebugDraw) {
+        let (vertices, depth_tested_count) = debug_draw.get_vertices();
+
+        if vertices.len() > self.capacity {
+            self.capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = Self::create_buffer(&ctx.device, self.capacity);
+        }
+
+        if !vertices.is_empty() {
+            ctx.queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        }
+
+        self.depth_tested = 0..depth_tested_count as u32;
+        self.on_top = depth_tested_count as u32..vertices.len() as u32;
+    }
+
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass, uniforms: &UniformManager) {
+        if self.depth_tested.is_empty() && self.on_top.is_empty() {
+            return;
+        }
+
+        render_pass.set_bind_group(0, &uniforms.camera.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+
+        if !self.depth_tested.is_empty() {
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.draw(self.depth_tested.clone(), 0..1);
+        }
+
+        if !self.on_top.is_empty() {
+            render_pass.set_pipeline(&self.on_top_pipeline);
+            render_pass.draw(self.on_top.clone(), 0..1);
+        }
+    }
+
+    pub fn hotload_shader(&mut self, ctx: &WgpuContext, uniforms: &UniformManager) {
+        let (pipeline, on_top_pipeline) = Self::create_pipelines(ctx, uniforms, self.sample_count);
+
+        self.pipeline = pipeline;
+        self.on_top_pipeline = on_top_pipeline;
+    }
+
+    pub fn set_sample_count(&mut self, ctx: &WgpuContext, uniforms: &UniformManager, sample_count: u32) {
+        self.sample_count = sample_count;
+        self.hotload_shader(ctx, uniforms);
+    }
+}
@@ -0,0 +1,154 @@
+use wgpu::util::DeviceExt;
+
+use crate::{bind_group_manager::{BindGroupManager, TL}, common::constants::{GRAPH_HDR, LUMINANCE_HISTOGRAM_BINS, LUMINANCE_HISTOGRAM_WORKGROUP_SIZE}, render_graph::{GraphBindGroup, RenderGraph}, uniform_manager::UniformManager, wgpu_context::WgpuContext};
+
+/// Auto exposure. Builds a luminance histogram of the HDR image in a compute pass and adapts
+/// the luminance the post process pass exposes for over time.
+pub struct ExposurePass {
+    histogram_pipeline: wgpu::ComputePipeline,
+    average_pipeline: wgpu::ComputePipeline,
+    pipeline_layout: wgpu::PipelineLayout,
+    hdr_bg_layout: wgpu::BindGroupLayout,
+    hdr_bind_group: GraphBindGroup,
+    storage_bind_group: wgpu::BindGroup,
+    /// Read only view of the adapted luminance for the post process pass.
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+    needs_reset: bool
+}
+
+impl ExposurePass {
+    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager) -> Self {
+        let histogram_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Luminance_Histogram_Buffer"),
+            size: (LUMINANCE_HISTOGRAM_BINS * std::mem::size_of::<u32>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false
+        });
+
+        let luminance_buffer = ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Adapted_Luminance_Buffer"),
+            contents: bytemuck::bytes_of(&1.0f32),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let storage_bg_layout = ctx.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Exposure_Storage_Bind_Group_Layout"),
+            entries: &[
+                storage_layout_entry(0, wgpu::ShaderStages::COMPUTE, false),
+                storage_layout_entry(1, wgpu::ShaderStages::COMPUTE, false)
+            ]
+        });
+
+        let storage_bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Exposure_Storage_Bind_Group"),
+            layout: &storage_bg_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: histogram_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: luminance_buffer.as_entire_binding() }
+            ]
+        });
+
+        let bind_group_layout = ctx.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Exposure_Bind_Group_Layout"),
+            entries: &[storage_layout_entry(0, wgpu::ShaderStages::FRAGMENT, true)]
+        });
+
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Exposure_Bind_Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry { binding: 0, resource: luminance_buffer.as_entire_binding() }]
+        });
+
+        let hdr_bg_layout = BindGroupManager::create_texture_bind_group_layout(&ctx.device, [TL::Float]).unwrap();
+
+        let pipeline_layout = ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Exposure_Pipeline_Layout"),
+            bind_group_layouts: &[&hdr_bg_layout, &storage_bg_layout, &uniforms.exposure.bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let (histogram_pipeline, average_pipeline) = Self::create_pipelines(ctx, &pipeline_layout);
+
+        Self {
+            histogram_pipeline,
+            average_pipeline,
+            pipeline_layout,
+            hdr_bg_layout,
+            hdr_bind_group: GraphBindGroup::new(),
+            storage_bind_group,
+            bind_group_layout,
+            bind_group,
+            needs_reset: true
+        }
+    }
+
+    fn create_pipelines(ctx: &WgpuContext, pipeline_layout: &wgpu::PipelineLayout) -> (wgpu::ComputePipeline, wgpu::ComputePipeline) {
+        let shader_code = std::fs::read_to_string("res/shaders/luminance_histogram.wgsl").unwrap();
+        let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Luminance_Histogram_Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_code.into()),
+        });
+
+        let create = |label: &str, entry_point: &str| ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(label),
+            layout: Some(pipeline_layout),
+            module: &shader_module,
+            entry_point: Some(entry_point),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        (create("luminance histogram pipeline", "cs_histogram"), create("luminance average pipeline", "cs_average"))
+    }
+
+    /// Whether the next frame snaps to the measured luminance instead of adapting, consumed by the caller.
+    pub fn take_reset(&mut self) -> bool {
+        std::mem::take(&mut self.needs_reset)
+    }
+
+    /// Call when the lighting changes abruptly (level switch, auto exposure toggled).
+    pub fn invalidate(&mut self) {
+        self.needs_reset = true;
+    }
+
+    pub fn render(&mut self, ctx: &WgpuContext, encoder: &mut wgpu::CommandEncoder, graph: &RenderGraph, uniforms: &UniformManager) {
+        let hdr_bind_group = self.hdr_bind_group.get(&ctx.device, graph, &self.hdr_bg_layout, &[GRAPH_HDR]);
+        let (width, height) = graph.get_render_size();
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Exposure_Pass"),
+            timestamp_writes: None,
+        });
+
+        compute_pass.set_bind_group(0, hdr_bind_group, &[]);
+        compute_pass.set_bind_group(1, &self.storage_bind_group, &[]);
+        compute_pass.set_bind_group(2, &uniforms.exposure.bind_group, &[]);
+
+        compute_pass.set_pipeline(&self.histogram_pipeline);
+        compute_pass.dispatch_workgroups(width.div_ceil(LUMINANCE_HISTOGRAM_WORKGROUP_SIZE), height.div_ceil(LUMINANCE_HISTOGRAM_WORKGROUP_SIZE), 1);
+
+        compute_pass.set_pipeline(&self.average_pipeline);
+        compute_pass.dispatch_workgroups(1, 1, 1);
+    }
+
+    pub fn hotload_shader(&mut self, ctx: &WgpuContext) {
+        let (histogram_pipeline, average_pipeline) = Self::create_pipelines(ctx, &self.pipeline_layout);
+
+        self.histogram_pipeline = histogram_pipeline;
+        self.average_pipeline = average_pipeline;
+    }
+}
+
+fn storage_layout_entry(binding: u32, visibility: wgpu::ShaderStages, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
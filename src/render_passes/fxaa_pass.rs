@@ -0,0 +1,71 @@
+use crate::{bind_group_manager::{BindGroupManager, TL}, common::constants::GRAPH_LDR, pipeline_builder::PipelineBuilder, render_graph::{GraphBindGroup, RenderGraph, TransientTextureDesc}, wgpu_context::WgpuContext};
+
+/// Smooths edges of the tonemapped image on its way to the swapchain.
+pub struct FxaaPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: GraphBindGroup
+}
+
+impl FxaaPass {
+    pub fn new(ctx: &WgpuContext) -> Self {
+        let bind_group_layout = BindGroupManager::create_texture_bind_group_layout(&ctx.device, [TL::Float]).unwrap();
+        let pipeline = Self::create_pipeline(ctx, &bind_group_layout);
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            bind_group: GraphBindGroup::new()
+        }
+    }
+
+    /// The post process pass writes here instead of the swapchain while FXAA is on.
+    pub fn register_textures(ctx: &WgpuContext, graph: &mut RenderGraph) {
+        graph.add_texture(&ctx.device, GRAPH_LDR, TransientTextureDesc::render_target(ctx.config.format.add_srgb_suffix()));
+    }
+
+    fn create_pipeline(ctx: &WgpuContext, bind_group_layout: &wgpu::BindGroupLayout) -> wgpu::RenderPipeline {
+        let shader_code = std::fs::read_to_string("res/shaders/fxaa.wgsl").unwrap();
+        let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("FXAA_Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_code.into()),
+        });
+
+        PipelineBuilder::new(
+            "fxaa pipeline",
+            &[bind_group_layout],
+            &[],
+            &shader_module,
+            [ctx.config.format.add_srgb_suffix()],
+        )
+        .with_blend(wgpu::BlendState::REPLACE)
+        .build(&ctx.device)
+    }
+
+    pub fn render(&mut self, ctx: &WgpuContext, encoder: &mut wgpu::CommandEncoder, swapchain_view: &wgpu::TextureView, graph: &RenderGraph) {
+        let bind_group = self.bind_group.get(&ctx.device, graph, &self.bind_group_layout, &[GRAPH_LDR]);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("FXAA_Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: swapchain_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    pub fn hotload_shader(&mut self, ctx: &WgpuContext) {
+        self.pipeline = Self::create_pipeline(ctx, &self.bind_group_layout);
+    }
+}
@@ -1,47 +1,97 @@
-use crate::{asset_manager::AssetManager, common::constants::{DEPTH_TEXTURE_STENCIL_FORMAT, HDR_TEX_FORMAT}, objects::game_object::GameObject, pipeline_builder::PipelineBuilder, pipeline_manager::PipelineManager, uniform_manager::UniformManager, vertex::Vertex, wgpu_context::WgpuContext};
+use std::{collections::HashMap, ops::Range};
 
-pub struct LightingPass {
-    stencil_pipeline: wgpu::RenderPipeline,
+use cgmath::MetricSpace;
+
+use crate::{asset_manager::AssetManager, frustum_culling::FrustumCuller, common::{constants::{DEPTH_TEXTURE_STENCIL_FORMAT, HDR_TEX_FORMAT}, enums::AlphaMode}, instance::{InstanceBuffer, InstanceUniform}, objects::game_object::GameObject, pipeline_builder::PipelineBuilder, uniform_manager::UniformManager, vertex::Vertex, wgpu_context::WgpuContext};
+
+#[derive(Hash, PartialEq, Eq, Clone)]
+struct BatchKey {
+    model_name: String,
+    mesh_index: usize,
+    material_index: usize,
+    selected: bool,
+    alpha_mode: AlphaMode
+}
+
+struct InstanceBatch {
+    key: BatchKey,
+    instances: Range<u32>
+}
+
+struct LightingPipelines {
     pipeline: wgpu::RenderPipeline,
-    texture_bg_layout: wgpu::BindGroupLayout
+    stencil_pipeline: wgpu::RenderPipeline,
+    transparent_pipeline: wgpu::RenderPipeline,
+    transparent_stencil_pipeline: wgpu::RenderPipeline
+}
+
+/// Draws the static objects. Opaque batches go first, then the alpha tested ones, and
+/// alpha blended meshes are drawn one by one back to front by `render_transparent`.
+pub struct LightingPass {
+    pipelines: LightingPipelines,
+    texture_bg_layout: wgpu::BindGroupLayout,
+    shadow_bg_layout: wgpu::BindGroupLayout,
+    environment_bg_layout: wgpu::BindGroupLayout,
+    ssao_bg_layout: wgpu::BindGroupLayout,
+    instance_buffer: InstanceBuffer,
+    batches: Vec<InstanceBatch>,
+    transparent_batches: Vec<InstanceBatch>,
+    sample_count: u32
 }
 
 impl LightingPass {
-    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager, asset_manager: &AssetManager) -> Self {
+    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager, asset_manager: &AssetManager, shadow_bind_group_layout: &wgpu::BindGroupLayout, environment_bind_group_layout: &wgpu::BindGroupLayout, ssao_bind_group_layout: &wgpu::BindGroupLayout, sample_count: u32) -> Self {
+        let texture_bind_group_layout = &asset_manager.get_material_by_name("Barrel_RED").unwrap().bind_group_layout;
+
+        let pipelines = Self::create_pipelines(ctx, uniforms, texture_bind_group_layout, shadow_bind_group_layout, environment_bind_group_layout, ssao_bind_group_layout, sample_count);
+
+     Self {
+        pipelines,
+        texture_bg_layout: texture_bind_group_layout.clone(),
+        shadow_bg_layout: shadow_bind_group_layout.clone(),
+        environment_bg_layout: environment_bind_group_layout.clone(),
+        ssao_bg_layout: ssao_bind_group_layout.clone(),
+        instance_buffer: InstanceBuffer::new(&ctx.device, 256),
+        batches: Vec::new(),
+        transparent_batches: Vec::new(),
+        sample_count
+     }
+    }
+
+    fn create_pipelines(ctx: &WgpuContext, uniforms: &UniformManager, texture_bind_group_layout: &wgpu::BindGroupLayout, shadow_bind_group_layout: &wgpu::BindGroupLayout, environment_bind_group_layout: &wgpu::BindGroupLayout, ssao_bind_group_layout: &wgpu::BindGroupLayout, sample_count: u32) -> LightingPipelines {
         let shader_code = std::fs::read_to_string("res/shaders/lighting.wgsl").unwrap();
         let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Lighting_Shader"),
             source: wgpu::ShaderSource::Wgsl(shader_code.into()),
         });
 
-        let texture_bind_group_layout = &asset_manager.get_material_by_name("Barrel_RED").unwrap().bind_group_layout;
+        let layouts = [
+          texture_bind_group_layout,
+          &uniforms.camera.bind_group_layout,
+          &uniforms.lights_ssbo.bind_group_layout,
+          shadow_bind_group_layout,
+          environment_bind_group_layout,
+          ssao_bind_group_layout
+        ];
+        let vertex_buffers = [Vertex::desc(), InstanceUniform::desc()];
 
-         let pipeline = PipelineBuilder::new(
+        let pipeline = PipelineBuilder::new(
             "lighting pipeline",
-            &[
-              &texture_bind_group_layout,
-              &uniforms.camera.bind_group_layout,
-              &uniforms.bind_group_layout,
-              &uniforms.lights_ssbo.bind_group_layout
-            ],
-            &[Vertex::desc()],
+            &layouts,
+            &vertex_buffers,
             &shader_module,
             [HDR_TEX_FORMAT, HDR_TEX_FORMAT],
         )
         .with_depth(DEPTH_TEXTURE_STENCIL_FORMAT)
         .with_depth_write()
+        .with_sample_count(sample_count)
         .build(&ctx.device);
 
         let write_stencil = true;
         let stencil_pipeline = PipelineBuilder::new(
             "lighting stencil pipeline",
-            &[
-              &texture_bind_group_layout,
-              &uniforms.camera.bind_group_layout,
-              &uniforms.bind_group_layout,
-              &uniforms.lights_ssbo.bind_group_layout
-            ],
-            &[Vertex::desc()],
+            &layouts,
+            &vertex_buffers,
             &shader_module,
             [HDR_TEX_FORMAT, HDR_TEX_FORMAT],
         )
@@ -49,95 +99,212 @@ impl LightingPass {
         .with_depth_write()
         .with_stencil_state(write_stencil)
         .with_blend(wgpu::BlendState::REPLACE)
+        .with_sample_count(sample_count)
         .build(&ctx.device);
 
-     Self {
-        stencil_pipeline,
-        pipeline,
-        texture_bg_layout: texture_bind_group_layout.clone()
-     }
+        // transparent meshes are depth tested against the opaque scene but don't occlude each other
+        let transparent_pipeline = PipelineBuilder::new(
+            "lighting transparent pipeline",
+            &layouts,
+            &vertex_buffers,
+            &shader_module,
+            [HDR_TEX_FORMAT, HDR_TEX_FORMAT],
+        )
+        .with_depth(DEPTH_TEXTURE_STENCIL_FORMAT)
+        .with_sample_count(sample_count)
+        .build(&ctx.device);
+
+        let transparent_stencil_pipeline = PipelineBuilder::new(
+            "lighting transparent stencil pipeline",
+            &layouts,
+            &vertex_buffers,
+            &shader_module,
+            [HDR_TEX_FORMAT, HDR_TEX_FORMAT],
+        )
+        .with_depth(DEPTH_TEXTURE_STENCIL_FORMAT)
+        .with_stencil_state(write_stencil)
+        .with_sample_count(sample_count)
+        .build(&ctx.device);
+
+        LightingPipelines {
+            pipeline,
+            stencil_pipeline,
+            transparent_pipeline,
+            transparent_stencil_pipeline
+        }
     }
 
-    pub fn render(&self, render_pass: &mut wgpu::RenderPass, uniforms: &UniformManager, asset_manager: &AssetManager, game_objects: &Vec<GameObject>) {
+    /// Groups the visible meshes by model, mesh and material and uploads one instance per object.
+    /// Alpha blended meshes get a batch each, sorted by their distance to `camera_position`.
+    pub fn prepare(&mut self, ctx: &WgpuContext, asset_manager: &AssetManager, game_objects: &Vec<GameObject>, culler: &FrustumCuller, camera_position: cgmath::Point3<f32>) {
+        let mut batch_indices: HashMap<BatchKey, usize> = HashMap::new();
+        let mut batch_instances: Vec<(BatchKey, Vec<InstanceUniform>)> = Vec::new();
+        let mut transparent_instances: Vec<(f32, BatchKey, InstanceUniform)> = Vec::new();
+
         for game_object in game_objects.iter() {
-          let Some(model_uniform) = uniforms.models.get(&game_object.id) else {
-            println!("No model bind group for object {:?}, skipping draw", game_object.id);
+          if !culler.is_object_visible(game_object.id) {
+            continue;
+          }
+
+          let Some(model) = asset_manager.get_model_by_name(&game_object.get_model_name()) else {
+            continue;
+          };
+
+          let model_matrix = game_object.get_model_matrix();
+          let instance = InstanceUniform::new(&model_matrix, &game_object.tex_scale);
+
+          for (mesh_index, mesh) in model.meshes.iter().enumerate() {
+            let mesh_node = game_object.get_mesh_nodes().get_mesh_rendering_info_by_mesh_name(&mesh.name);
+
+            if !culler.is_mesh_visible(game_object.id, mesh_index) || mesh_node.emissive {
+              continue;
+            }
+
+            let key = BatchKey {
+              model_name: model.name.clone(),
+              mesh_index,
+              material_index: mesh_node.material_index,
+              selected: game_object.is_selected,
+              alpha_mode: mesh_node.alpha_mode
+            };
+            let instance = instance.with_alpha_mode(mesh_node.alpha_mode, mesh_node.alpha_cutoff);
+
+            if mesh_node.alpha_mode == AlphaMode::Blend {
+              let center = mesh.aabb.transform(&model_matrix).get_center();
+              let distance = camera_position.distance2(cgmath::Point3::new(center.x, center.y, center.z));
+
+              transparent_instances.push((distance, key, instance));
+              continue;
+            }
+
+            let index = *batch_indices.entry(key.clone()).or_insert_with(|| {
+              batch_instances.push((key, Vec::new()));
+              batch_instances.len() - 1
+            });
+
+            batch_instances[index].1.push(instance);
+          }
+        }
+
+        // cut-outs after the opaque batches so early depth testing rejects most of their fragments
+        batch_instances.sort_by_key(|(key, _)| key.alpha_mode == AlphaMode::Mask);
+        transparent_instances.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut instances: Vec<InstanceUniform> = Vec::new();
+        self.batches.clear();
+        self.transparent_batches.clear();
+
+        for (key, batch) in batch_instances {
+          let start = instances.len() as u32;
+          instances.extend(batch);
+
+          self.batches.push(InstanceBatch {
+            key,
+            instances: start..instances.len() as u32
+          });
+        }
+
+        for (_, key, instance) in transparent_instances {
+          let start = instances.len() as u32;
+          instances.push(instance);
+
+          self.transparent_batches.push(InstanceBatch {
+            key,
+            instances: start..start + 1
+          });
+        }
+
+        self.instance_buffer.update(ctx, &instances);
+    }
+
+    /// Opaque and alpha tested batches.
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass, uniforms: &UniformManager, asset_manager: &AssetManager, shadow_bind_group: &wgpu::BindGroup, environment_bind_group: &wgpu::BindGroup, ssao_bind_group: &wgpu::BindGroup) {
+        self.render_batches(render_pass, uniforms, asset_manager, shadow_bind_group, environment_bind_group, ssao_bind_group, &self.batches, &self.pipelines.pipeline, &self.pipelines.stencil_pipeline);
+    }
+
+    /// Alpha blended meshes back to front, call after everything opaque including the skybox.
+    pub fn render_transparent(&self, render_pass: &mut wgpu::RenderPass, uniforms: &UniformManager, asset_manager: &AssetManager, shadow_bind_group: &wgpu::BindGroup, environment_bind_group: &wgpu::BindGroup, ssao_bind_group: &wgpu::BindGroup) {
+        self.render_batches(render_pass, uniforms, asset_manager, shadow_bind_group, environment_bind_group, ssao_bind_group, &self.transparent_batches, &self.pipelines.transparent_pipeline, &self.pipelines.transparent_stencil_pipeline);
+    }
+
+    fn render_batches(&self, render_pass: &mut wgpu::RenderPass, uniforms: &UniformManager, asset_manager: &AssetManager, shadow_bind_group: &wgpu::BindGroup, environment_bind_group: &wgpu::BindGroup, ssao_bind_group: &wgpu::BindGroup, batches: &[InstanceBatch], pipeline: &wgpu::RenderPipeline, stencil_pipeline: &wgpu::RenderPipeline) {
+        render_pass.set_bind_group(1, &uniforms.camera.bind_group, &[]);
+        render_pass.set_bind_group(2, &uniforms.lights_ssbo.bind_group, &[]);
+        render_pass.set_bind_group(3, shadow_bind_group, &[]);
+        render_pass.set_bind_group(4, environment_bind_group, &[]);
+        render_pass.set_bind_group(5, ssao_bind_group, &[]);
+        render_pass.set_vertex_buffer(1, self.instance_buffer.buffer.slice(..));
+
+        for batch in batches.iter() {
+          let Some(model) = asset_manager.get_model_by_name(&batch.key.model_name) else {
             continue;
           };
+          let Some(mesh_material) = asset_manager.get_material_by_index(batch.key.material_index) else {
+            continue;
+          };
+
+          let mesh = &model.meshes[batch.key.mesh_index];
 
-          if game_object.is_selected {
-            render_pass.set_pipeline(&self.stencil_pipeline);
+          if batch.key.selected {
+            render_pass.set_pipeline(stencil_pipeline);
           } else {
-            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_pipeline(pipeline);
           }
 
-          render_pass.set_bind_group(1, &uniforms.camera.bind_group, &[]);
-          render_pass.set_bind_group(2, &model_uniform.bind_group, &[]);
-          render_pass.set_bind_group(3, &uniforms.lights_ssbo.bind_group, &[]);
+          render_pass.set_bind_group(0, &mesh_material.bind_group, &[]);
 
-          if let Some(model) = asset_manager.get_model_by_name(&game_object.get_model_name()) {
-            for mesh in &model.meshes {
-                if game_object.get_mesh_nodes().get_mesh_rendering_info_by_mesh_name(&mesh.name).emissive {
-                  continue;
-                }
+          render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+          render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+          render_pass.set_stencil_reference(1);
+          render_pass.draw_indexed(0..mesh.num_elements, 0, batch.instances.clone());
+        }
+    }
+
+    /// Draws the prepared opaque and alpha tested batches for depth only passes, alpha blended meshes are left out.
+    /// Expects the caller's opaque pipeline to be set and switches to `masked_pipeline` for the alpha tested
+    /// batches, which get their material at group 1.
+    pub fn render_depth(&self, render_pass: &mut wgpu::RenderPass, asset_manager: &AssetManager, masked_pipeline: &wgpu::RenderPipeline) {
+        render_pass.set_vertex_buffer(1, self.instance_buffer.buffer.slice(..));
+
+        let mut masked = false;
+
+        for batch in self.batches.iter() {
+          let Some(model) = asset_manager.get_model_by_name(&batch.key.model_name) else {
+            continue;
+          };
 
-                let mesh_material_index = game_object.get_mesh_nodes().get_mesh_material_index_by_mesh_name(&mesh.name);
-                let mesh_material = asset_manager.get_material_by_index(mesh_material_index);
+          let mesh = &model.meshes[batch.key.mesh_index];
 
-                render_pass.set_bind_group(0, &mesh_material.unwrap().bind_group, &[]);
+          // the alpha tested batches are sorted after the opaque ones
+          if batch.key.alpha_mode == AlphaMode::Mask {
+            let Some(mesh_material) = asset_manager.get_material_by_index(batch.key.material_index) else {
+              continue;
+            };
 
-                render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-                render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-                render_pass.set_stencil_reference(1);
-                render_pass.draw_indexed(0..mesh.num_elements, 0, 0..1);
+            if !masked {
+              render_pass.set_pipeline(masked_pipeline);
+              masked = true;
             }
+
+            render_pass.set_bind_group(1, &mesh_material.bind_group, &[]);
           }
+
+          render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+          render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+          render_pass.draw_indexed(0..mesh.num_elements, 0, batch.instances.clone());
         }
     }
 
-    pub fn hotload_shader(&mut self, ctx: &WgpuContext, uniforms: &UniformManager) {
-      let shader_code = std::fs::read_to_string("res/shaders/lighting.wgsl").unwrap();
-      let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Lighting_Shader"),
-            source: wgpu::ShaderSource::Wgsl(shader_code.into()),
-       });
-
-       let pipeline = PipelineBuilder::new(
-            "lighting pipeline",
-            &[
-              &self.texture_bg_layout,
-              &uniforms.camera.bind_group_layout,
-              &uniforms.bind_group_layout,
-              &uniforms.lights_ssbo.bind_group_layout
-            ],
-            &[Vertex::desc()],
-            &shader_module,
-            [HDR_TEX_FORMAT, HDR_TEX_FORMAT],
-        )
-        .with_depth(DEPTH_TEXTURE_STENCIL_FORMAT)
-        .with_depth_write()
-        .build(&ctx.device);
+    pub fn get_draw_call_count(&self) -> usize {
+        self.batches.len() + self.transparent_batches.len()
+    }
 
-      let write_stencil = true;
-      let stencil_pipeline = PipelineBuilder::new(
-            "lighting stencil pipeline",
-            &[
-              &self.texture_bg_layout,
-              &uniforms.camera.bind_group_layout,
-              &uniforms.bind_group_layout,
-              &uniforms.lights_ssbo.bind_group_layout
-            ],
-            &[Vertex::desc()],
-            &shader_module,
-            [HDR_TEX_FORMAT, HDR_TEX_FORMAT],
-        )
-        .with_depth(DEPTH_TEXTURE_STENCIL_FORMAT)
-        .with_depth_write()
-        .with_stencil_state(write_stencil)
-        .with_blend(wgpu::BlendState::REPLACE)
-        .build(&ctx.device);
+    pub fn hotload_shader(&mut self, ctx: &WgpuContext, uniforms: &UniformManager) {
+      self.pipelines = Self::create_pipelines(ctx, uniforms, &self.texture_bg_layout, &self.shadow_bg_layout, &self.environment_bg_layout, &self.ssao_bg_layout, self.sample_count);
+    }
 
-      self.pipeline = pipeline;
-      self.stencil_pipeline = stencil_pipeline;
+    pub fn set_sample_count(&mut self, ctx: &WgpuContext, uniforms: &UniformManager, sample_count: u32) {
+      self.sample_count = sample_count;
+      self.hotload_shader(ctx, uniforms);
     }
-}
\ No newline at end of file
+}
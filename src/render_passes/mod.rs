@@ -3,4 +3,13 @@ pub mod animation_pass;
 pub mod skybox_pass;
 pub mod lighting_pass;
 pub mod outline_pass;
-pub mod emissive_pass;
\ No newline at end of file
+pub mod emissive_pass;
+pub mod shadow_pass;
+pub mod ssao_pass;
+pub mod fxaa_pass;
+pub mod taa_pass;
+pub mod bloom_pass;
+pub mod exposure_pass;
+pub mod post_effect_pass;
+pub mod debug_pass;
+pub mod picking_pass;
\ No newline at end of file
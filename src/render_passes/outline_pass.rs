@@ -1,12 +1,13 @@
-use crate::{asset_manager::AssetManager, bind_group_manager::{BindGroupManager, TL}, common::constants::{DEPTH_TEXTURE_STENCIL_FORMAT, HDR_TEX_FORMAT}, objects::game_object::GameObject, pipeline_builder::PipelineBuilder, pipeline_manager::PipelineManager, texture, uniform_manager::UniformManager, vertex::Vertex, wgpu_context::WgpuContext};
+use crate::{asset_manager::AssetManager, frustum_culling::FrustumCuller, bind_group_manager::{BindGroupManager, TL}, common::constants::{DEPTH_TEXTURE_STENCIL_FORMAT, HDR_TEX_FORMAT}, objects::game_object::GameObject, pipeline_builder::PipelineBuilder, pipeline_manager::PipelineManager, texture, uniform_manager::UniformManager, vertex::Vertex, wgpu_context::WgpuContext};
 
 pub struct OutlinePass {
     //pipeline_layout: wgpu::PipelineLayout,
     pipeline: wgpu::RenderPipeline,
+    sample_count: u32
 }
 
 impl OutlinePass {
-    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager) -> Self {
+    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager, sample_count: u32) -> Self {
         let shader_code = std::fs::read_to_string("res/shaders/outline.wgsl").unwrap();
         let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Outline_Shader"),
@@ -36,37 +37,32 @@ impl OutlinePass {
 
         let pipeline = PipelineBuilder::new(
             "outline pipeline",
-            &[&uniforms.camera.bind_group_layout, &uniforms.bind_group_layout],
+            &[&uniforms.camera.bind_group_layout, &uniforms.models.bind_group_layout],
             &[Vertex::desc()],
             &shader_module,
-            [HDR_TEX_FORMAT, HDR_TEX_FORMAT],
+            [HDR_TEX_FORMAT],
         )
         .with_depth(DEPTH_TEXTURE_STENCIL_FORMAT)
         .with_stencil_state(write_stencil)
         .with_blend(wgpu::BlendState::REPLACE)
+        .with_sample_count(sample_count)
         .build(&ctx.device);
 
         Self {
             //pipeline_layout,
             pipeline,
+            sample_count
         }
     }
 
-    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, out_texture_view: &wgpu::TextureView, depth_texture_view: &wgpu::TextureView, uniforms: &UniformManager, game_objects: &Vec<GameObject>, asset_manager: &AssetManager) {
+    /// With MSAA `out_texture_view` is the multisampled target and `resolve_target` the HDR texture.
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, out_texture_view: &wgpu::TextureView, resolve_target: Option<&wgpu::TextureView>, depth_texture_view: &wgpu::TextureView, uniforms: &UniformManager, game_objects: &Vec<GameObject>, asset_manager: &AssetManager, culler: &FrustumCuller) {
          let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Outline_Pass"),
             color_attachments: &[
               Some(wgpu::RenderPassColorAttachment {
                 view: &out_texture_view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Load,
-                    store: wgpu::StoreOp::Store,
-                },
-            }),
-              Some(wgpu::RenderPassColorAttachment {
-                view: &out_texture_view,
-                resolve_target: None,
+                resolve_target,
                 ops: wgpu::Operations {
                     load: wgpu::LoadOp::Load,
                     store: wgpu::StoreOp::Store,
@@ -88,16 +84,20 @@ impl OutlinePass {
         render_pass.set_bind_group(0, &uniforms.camera.bind_group, &[]);
 
         for game_object in game_objects.iter() {
-           if game_object.is_selected {
-            let Some(model_uniform) = uniforms.models.get(&game_object.id) else {
+           if game_object.is_selected && culler.is_object_visible(game_object.id) {
+            let Some(model_offset) = uniforms.models.get_offset(game_object.id) else {
              println!("No model bind group for object {:?}, skipping draw", game_object.id);
              continue;
             };
 
-            render_pass.set_bind_group(1, &model_uniform.bind_group, &[]);
+            render_pass.set_bind_group(1, &uniforms.models.bind_group, &[model_offset]);
 
             if let Some(model) = asset_manager.get_model_by_name(&game_object.get_model_name()) {
-             for mesh in model.meshes.iter() {
+             for (mesh_index, mesh) in model.meshes.iter().enumerate() {
+                if !culler.is_mesh_visible(game_object.id, mesh_index) {
+                  continue;
+                }
+
                 render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
                 render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
                 render_pass.set_stencil_reference(1);
@@ -120,14 +120,15 @@ impl OutlinePass {
 
     let new_pipeline = PipelineBuilder::new(
             "outline pipeline",
-            &[&uniforms.camera.bind_group_layout, &uniforms.bind_group_layout],
+            &[&uniforms.camera.bind_group_layout, &uniforms.models.bind_group_layout],
             &[Vertex::desc()],
             &shader_module,
-            [HDR_TEX_FORMAT, HDR_TEX_FORMAT],
+            [HDR_TEX_FORMAT],
         )
         .with_depth(DEPTH_TEXTURE_STENCIL_FORMAT)
         .with_stencil_state(write_stencil)
         .with_blend(wgpu::BlendState::REPLACE)
+        .with_sample_count(self.sample_count)
         .build(&ctx.device);
 
     self.pipeline = new_pipeline;
@@ -137,4 +138,9 @@ impl OutlinePass {
     //       Err(err) => println!("PostProcessPass::hotload_shader() error: {err}")
     //   }
     }
+
+    pub fn set_sample_count(&mut self, ctx: &WgpuContext, uniforms: &UniformManager, sample_count: u32) {
+      self.sample_count = sample_count;
+      self.hotload_shader(ctx, uniforms);
+    }
 }
\ No newline at end of file
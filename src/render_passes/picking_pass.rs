@@ -0,0 +1,267 @@
+use crate::{asset_manager::AssetManager, common::constants::{DEPTH_TEXTURE_FORMAT, GRAPH_OBJECT_ID, GRAPH_OBJECT_ID_DEPTH, OBJECT_ID_FORMAT}, pipeline_builder::PipelineBuilder, render_graph::{RenderGraph, TransientTextureDesc}, scene::Scene, uniform_manager::UniformManager, vertex::Vertex, wgpu_context::WgpuContext};
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightIcon {
+    position: [f32; 3],
+    id: u32
+}
+
+impl LightIcon {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Uint32
+        ];
+
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<LightIcon>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PickRequest {
+    /// Cursor position in surface pixels.
+    pub x: f64,
+    pub y: f64,
+    /// Shift click, adds to the selection instead of replacing it.
+    pub additive: bool
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PickResult {
+    /// `GameObject::id`, `AnimatedGameObject::object_id` or `LightObject::id`, `None` for empty space.
+    pub id: Option<usize>,
+    pub additive: bool
+}
+
+/// Editor picking. On request renders the ids of the objects and light icons into an integer target
+/// and copies back the pixel under the cursor.
+pub struct PickingPass {
+    pipeline: wgpu::RenderPipeline,
+    icon_pipeline: wgpu::RenderPipeline,
+    icon_buffer: wgpu::Buffer,
+    icon_capacity: usize,
+    readback_buffer: wgpu::Buffer,
+    pending: Option<PickRequest>,
+    recorded: Option<PickRequest>
+}
+
+impl PickingPass {
+    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager) -> Self {
+        let (pipeline, icon_pipeline) = Self::create_pipelines(ctx, uniforms);
+        let icon_capacity = 16;
+
+        let readback_buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Picking_Readback_Buffer"),
+            size: std::mem::size_of::<u32>() as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            icon_pipeline,
+            icon_buffer: Self::create_icon_buffer(&ctx.device, icon_capacity),
+            icon_capacity,
+            readback_buffer,
+            pending: None,
+            recorded: None
+        }
+    }
+
+    pub fn register_textures(ctx: &WgpuContext, graph: &mut RenderGraph) {
+        graph.add_texture(&ctx.device, GRAPH_OBJECT_ID, TransientTextureDesc::render_target(OBJECT_ID_FORMAT).with_usage(wgpu::TextureUsages::COPY_SRC));
+        graph.add_texture(&ctx.device, GRAPH_OBJECT_ID_DEPTH, TransientTextureDesc::render_target(DEPTH_TEXTURE_FORMAT));
+    }
+
+    fn create_icon_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Light_Icon_Buffer"),
+            size: (capacity * std::mem::size_of::<LightIcon>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_pipelines(ctx: &WgpuContext, uniforms: &UniformManager) -> (wgpu::RenderPipeline, wgpu::RenderPipeline) {
+        let shader_code = std::fs::read_to_string("res/shaders/object_id.wgsl").unwrap();
+        let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Object_Id_Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_code.into()),
+        });
+
+        let pipeline = PipelineBuilder::new(
+            "object id pipeline",
+            &[&uniforms.camera.bind_group_layout, &uniforms.models.bind_group_layout, &uniforms.animation.bind_group_layout],
+            &[Vertex::desc()],
+            &shader_module,
+            [OBJECT_ID_FORMAT],
+        )
+        .with_depth(DEPTH_TEXTURE_FORMAT)
+        .with_depth_write()
+        .without_blend()
+        .build(&ctx.device);
+
+        let icon_pipeline = PipelineBuilder::new(
+            "light icon id pipeline",
+            &[&uniforms.camera.bind_group_layout],
+            &[LightIcon::desc()],
+            &shader_module,
+            [OBJECT_ID_FORMAT],
+        )
+        .with_depth(DEPTH_TEXTURE_FORMAT)
+        .with_depth_write()
+        .without_blend()
+        .with_vertex_entry_point("vs_icon")
+        .build(&ctx.device);
+
+        (pipeline, icon_pipeline)
+    }
+
+    /// Picks at the cursor on the next rendered frame, replaces a request that wasn't rendered yet.
+    pub fn request(&mut self, request: PickRequest) {
+        self.pending = Some(request);
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn render(&mut self, ctx: &WgpuContext, encoder: &mut wgpu::CommandEncoder, graph: &RenderGraph, uniforms: &UniformManager, asset_manager: &AssetManager, scene: &Scene) {
+        let Some(request) = self.pending.take() else {
+            return;
+        };
+
+        // ids are offset by one so a cleared pixel reads as nothing
+        let icons: Vec<LightIcon> = scene.lights.iter()
+            .map(|light| LightIcon { position: light.get_world_position().into(), id: light.id as u32 + 1 })
+            .collect();
+
+        if icons.len() > self.icon_capacity {
+            self.icon_capacity = icons.len().next_power_of_two();
+            self.icon_buffer = Self::create_icon_buffer(&ctx.device, self.icon_capacity);
+        }
+
+        if !icons.is_empty() {
+            ctx.queue.write_buffer(&self.icon_buffer, 0, bytemuck::cast_slice(&icons));
+        }
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Picking_Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: graph.get_view(GRAPH_OBJECT_ID),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: graph.get_view(GRAPH_OBJECT_ID_DEPTH),
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &uniforms.camera.bind_group, &[]);
+
+            let objects = scene.game_objects.iter().map(|game_object| (game_object.id, game_object.get_model_name()))
+                .chain(scene.animated_game_objects.iter().map(|animated_game_object| (animated_game_object.object_id, animated_game_object.get_model_name())));
+
+            for (id, model_name) in objects {
+                let Some((model, model_offset)) = asset_manager.get_model_by_name(model_name).zip(uniforms.models.get_offset(id)) else {
+                    continue;
+                };
+
+                render_pass.set_bind_group(1, &uniforms.models.bind_group, &[model_offset]);
+                // static meshes have no weights, any slot does for them
+                render_pass.set_bind_group(2, &uniforms.animation.bind_group, &[uniforms.animation.get_offset(id).unwrap_or(0)]);
+
+                // the instance index carries the id into the shader
+                let instance = id as u32 + 1;
+
+                for mesh in model.meshes.iter() {
+                    render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                    render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                    render_pass.draw_indexed(0..mesh.num_elements, 0, instance..instance + 1);
+                }
+            }
+
+            if !icons.is_empty() {
+                render_pass.set_pipeline(&self.icon_pipeline);
+                render_pass.set_vertex_buffer(0, self.icon_buffer.slice(..));
+                render_pass.draw(0..6, 0..icons.len() as u32);
+            }
+        }
+
+        // the cursor is in surface pixels, the target follows the render scale
+        let (surface_width, surface_height) = graph.get_surface_size();
+        let (width, height) = graph.get_render_size();
+        let x = ((request.x / surface_width.max(1) as f64 * width as f64) as u32).min(width.saturating_sub(1));
+        let y = ((request.y / surface_height.max(1) as f64 * height as f64) as u32).min(height.saturating_sub(1));
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &graph.get_texture(GRAPH_OBJECT_ID).texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: None,
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        );
+
+        self.recorded = Some(request);
+    }
+
+    /// Blocks until the pixel recorded this frame is copied back, call after the frame was submitted.
+    pub fn read(&mut self, ctx: &WgpuContext) -> Option<PickResult> {
+        let request = self.recorded.take()?;
+
+        let slice = self.readback_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = ctx.device.poll(wgpu::Maintain::Wait);
+
+        if let Err(e) = receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError)) {
+            println!("PickingPass::read() error: {:?}!!", e);
+            return None;
+        }
+
+        let value = *bytemuck::from_bytes::<u32>(&slice.get_mapped_range());
+        self.readback_buffer.unmap();
+
+        Some(PickResult {
+            id: value.checked_sub(1).map(|id| id as usize),
+            additive: request.additive
+        })
+    }
+
+    pub fn hotload_shader(&mut self, ctx: &WgpuContext, uniforms: &UniformManager) {
+        let (pipeline, icon_pipeline) = Self::create_pipelines(ctx, uniforms);
+
+        self.pipeline = pipeline;
+        self.icon_pipeline = icon_pipeline;
+    }
+}
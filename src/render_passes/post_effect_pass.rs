@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{bind_group_manager::{BindGroupManager, TL}, color_lut::ColorLut, common::constants::{GRAPH_POST_EFFECT_A, GRAPH_POST_EFFECT_B}, pipeline_builder::PipelineBuilder, render_graph::{GraphBindGroup, RenderGraph, TransientTextureDesc}, uniform_manager::UniformManager, wgpu_context::WgpuContext};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum PostEffectKind {
+    /// `lut` is a `.cube` file in res/luts.
+    ColorGrade { lut: String, strength: f32 },
+    Vignette { intensity: f32, radius: f32, smoothness: f32 },
+    FilmGrain { intensity: f32 },
+    ChromaticAberration { strength: f32 },
+    LiftGammaGain { lift: [f32; 3], gamma: [f32; 3], gain: [f32; 3] }
+}
+
+impl PostEffectKind {
+    /// Every effect with neutral-ish parameters, offered by the editor when adding to the stack.
+    pub fn defaults() -> [PostEffectKind; 5] {
+        [
+            PostEffectKind::ColorGrade { lut: String::new(), strength: 1.0 },
+            PostEffectKind::Vignette { intensity: 0.4, radius: 0.5, smoothness: 0.5 },
+            PostEffectKind::FilmGrain { intensity: 0.05 },
+            PostEffectKind::ChromaticAberration { strength: 0.5 },
+            PostEffectKind::LiftGammaGain { lift: [0.0; 3], gamma: [1.0; 3], gain: [1.0; 3] }
+        ]
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            PostEffectKind::ColorGrade { .. } => "Color Grade",
+            PostEffectKind::Vignette { .. } => "Vignette",
+            PostEffectKind::FilmGrain { .. } => "Film Grain",
+            PostEffectKind::ChromaticAberration { .. } => "Chromatic Aberration",
+            PostEffectKind::LiftGammaGain { .. } => "Lift Gamma Gain"
+        }
+    }
+
+    /// Index used to identify the effect inside post_effects.wgsl.
+    pub fn to_shader_index(&self) -> u32 {
+        match self {
+            PostEffectKind::ColorGrade { .. } => 0,
+            PostEffectKind::Vignette { .. } => 1,
+            PostEffectKind::FilmGrain { .. } => 2,
+            PostEffectKind::ChromaticAberration { .. } => 3,
+            PostEffectKind::LiftGammaGain { .. } => 4
+        }
+    }
+
+    /// Parameters packed the way post_effects.wgsl reads them.
+    pub fn get_shader_values(&self) -> [[f32; 4]; 3] {
+        match self {
+            PostEffectKind::ColorGrade { strength, .. } => [[*strength, 0.0, 0.0, 0.0], [0.0; 4], [0.0; 4]],
+            PostEffectKind::Vignette { intensity, radius, smoothness } => [[*intensity, *radius, *smoothness, 0.0], [0.0; 4], [0.0; 4]],
+            PostEffectKind::FilmGrain { intensity } => [[*intensity, 0.0, 0.0, 0.0], [0.0; 4], [0.0; 4]],
+            PostEffectKind::ChromaticAberration { strength } => [[*strength, 0.0, 0.0, 0.0], [0.0; 4], [0.0; 4]],
+            PostEffectKind::LiftGammaGain { lift, gamma, gain } => [
+                [lift[0], lift[1], lift[2], 0.0],
+                [gamma[0], gamma[1], gamma[2], 0.0],
+                [gain[0], gain[1], gain[2], 0.0]
+            ]
+        }
+    }
+}
+
+/// Entry of the post effect stack of a level, applied in order after tonemapping.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PostEffect {
+    pub enabled: bool,
+    pub kind: PostEffectKind
+}
+
+/// Runs the enabled effects of the stack one after another, ping-ponging between two targets.
+/// The post process pass renders into the first one while any effect is enabled.
+pub struct PostEffectPass {
+    pipeline: wgpu::RenderPipeline,
+    source_bg_layout: wgpu::BindGroupLayout,
+    lut_bg_layout: wgpu::BindGroupLayout,
+    source_bind_groups: [GraphBindGroup; 2],
+    identity_lut_bind_group: wgpu::BindGroup,
+    /// Failed loads are kept as `None` so a broken file is reported once.
+    luts: HashMap<String, Option<(ColorLut, wgpu::BindGroup)>>,
+    time: f32
+}
+
+impl PostEffectPass {
+    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager) -> Self {
+        let source_bg_layout = BindGroupManager::create_texture_bind_group_layout(&ctx.device, [TL::Float]).unwrap();
+        let lut_bg_layout = ColorLut::create_bind_group_layout(&ctx.device);
+        let identity_lut_bind_group = ColorLut::identity(ctx).create_bind_group(&ctx.device, &lut_bg_layout);
+        let pipeline = Self::create_pipeline(ctx, uniforms, &source_bg_layout, &lut_bg_layout);
+
+        Self {
+            pipeline,
+            source_bg_layout,
+            lut_bg_layout,
+            source_bind_groups: [GraphBindGroup::new(), GraphBindGroup::new()],
+            identity_lut_bind_group,
+            luts: HashMap::new(),
+            time: 0.0
+        }
+    }
+
+    pub fn register_textures(ctx: &WgpuContext, graph: &mut RenderGraph) {
+        let format = ctx.config.format.add_srgb_suffix();
+
+        graph.add_texture(&ctx.device, GRAPH_POST_EFFECT_A, TransientTextureDesc::render_target(format));
+        graph.add_texture(&ctx.device, GRAPH_POST_EFFECT_B, TransientTextureDesc::render_target(format));
+    }
+
+    fn create_pipeline(ctx: &WgpuContext, uniforms: &UniformManager, source_bg_layout: &wgpu::BindGroupLayout, lut_bg_layout: &wgpu::BindGroupLayout) -> wgpu::RenderPipeline {
+        let shader_code = std::fs::read_to_string("res/shaders/post_effects.wgsl").unwrap();
+        let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post_Effects_Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_code.into()),
+        });
+
+        PipelineBuilder::new(
+            "post effects pipeline",
+            &[source_bg_layout, &uniforms.post_effects.bind_group_layout, lut_bg_layout],
+            &[],
+            &shader_module,
+            [ctx.config.format.add_srgb_suffix()],
+        )
+        .with_blend(wgpu::BlendState::REPLACE)
+        .build(&ctx.device)
+    }
+
+    pub fn is_active(effects: &[PostEffect]) -> bool {
+        effects.iter().any(|effect| effect.enabled)
+    }
+
+    /// Seconds since startup, animates the film grain.
+    pub fn advance_time(&mut self, delta_time: f32) -> f32 {
+        self.time += delta_time;
+        self.time
+    }
+
+    fn load_luts(&mut self, ctx: &WgpuContext, effects: &[PostEffect]) {
+        for effect in effects.iter().filter(|effect| effect.enabled) {
+            let PostEffectKind::ColorGrade { lut, .. } = &effect.kind else {
+                continue;
+            };
+
+            if lut.is_empty() || self.luts.contains_key(lut) {
+                continue;
+            }
+
+            let loaded = match ColorLut::load(ctx, lut) {
+                Ok(color_lut) => {
+                    let bind_group = color_lut.create_bind_group(&ctx.device, &self.lut_bg_layout);
+                    Some((color_lut, bind_group))
+                }
+                Err(e) => {
+                    println!("PostEffectPass::load_luts() error: could not load {}!! {:?}", lut, e);
+                    None
+                }
+            };
+
+            self.luts.insert(lut.clone(), loaded);
+        }
+    }
+
+    /// `target_view` receives the result of the last enabled effect.
+    pub fn render(&mut self, ctx: &WgpuContext, encoder: &mut wgpu::CommandEncoder, graph: &RenderGraph, uniforms: &UniformManager, effects: &[PostEffect], target_view: &wgpu::TextureView) {
+        self.load_luts(ctx, effects);
+
+        let targets = [GRAPH_POST_EFFECT_A, GRAPH_POST_EFFECT_B];
+        let enabled: Vec<(usize, &PostEffect)> = effects.iter().enumerate().filter(|(_, effect)| effect.enabled).collect();
+        let mut source = 0;
+
+        for (position, (index, effect)) in enabled.iter().enumerate() {
+            let Some(offset) = uniforms.post_effects.get_offset(*index) else {
+                continue;
+            };
+
+            let is_last = position + 1 == enabled.len();
+            let view = if is_last { target_view } else { graph.get_view(targets[1 - source]) };
+
+            let lut_bind_group = match &effect.kind {
+                PostEffectKind::ColorGrade { lut, .. } => match self.luts.get(lut) {
+                    Some(Some((_, bind_group))) => bind_group,
+                    _ => &self.identity_lut_bind_group
+                },
+                _ => &self.identity_lut_bind_group
+            };
+
+            let source_bind_group = self.source_bind_groups[source].get(&ctx.device, graph, &self.source_bg_layout, &[targets[source]]);
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(effect.kind.get_name()),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, source_bind_group, &[]);
+            render_pass.set_bind_group(1, &uniforms.post_effects.bind_group, &[offset]);
+            render_pass.set_bind_group(2, lut_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+
+            source = 1 - source;
+        }
+    }
+
+    /// Edited `.cube` files are picked up again.
+    pub fn hotload_shader(&mut self, ctx: &WgpuContext, uniforms: &UniformManager) {
+        self.pipeline = Self::create_pipeline(ctx, uniforms, &self.source_bg_layout, &self.lut_bg_layout);
+        self.luts.clear();
+    }
+}
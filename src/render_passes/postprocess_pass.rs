@@ -1,29 +1,51 @@
-use crate::{bind_group_manager::{BindGroupManager, TL}, common::constants::HDR_TEX_FORMAT, pipeline_builder::PipelineBuilder, pipeline_manager::PipelineManager, texture::{self, Texture}, wgpu_context::WgpuContext};
+use serde::{Deserialize, Serialize};
+
+use crate::{bind_group_manager::{BindGroupManager, TL}, common::{constants::{GRAPH_HDR, HDR_TEX_FORMAT}, enums::Tonemapper}, pipeline_builder::PipelineBuilder, pipeline_manager::PipelineManager, render_passes::post_effect_pass::PostEffect, render_graph::{GraphBindGroup, RenderGraph, TransientTextureDesc}, uniform_manager::UniformManager, wgpu_context::WgpuContext};
+
+/// Tonemapping and exposure of a level, saved with it and edited from the render settings window.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PostProcessSettings {
+    pub tonemapper: Tonemapper,
+    /// In EV stops, the manual exposure or the compensation on top of auto exposure.
+    pub exposure: f32,
+    pub auto_exposure: bool,
+    /// Log2 luminance range covered by the auto exposure histogram.
+    pub min_log_luminance: f32,
+    pub max_log_luminance: f32,
+    /// How fast the eye adapts to a new luminance, per second.
+    pub adaptation_speed: f32,
+    /// Applied in order after tonemapping.
+    pub effects: Vec<PostEffect>
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> Self {
+        Self {
+            tonemapper: Tonemapper::Aces,
+            exposure: 0.0,
+            auto_exposure: false,
+            min_log_luminance: -8.0,
+            max_log_luminance: 4.0,
+            adaptation_speed: 1.5,
+            effects: Vec::new()
+        }
+    }
+}
 
 pub struct PostProcessPass {
     pipeline_layout: wgpu::PipelineLayout,
     pipeline: wgpu::RenderPipeline,
     bind_group_layout: wgpu::BindGroupLayout,
-    bind_group: wgpu::BindGroup,
-    texture: Texture,
-    emissive_texture: Texture,
-    width: u32,
-    height: u32,
+    bind_group: GraphBindGroup,
     format: wgpu::TextureFormat,
 }
 
 impl PostProcessPass {
-    pub fn new(ctx: &WgpuContext, config: &wgpu::SurfaceConfiguration) -> Self {
-       let format = wgpu::TextureFormat::Rgba16Float;
- 
-       let width = config.width;
-       let height = config.height;
-
-       let hdr_texture = Texture::create_fbo(&ctx.device, (1920, 1080), format, wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT);
-       let emissive_texture = Texture::create_fbo(&ctx.device, (1920, 1080), format, wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT);
+    pub fn new(ctx: &WgpuContext, config: &wgpu::SurfaceConfiguration, uniforms: &UniformManager, exposure_bg_layout: &wgpu::BindGroupLayout) -> Self {
+       let format = HDR_TEX_FORMAT;
 
         let bind_group_layout = BindGroupManager::create_texture_bind_group_layout(&ctx.device, [TL::Float, TL::Float]).unwrap();
-        let bind_group = BindGroupManager::create_multi_texture_bind_group(&ctx.device, &bind_group_layout, &[&hdr_texture, &emissive_texture]).unwrap();
 
        let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Default_Shader"),
@@ -32,7 +54,7 @@ impl PostProcessPass {
 
         let pipeline_layout = ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Post_Process_Pipeline_Layout"),
-            bind_group_layouts: &[&bind_group_layout],
+            bind_group_layouts: &[&bind_group_layout, &uniforms.bloom.bind_group_layout, &uniforms.post_process.bind_group_layout, exposure_bg_layout],
             push_constant_ranges: &[],
         });
 
@@ -40,7 +62,7 @@ impl PostProcessPass {
 
          let pipeline = PipelineBuilder::new(
             "postprocess pipeline",
-            &[&bind_group_layout],
+            &[&bind_group_layout, &uniforms.bloom.bind_group_layout, &uniforms.post_process.bind_group_layout, exposure_bg_layout],
             &[],
             &shader_module,
             [config.format.add_srgb_suffix()],
@@ -48,54 +70,24 @@ impl PostProcessPass {
         .build(&ctx.device);
 
         Self {
-            bind_group,
+            bind_group: GraphBindGroup::new(),
             format,
-            height, 
             bind_group_layout, 
             pipeline,
-            texture: hdr_texture,
-            emissive_texture,
-            width,
             pipeline_layout
         }
     }
 
-    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32, final_blur_texture: &Texture) {
-       //self.texture = texture::Texture::create_fbo(&device, (width, height),  wgpu::TextureFormat::Rgba16Float, wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT);
-      // self.emissive_texture = texture::Texture::create_fbo(&device, (width, height),  wgpu::TextureFormat::Rgba16Float, wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT);
-
-    //    match BindGroupManager::create_multi_texture_bind_group(&device, &self.bind_group_layout, &[&self.texture, &final_blur_texture]) {
-    //     Ok(result) => self.bind_group = result,
-    //     Err(e) => {
-    //         println!("PostProcessGroup::resize() error: failed to resize texture bind group!! {e}")
-    //     }
-    //    }
-
-       self.width = width;
-       self.height = height;
-    }
-
-    pub fn get_view(&self) -> &wgpu::TextureView {
-       return &self.texture.view
-    }
-
-    pub fn get_emissive_view(&self) -> &wgpu::TextureView {
-        return &self.emissive_texture.view
-    }
-
-    pub fn get_hdr_texture(&self) -> &Texture {
-        return &self.texture
-    }
-
-    pub fn get_emmisive_texture(&self) -> &Texture {
-        return &self.emissive_texture
+    pub fn register_textures(ctx: &WgpuContext, graph: &mut RenderGraph) {
+        // TAA copies its resolved image back into the HDR target
+        graph.add_texture(&ctx.device, GRAPH_HDR, TransientTextureDesc::render_target(HDR_TEX_FORMAT).with_usage(wgpu::TextureUsages::COPY_DST));
     }
 
     pub fn get_format(&self) -> wgpu::TextureFormat {
         return self.format
     }
 
-    pub fn hotload_shader(&mut self, ctx: &WgpuContext) {
+    pub fn hotload_shader(&mut self, ctx: &WgpuContext, uniforms: &UniformManager, exposure_bg_layout: &wgpu::BindGroupLayout) {
       let shader_code = std::fs::read_to_string("res/shaders/postprocess.wgsl").unwrap();
       let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Default_Shader"),
@@ -104,7 +96,7 @@ impl PostProcessPass {
 
        let pipeline = PipelineBuilder::new(
             "postprocess pipeline",
-            &[&self.bind_group_layout],
+            &[&self.bind_group_layout, &uniforms.bloom.bind_group_layout, &uniforms.post_process.bind_group_layout, exposure_bg_layout],
             &[],
             &shader_module,
             [ctx.config.format.add_srgb_suffix()],
@@ -114,17 +106,14 @@ impl PostProcessPass {
        self.pipeline = pipeline;
     }
 
-    pub fn render(&mut self, encoder: &mut wgpu::CommandEncoder, swapchain_view: &wgpu::TextureView, ctx: &WgpuContext, final_blur_texture: &Texture) {
+    /// `target_view` is the swapchain, or the target of whatever runs afterwards (post effects, FXAA).
+    pub fn render(&mut self, encoder: &mut wgpu::CommandEncoder, target_view: &wgpu::TextureView, ctx: &WgpuContext, graph: &RenderGraph, uniforms: &UniformManager, bloom_texture: &str, exposure_bind_group: &wgpu::BindGroup) {
+       let bind_group = self.bind_group.get(&ctx.device, graph, &self.bind_group_layout, &[GRAPH_HDR, bloom_texture]);
 
-self.bind_group = BindGroupManager::create_multi_texture_bind_group(
-    &ctx.device,
-    &self.bind_group_layout,
-    &[&self.texture, &final_blur_texture],
-).unwrap();
        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Post_Process::render()"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &swapchain_view,
+                view: &target_view,
                 resolve_target: None,
                 ops: wgpu::Operations {
                     load: wgpu::LoadOp::Load,
@@ -136,7 +125,10 @@ self.bind_group = BindGroupManager::create_multi_texture_bind_group(
             occlusion_query_set: None
         });
         pass.set_pipeline(&self.pipeline);
-        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.set_bind_group(1, &uniforms.bloom.bind_group, &[]);
+        pass.set_bind_group(2, &uniforms.post_process.bind_group, &[]);
+        pass.set_bind_group(3, exposure_bind_group, &[]);
         pass.draw(0..3, 0..1);
     }
 }
\ No newline at end of file
@@ -0,0 +1,315 @@
+use cgmath::SquareMatrix;
+
+use crate::{asset_manager::AssetManager, bind_group_manager::{BindGroupManager, TL}, common::constants::{CASCADE_SHADOW_MAP_SIZE, MAX_DIRECTIONAL_SHADOWS, MAX_POINT_SHADOWS, MAX_SPOT_SHADOWS, POINT_SHADOW_MAP_SIZE, SHADOW_CASCADE_COUNT, SHADOW_MAP_FORMAT, SPOT_SHADOW_MAP_SIZE}, common::enums::{AlphaMode, LightType}, instance::{InstanceBuffer, InstanceUniform}, model::Mesh, objects::{animated_game_object::AnimatedGameObject, game_object::GameObject}, pipeline_builder::PipelineBuilder, texture::Texture, uniform_manager::UniformManager, vertex::Vertex, wgpu_context::WgpuContext};
+
+struct ShadowPipelines {
+    pipeline: wgpu::RenderPipeline,
+    skinned_pipeline: wgpu::RenderPipeline,
+    masked_pipeline: wgpu::RenderPipeline,
+    skinned_masked_pipeline: wgpu::RenderPipeline
+}
+
+impl ShadowPipelines {
+    fn get(&self, skinned: bool, masked: bool) -> &wgpu::RenderPipeline {
+        match (skinned, masked) {
+            (false, false) => &self.pipeline,
+            (true, false) => &self.skinned_pipeline,
+            (false, true) => &self.masked_pipeline,
+            (true, true) => &self.skinned_masked_pipeline
+        }
+    }
+}
+
+/// A mesh drawn into every shadow map this frame.
+struct ShadowDraw<'a> {
+    mesh: &'a Mesh,
+    material: &'a wgpu::BindGroup,
+    model_offset: u32,
+    skin_offset: Option<u32>,
+    /// Index into the mask buffer holding the alpha cutoff, for alpha tested meshes.
+    mask_instance: Option<u32>
+}
+
+pub struct ShadowPass {
+    pipelines: ShadowPipelines,
+    texture_bg_layout: wgpu::BindGroupLayout,
+    mask_buffer: InstanceBuffer,
+    pub point_shadow_maps: Texture,
+    pub spot_shadow_maps: Texture,
+    pub cascade_shadow_maps: Texture,
+    point_layer_views: Vec<wgpu::TextureView>,
+    spot_layer_views: Vec<wgpu::TextureView>,
+    cascade_layer_views: Vec<wgpu::TextureView>,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl ShadowPass {
+    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager, asset_manager: &AssetManager) -> Self {
+        let texture_bind_group_layout = &asset_manager.get_material_by_name("Barrel_RED").unwrap().bind_group_layout;
+        let pipelines = Self::create_pipelines(ctx, uniforms, texture_bind_group_layout);
+
+        let point_layers = (MAX_POINT_SHADOWS * 6) as u32;
+        let spot_layers = MAX_SPOT_SHADOWS as u32;
+        let cascade_layers = (MAX_DIRECTIONAL_SHADOWS * SHADOW_CASCADE_COUNT) as u32;
+
+        let point_shadow_maps = Texture::create_depth_array(&ctx.device, POINT_SHADOW_MAP_SIZE, point_layers, "Point_Shadow_Maps", SHADOW_MAP_FORMAT);
+        let spot_shadow_maps = Texture::create_depth_array(&ctx.device, SPOT_SHADOW_MAP_SIZE, spot_layers, "Spot_Shadow_Maps", SHADOW_MAP_FORMAT);
+        let cascade_shadow_maps = Texture::create_depth_array(&ctx.device, CASCADE_SHADOW_MAP_SIZE, cascade_layers, "Cascade_Shadow_Maps", SHADOW_MAP_FORMAT);
+
+        let point_layer_views = (0..point_layers).map(|layer| point_shadow_maps.create_layer_view(layer)).collect();
+        let spot_layer_views = (0..spot_layers).map(|layer| spot_shadow_maps.create_layer_view(layer)).collect();
+        let cascade_layer_views = (0..cascade_layers).map(|layer| cascade_shadow_maps.create_layer_view(layer)).collect();
+
+        // point, spot and cascade maps with their comparison samplers, then the shadow matrices
+        let layout_entries: Vec<wgpu::BindGroupLayoutEntry> = BindGroupManager::bindgroup_layout_entries(0, [TL::DepthArray, TL::DepthArray, TL::DepthArray].into_iter())
+            .chain(std::iter::once(wgpu::BindGroupLayoutEntry {
+                binding: 6,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }))
+            .collect();
+
+        let bind_group_layout = ctx.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Shadow_Bind_Group_Layout"),
+            entries: &layout_entries,
+        });
+
+        let texs = [&point_shadow_maps, &spot_shadow_maps, &cascade_shadow_maps];
+        let entries: Vec<wgpu::BindGroupEntry> = BindGroupManager::multi_bindgroup_entries(0, &texs)
+            .chain(std::iter::once(wgpu::BindGroupEntry {
+                binding: 6,
+                resource: uniforms.shadows.buffer.as_entire_binding(),
+            }))
+            .collect();
+
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow_Bind_Group"),
+            layout: &bind_group_layout,
+            entries: &entries,
+        });
+
+        Self {
+            pipelines,
+            texture_bg_layout: texture_bind_group_layout.clone(),
+            mask_buffer: InstanceBuffer::new(&ctx.device, 64),
+            point_shadow_maps,
+            spot_shadow_maps,
+            cascade_shadow_maps,
+            point_layer_views,
+            spot_layer_views,
+            cascade_layer_views,
+            bind_group_layout,
+            bind_group
+        }
+    }
+
+    fn create_pipelines(ctx: &WgpuContext, uniforms: &UniformManager, texture_bind_group_layout: &wgpu::BindGroupLayout) -> ShadowPipelines {
+        let shader_code = std::fs::read_to_string("res/shaders/shadow.wgsl").unwrap();
+        let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow_Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_code.into()),
+        });
+
+        let layouts = [
+            &uniforms.shadow_views[0].bind_group_layout,
+            &uniforms.models.bind_group_layout,
+            texture_bind_group_layout,
+            &uniforms.animation.bind_group_layout
+        ];
+
+        let pipeline = PipelineBuilder::new(
+            "shadow pipeline",
+            &layouts[..2],
+            &[Vertex::desc()],
+            &shader_module,
+            [],
+        )
+        .with_depth(SHADOW_MAP_FORMAT)
+        .with_depth_write()
+        .with_depth_bias(2, 2.0)
+        .build(&ctx.device);
+
+        let skinned_pipeline = PipelineBuilder::new(
+            "skinned shadow pipeline",
+            &layouts,
+            &[Vertex::desc()],
+            &shader_module,
+            [],
+        )
+        .with_depth(SHADOW_MAP_FORMAT)
+        .with_depth_write()
+        .with_depth_bias(2, 2.0)
+        .with_vertex_entry_point("vs_skinned")
+        .build(&ctx.device);
+
+        let masked_pipeline = PipelineBuilder::new(
+            "masked shadow pipeline",
+            &layouts[..3],
+            &[Vertex::desc(), InstanceUniform::desc()],
+            &shader_module,
+            [],
+        )
+        .with_depth(SHADOW_MAP_FORMAT)
+        .with_depth_write()
+        .with_depth_bias(2, 2.0)
+        .with_vertex_entry_point("vs_masked")
+        .with_fragment_entry_point("fs_masked")
+        .build(&ctx.device);
+
+        let skinned_masked_pipeline = PipelineBuilder::new(
+            "skinned masked shadow pipeline",
+            &layouts,
+            &[Vertex::desc(), InstanceUniform::desc()],
+            &shader_module,
+            [],
+        )
+        .with_depth(SHADOW_MAP_FORMAT)
+        .with_depth_write()
+        .with_depth_bias(2, 2.0)
+        .with_vertex_entry_point("vs_skinned_masked")
+        .with_fragment_entry_point("fs_masked")
+        .build(&ctx.device);
+
+        ShadowPipelines {
+            pipeline,
+            skinned_pipeline,
+            masked_pipeline,
+            skinned_masked_pipeline
+        }
+    }
+
+    /// Collects the shadow casting meshes once per frame and uploads the cutoffs of the alpha tested ones.
+    /// Alpha blended meshes don't cast shadows, emissive meshes are the light sources themselves.
+    fn prepare<'a>(&mut self, ctx: &WgpuContext, uniforms: &UniformManager, asset_manager: &'a AssetManager, game_objects: &Vec<GameObject>, animated_game_objects: &Vec<AnimatedGameObject>) -> Vec<ShadowDraw<'a>> {
+        let mut draws = Vec::new();
+        let mut mask_instances = Vec::new();
+
+        let static_objects = game_objects.iter()
+            .map(|game_object| (game_object.id, game_object.get_model_name(), game_object.get_mesh_nodes(), game_object.tex_scale, false));
+        let animated_objects = animated_game_objects.iter()
+            .map(|animated_game_object| (animated_game_object.object_id, animated_game_object.get_model_name(), animated_game_object.get_mesh_nodes(), animated_game_object.tex_scale, true));
+
+        for (id, model_name, mesh_nodes, tex_scale, skinned) in static_objects.chain(animated_objects) {
+            let Some(model_offset) = uniforms.models.get_offset(id) else {
+                continue;
+            };
+
+            let skin_offset = if skinned {
+                let Some(skin_offset) = uniforms.animation.get_offset(id) else {
+                    continue;
+                };
+                Some(skin_offset)
+            } else {
+                None
+            };
+
+            let Some(model) = asset_manager.get_model_by_name(model_name) else {
+                continue;
+            };
+
+            for mesh in model.meshes.iter() {
+                let mesh_node = mesh_nodes.get_mesh_rendering_info_by_mesh_name(&mesh.name);
+
+                if mesh_node.emissive || mesh_node.alpha_mode == AlphaMode::Blend {
+                    continue;
+                }
+
+                let Some(material) = asset_manager.get_material_by_index(mesh_node.material_index) else {
+                    continue;
+                };
+
+                let mask_instance = (mesh_node.alpha_mode == AlphaMode::Mask).then(|| {
+                    mask_instances.push(InstanceUniform::new(&cgmath::Matrix4::identity(), &tex_scale).with_alpha_mode(AlphaMode::Mask, mesh_node.alpha_cutoff));
+                    mask_instances.len() as u32 - 1
+                });
+
+                draws.push(ShadowDraw {
+                    mesh,
+                    material: &material.bind_group,
+                    model_offset,
+                    skin_offset,
+                    mask_instance
+                });
+            }
+        }
+
+        // grouped by pipeline, the alpha tested meshes last
+        draws.sort_by_key(|draw| (draw.mask_instance.is_some(), draw.skin_offset.is_some()));
+        self.mask_buffer.update(ctx, &mask_instances);
+
+        draws
+    }
+
+    pub fn render(&mut self, ctx: &WgpuContext, encoder: &mut wgpu::CommandEncoder, uniforms: &UniformManager, asset_manager: &AssetManager, game_objects: &Vec<GameObject>, animated_game_objects: &Vec<AnimatedGameObject>) {
+        let draws = self.prepare(ctx, uniforms, asset_manager, game_objects, animated_game_objects);
+
+        for caster in uniforms.shadows.value().casters.iter().take(uniforms.shadow_caster_count) {
+            let layer_views = if caster.light_type == LightType::Point.to_shader_index() {
+                &self.point_layer_views
+            } else if caster.light_type == LightType::Spot.to_shader_index() {
+                &self.spot_layer_views
+            } else {
+                &self.cascade_layer_views
+            };
+
+            for face in 0..caster.get_face_count() {
+                let shadow_view = &uniforms.shadow_views[caster.matrix_index as usize + face];
+
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Shadow_Pass"),
+                    color_attachments: &[],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &layer_views[caster.layer as usize + face],
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: wgpu::StoreOp::Store,
+                        }),
+                        stencil_ops: None,
+                    }),
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+
+                render_pass.set_bind_group(0, &shadow_view.bind_group, &[]);
+                render_pass.set_vertex_buffer(1, self.mask_buffer.buffer.slice(..));
+
+                let mut current_pipeline = None;
+
+                for draw in draws.iter() {
+                    let pipeline_key = (draw.skin_offset.is_some(), draw.mask_instance.is_some());
+
+                    if current_pipeline != Some(pipeline_key) {
+                        render_pass.set_pipeline(self.pipelines.get(pipeline_key.0, pipeline_key.1));
+                        current_pipeline = Some(pipeline_key);
+                    }
+
+                    render_pass.set_bind_group(1, &uniforms.models.bind_group, &[draw.model_offset]);
+
+                    if pipeline_key != (false, false) {
+                        render_pass.set_bind_group(2, draw.material, &[]);
+                    }
+
+                    if let Some(skin_offset) = draw.skin_offset {
+                        render_pass.set_bind_group(3, &uniforms.animation.bind_group, &[skin_offset]);
+                    }
+
+                    let instance = draw.mask_instance.unwrap_or(0);
+
+                    render_pass.set_vertex_buffer(0, draw.mesh.vertex_buffer.slice(..));
+                    render_pass.set_index_buffer(draw.mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                    render_pass.draw_indexed(0..draw.mesh.num_elements, 0, instance..instance + 1);
+                }
+            }
+        }
+    }
+
+    pub fn hotload_shader(&mut self, ctx: &WgpuContext, uniforms: &UniformManager) {
+        self.pipelines = Self::create_pipelines(ctx, uniforms, &self.texture_bg_layout);
+    }
+}
@@ -1,33 +1,40 @@
-use crate::bind_group_manager::{BindGroupManager, TL};
+use wgpu::util::DeviceExt;
+
 use crate::common::constants::{DEPTH_TEXTURE_STENCIL_FORMAT, HDR_TEX_FORMAT};
-use crate::cube_map::CubeMap;
+use crate::environment_map::EnvironmentMap;
 use crate::pipeline_builder::PipelineBuilder;
 use crate::renderer_common::SKYBOX_VERTICES;
 use crate::uniform_manager::UniformManager;
-use crate::{asset_manager::AssetManager, wgpu_context::WgpuContext};
-use crate::texture::{Texture, TextureHelpers};
+use crate::wgpu_context::WgpuContext;
 
 pub struct SkyboxPass {
     pipeline: wgpu::RenderPipeline,
-    cubemap: CubeMap
+    skybox_bg_layout: wgpu::BindGroupLayout,
+    vertex_buffer: wgpu::Buffer
 }
 
 impl SkyboxPass {
-    pub fn new(ctx: &WgpuContext, asset_manager: &AssetManager, uniforms: &UniformManager) -> Self {
+    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager, environment_map: &EnvironmentMap, sample_count: u32) -> Self {
+        let vertex_buffer = ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Cube_Vertex_Buffer"),
+            contents: bytemuck::cast_slice(SKYBOX_VERTICES),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let pipeline = Self::create_pipeline(ctx, uniforms, &environment_map.skybox_bind_group_layout, sample_count);
+
+        Self {
+            vertex_buffer,
+            skybox_bg_layout: environment_map.skybox_bind_group_layout.clone(),
+            pipeline
+        }
+    }
+
+    fn create_pipeline(ctx: &WgpuContext, uniforms: &UniformManager, skybox_bind_group_layout: &wgpu::BindGroupLayout, sample_count: u32) -> wgpu::RenderPipeline {
         let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Cubemap_Shader"),
             source: wgpu::ShaderSource::Wgsl(include_str!("../../res/shaders/cube_map.wgsl").into()),
         });
-        
-        let flipped_right = asset_manager.get_texture_by_name("SkyRight.jpg").unwrap().flip_horizontal();
-        let cubemap = CubeMap::new(&ctx.device, &ctx.queue, asset_manager.get_texture_by_name("SkyRight.jpg").unwrap().dimensions, [
-            &flipped_right.pixel_data,
-            &asset_manager.get_texture_by_name("SkyLeft.jpg").unwrap().pixel_data,
-            &asset_manager.get_texture_by_name("SkyTop.jpg").unwrap().pixel_data,
-            &asset_manager.get_texture_by_name("SkyBottom.jpg").unwrap().pixel_data,
-            &asset_manager.get_texture_by_name("SkyFront.jpg").unwrap().pixel_data,
-            &asset_manager.get_texture_by_name("SkyBack.jpg").unwrap().pixel_data,
-        ]);
 
         let cubemap_buffers = [wgpu::VertexBufferLayout {
                 array_stride:  3 * std::mem::size_of::<f32>() as wgpu::BufferAddress, 
@@ -41,30 +48,30 @@ impl SkyboxPass {
               ]
         }];
 
-        let pipeline = PipelineBuilder::new(
+        PipelineBuilder::new(
             "skybox pipeline",
-            &[&cubemap.texture_bind_group_layout, &uniforms.camera.bind_group_layout],
+            &[skybox_bind_group_layout, &uniforms.camera.bind_group_layout],
             &cubemap_buffers,
             &shader_module,
             [HDR_TEX_FORMAT, HDR_TEX_FORMAT]
         )
         .with_depth(DEPTH_TEXTURE_STENCIL_FORMAT)
         .with_cull_mode(wgpu::Face::Back)
-        .build(&ctx.device);
+        .with_sample_count(sample_count)
+        .build(&ctx.device)
+    }
 
-        Self {
-            cubemap,
-            pipeline
-        }
+    pub fn set_sample_count(&mut self, ctx: &WgpuContext, uniforms: &UniformManager, sample_count: u32) {
+        self.pipeline = Self::create_pipeline(ctx, uniforms, &self.skybox_bg_layout, sample_count);
     }
 
-    pub fn render(&self, render_pass: &mut wgpu::RenderPass, uniforms: &UniformManager, ) {
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass, uniforms: &UniformManager, environment_map: &EnvironmentMap) {
        render_pass.set_pipeline(&self.pipeline);
 
-       render_pass.set_bind_group(0, &self.cubemap.texture_bind_group, &[]);
+       render_pass.set_bind_group(0, &environment_map.skybox_bind_group, &[]);
        render_pass.set_bind_group(1, &uniforms.camera.bind_group, &[]);
 
-       render_pass.set_vertex_buffer(0, self.cubemap.vertex_buffer.slice(..));
+       render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
        render_pass.draw(0..(SKYBOX_VERTICES.len() / 3) as u32, 0..1);
     }
 }
\ No newline at end of file
@@ -0,0 +1,231 @@
+use crate::{asset_manager::AssetManager, bind_group_manager::{BindGroupManager, TL}, common::constants::{DEPTH_TEXTURE_FORMAT, GRAPH_SSAO, GRAPH_SSAO_BLUR, GRAPH_SSAO_DEPTH, GRAPH_SSAO_RAW, SSAO_KERNEL_SIZE, SSAO_TEXTURE_FORMAT}, instance::InstanceUniform, pipeline_builder::PipelineBuilder, render_graph::{GraphBindGroup, RenderGraph, TransientTextureDesc}, render_passes::lighting_pass::LightingPass, uniform_manager::UniformManager, vertex::Vertex, wgpu_context::WgpuContext};
+
+/// Runtime tunable SSAO parameters, edited from the render settings window.
+#[derive(Debug, Clone, Copy)]
+pub struct SsaoSettings {
+    pub enabled: bool,
+    pub radius: f32,
+    pub bias: f32,
+    pub intensity: f32,
+    pub power: f32,
+    pub sample_count: u32,
+    pub blur: bool,
+    pub blur_sharpness: f32
+}
+
+impl Default for SsaoSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            radius: 0.5,
+            bias: 0.025,
+            intensity: 1.0,
+            power: 1.5,
+            sample_count: SSAO_KERNEL_SIZE as u32,
+            blur: true,
+            blur_sharpness: 4.0
+        }
+    }
+}
+
+pub struct SsaoPass {
+    pub settings: SsaoSettings,
+    depth_pipeline: wgpu::RenderPipeline,
+    masked_depth_pipeline: wgpu::RenderPipeline,
+    ssao_pipeline: wgpu::RenderPipeline,
+    blur_pipeline: wgpu::RenderPipeline,
+    texture_bg_layout: wgpu::BindGroupLayout,
+    depth_bg_layout: wgpu::BindGroupLayout,
+    blur_bg_layout: wgpu::BindGroupLayout,
+    depth_bind_group: GraphBindGroup,
+    blur_horizontal_bind_group: GraphBindGroup,
+    blur_vertical_bind_group: GraphBindGroup,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: GraphBindGroup
+}
+
+impl SsaoPass {
+    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager, asset_manager: &AssetManager) -> Self {
+        let texture_bind_group_layout = &asset_manager.get_material_by_name("Barrel_RED").unwrap().bind_group_layout;
+        let depth_bg_layout = BindGroupManager::create_texture_bind_group_layout(&ctx.device, [TL::Depth]).unwrap();
+        let blur_bg_layout = BindGroupManager::create_texture_bind_group_layout(&ctx.device, [TL::Float, TL::Depth]).unwrap();
+        let bind_group_layout = BindGroupManager::create_texture_bind_group_layout(&ctx.device, [TL::Float]).unwrap();
+
+        let (depth_pipeline, masked_depth_pipeline, ssao_pipeline, blur_pipeline) = Self::create_pipelines(ctx, uniforms, texture_bind_group_layout, &depth_bg_layout, &blur_bg_layout);
+
+        Self {
+            settings: SsaoSettings::default(),
+            depth_pipeline,
+            masked_depth_pipeline,
+            ssao_pipeline,
+            blur_pipeline,
+            texture_bg_layout: texture_bind_group_layout.clone(),
+            depth_bg_layout,
+            blur_bg_layout,
+            depth_bind_group: GraphBindGroup::new(),
+            blur_horizontal_bind_group: GraphBindGroup::new(),
+            blur_vertical_bind_group: GraphBindGroup::new(),
+            bind_group_layout,
+            bind_group: GraphBindGroup::new()
+        }
+    }
+
+    pub fn register_textures(ctx: &WgpuContext, graph: &mut RenderGraph) {
+        graph.add_texture(&ctx.device, GRAPH_SSAO_DEPTH, TransientTextureDesc::render_target(DEPTH_TEXTURE_FORMAT));
+        graph.add_texture(&ctx.device, GRAPH_SSAO_RAW, TransientTextureDesc::render_target(SSAO_TEXTURE_FORMAT));
+        graph.add_texture(&ctx.device, GRAPH_SSAO_BLUR, TransientTextureDesc::render_target(SSAO_TEXTURE_FORMAT));
+        graph.add_texture(&ctx.device, GRAPH_SSAO, TransientTextureDesc::render_target(SSAO_TEXTURE_FORMAT));
+    }
+
+    /// The occlusion texture as sampled by the lighting pass.
+    pub fn get_bind_group(&mut self, ctx: &WgpuContext, graph: &RenderGraph) -> &wgpu::BindGroup {
+        self.bind_group.get(&ctx.device, graph, &self.bind_group_layout, &[GRAPH_SSAO])
+    }
+
+    fn create_pipelines(ctx: &WgpuContext, uniforms: &UniformManager, texture_bg_layout: &wgpu::BindGroupLayout, depth_bg_layout: &wgpu::BindGroupLayout, blur_bg_layout: &wgpu::BindGroupLayout) -> (wgpu::RenderPipeline, wgpu::RenderPipeline, wgpu::RenderPipeline, wgpu::RenderPipeline) {
+        let depth_shader = Self::create_shader_module(ctx, "res/shaders/ssao_depth.wgsl", "SSAO_Depth_Shader");
+        let ssao_shader = Self::create_shader_module(ctx, "res/shaders/ssao.wgsl", "SSAO_Shader");
+        let blur_shader = Self::create_shader_module(ctx, "res/shaders/ssao_blur.wgsl", "SSAO_Blur_Shader");
+
+        let depth_pipeline = PipelineBuilder::new(
+            "ssao depth pipeline",
+            &[&uniforms.camera.bind_group_layout],
+            &[Vertex::desc(), InstanceUniform::desc()],
+            &depth_shader,
+            [],
+        )
+        .with_depth(DEPTH_TEXTURE_FORMAT)
+        .with_depth_write()
+        .build(&ctx.device);
+
+        let masked_depth_pipeline = PipelineBuilder::new(
+            "ssao masked depth pipeline",
+            &[&uniforms.camera.bind_group_layout, texture_bg_layout],
+            &[Vertex::desc(), InstanceUniform::desc()],
+            &depth_shader,
+            [],
+        )
+        .with_depth(DEPTH_TEXTURE_FORMAT)
+        .with_depth_write()
+        .with_vertex_entry_point("vs_masked")
+        .with_fragment_entry_point("fs_masked")
+        .build(&ctx.device);
+
+        let ssao_pipeline = PipelineBuilder::new(
+            "ssao pipeline",
+            &[depth_bg_layout, &uniforms.ssao.bind_group_layout],
+            &[],
+            &ssao_shader,
+            [SSAO_TEXTURE_FORMAT],
+        )
+        .with_blend(wgpu::BlendState::REPLACE)
+        .build(&ctx.device);
+
+        let blur_pipeline = PipelineBuilder::new(
+            "ssao blur pipeline",
+            &[blur_bg_layout, &uniforms.ssao.bind_group_layout],
+            &[],
+            &blur_shader,
+            [SSAO_TEXTURE_FORMAT],
+        )
+        .with_blend(wgpu::BlendState::REPLACE)
+        .build(&ctx.device);
+
+        (depth_pipeline, masked_depth_pipeline, ssao_pipeline, blur_pipeline)
+    }
+
+    fn create_shader_module(ctx: &WgpuContext, path: &str, label: &str) -> wgpu::ShaderModule {
+        let shader_code = std::fs::read_to_string(path).unwrap();
+        ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(shader_code.into()),
+        })
+    }
+
+    /// Renders a depth prepass of the batched lighting geometry, computes occlusion from it and blurs the result.
+    /// When disabled the occlusion texture is cleared to white so the lighting pass doesn't need to know.
+    pub fn render(&mut self, ctx: &WgpuContext, encoder: &mut wgpu::CommandEncoder, graph: &RenderGraph, uniforms: &UniformManager, asset_manager: &AssetManager, lighting_pass: &LightingPass) {
+        if !self.settings.enabled {
+            let _clear_pass = begin_ao_pass(encoder, graph.get_view(GRAPH_SSAO), "SSAO_Clear_Pass");
+            return;
+        }
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("SSAO_Depth_Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: graph.get_view(GRAPH_SSAO_DEPTH),
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.depth_pipeline);
+            render_pass.set_bind_group(0, &uniforms.camera.bind_group, &[]);
+            lighting_pass.render_depth(&mut render_pass, asset_manager, &self.masked_depth_pipeline);
+        }
+
+        let ssao_target = if self.settings.blur { GRAPH_SSAO_RAW } else { GRAPH_SSAO };
+        let depth_bind_group = self.depth_bind_group.get(&ctx.device, graph, &self.depth_bg_layout, &[GRAPH_SSAO_DEPTH]);
+
+        {
+            let mut render_pass = begin_ao_pass(encoder, graph.get_view(ssao_target), "SSAO_Pass");
+
+            render_pass.set_pipeline(&self.ssao_pipeline);
+            render_pass.set_bind_group(0, depth_bind_group, &[]);
+            render_pass.set_bind_group(1, &uniforms.ssao.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        if !self.settings.blur {
+            return;
+        }
+
+        // the instance index picks the blur direction, 0 is horizontal and 1 vertical
+        let blurs = [
+            (self.blur_horizontal_bind_group.get(&ctx.device, graph, &self.blur_bg_layout, &[GRAPH_SSAO_RAW, GRAPH_SSAO_DEPTH]), GRAPH_SSAO_BLUR, 0),
+            (self.blur_vertical_bind_group.get(&ctx.device, graph, &self.blur_bg_layout, &[GRAPH_SSAO_BLUR, GRAPH_SSAO_DEPTH]), GRAPH_SSAO, 1)
+        ];
+
+        for (bind_group, target, direction) in blurs {
+            let mut render_pass = begin_ao_pass(encoder, graph.get_view(target), "SSAO_Blur_Pass");
+
+            render_pass.set_pipeline(&self.blur_pipeline);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.set_bind_group(1, &uniforms.ssao.bind_group, &[]);
+            render_pass.draw(0..3, direction..direction + 1);
+        }
+    }
+
+    pub fn hotload_shader(&mut self, ctx: &WgpuContext, uniforms: &UniformManager) {
+        let (depth_pipeline, masked_depth_pipeline, ssao_pipeline, blur_pipeline) = Self::create_pipelines(ctx, uniforms, &self.texture_bg_layout, &self.depth_bg_layout, &self.blur_bg_layout);
+
+        self.depth_pipeline = depth_pipeline;
+        self.masked_depth_pipeline = masked_depth_pipeline;
+        self.ssao_pipeline = ssao_pipeline;
+        self.blur_pipeline = blur_pipeline;
+    }
+}
+
+fn begin_ao_pass<'a>(encoder: &'a mut wgpu::CommandEncoder, view: &'a wgpu::TextureView, label: &'static str) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    })
+}
@@ -0,0 +1,159 @@
+use crate::{bind_group_manager::{BindGroupManager, TL}, common::constants::{GRAPH_DEPTH, GRAPH_HDR, GRAPH_TAA_HISTORY, GRAPH_TAA_RESOLVE, HDR_TEX_FORMAT, TAA_JITTER_SAMPLES}, pipeline_builder::PipelineBuilder, render_graph::{GraphBindGroup, RenderGraph, TransientTextureDesc}, uniform_manager::UniformManager, wgpu_context::WgpuContext};
+
+/// Temporal anti aliasing. The camera is jittered by a sub pixel offset every frame and the HDR image
+/// is blended with the reprojected result of the previous frames, then copied back into the HDR target.
+pub struct TaaPass {
+    /// How much of the history is kept every frame.
+    pub feedback: f32,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    depth_bg_layout: wgpu::BindGroupLayout,
+    bind_group: GraphBindGroup,
+    depth_bind_group: GraphBindGroup,
+    frame_index: usize,
+    history_generation: Option<u64>
+}
+
+impl TaaPass {
+    pub fn new(ctx: &WgpuContext, uniforms: &UniformManager) -> Self {
+        let bind_group_layout = BindGroupManager::create_texture_bind_group_layout(&ctx.device, [TL::Float, TL::Float]).unwrap();
+
+        // the depth target has a stencil aspect, so it's loaded through a depth only view without a sampler
+        let depth_bg_layout = ctx.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Depth,
+                },
+                count: None,
+            }],
+            label: Some("TAA_Depth_Bind_Group_Layout"),
+        });
+
+        let pipeline = Self::create_pipeline(ctx, uniforms, &bind_group_layout, &depth_bg_layout);
+
+        Self {
+            feedback: 0.9,
+            pipeline,
+            bind_group_layout,
+            depth_bg_layout,
+            bind_group: GraphBindGroup::new(),
+            depth_bind_group: GraphBindGroup::new(),
+            frame_index: 0,
+            history_generation: None
+        }
+    }
+
+    pub fn register_textures(ctx: &WgpuContext, graph: &mut RenderGraph) {
+        graph.add_texture(&ctx.device, GRAPH_TAA_RESOLVE, TransientTextureDesc::render_target(HDR_TEX_FORMAT).with_usage(wgpu::TextureUsages::COPY_SRC));
+        graph.add_texture(&ctx.device, GRAPH_TAA_HISTORY, TransientTextureDesc::render_target(HDR_TEX_FORMAT).with_usage(wgpu::TextureUsages::COPY_DST));
+    }
+
+    fn create_pipeline(ctx: &WgpuContext, uniforms: &UniformManager, bind_group_layout: &wgpu::BindGroupLayout, depth_bg_layout: &wgpu::BindGroupLayout) -> wgpu::RenderPipeline {
+        let shader_code = std::fs::read_to_string("res/shaders/taa.wgsl").unwrap();
+        let shader_module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("TAA_Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_code.into()),
+        });
+
+        PipelineBuilder::new(
+            "taa pipeline",
+            &[bind_group_layout, depth_bg_layout, &uniforms.taa.bind_group_layout],
+            &[],
+            &shader_module,
+            [HDR_TEX_FORMAT],
+        )
+        .with_blend(wgpu::BlendState::REPLACE)
+        .build(&ctx.device)
+    }
+
+    /// Advances the halton sequence and returns this frame's projection offset in NDC.
+    pub fn next_jitter(&mut self, (width, height): (u32, u32)) -> [f32; 2] {
+        self.frame_index = (self.frame_index + 1) % TAA_JITTER_SAMPLES;
+
+        let x = halton(self.frame_index + 1, 2) - 0.5;
+        let y = halton(self.frame_index + 1, 3) - 0.5;
+
+        [2.0 * x / width as f32, 2.0 * y / height as f32]
+    }
+
+    /// The history is thrown away when the targets were reallocated or TAA was switched off in between.
+    pub fn needs_reset(&self, graph: &RenderGraph) -> bool {
+        self.history_generation != Some(graph.get_generation())
+    }
+
+    pub fn invalidate_history(&mut self) {
+        self.history_generation = None;
+    }
+
+    pub fn render(&mut self, ctx: &WgpuContext, encoder: &mut wgpu::CommandEncoder, graph: &RenderGraph, uniforms: &UniformManager) {
+        let bind_group = self.bind_group.get(&ctx.device, graph, &self.bind_group_layout, &[GRAPH_HDR, GRAPH_TAA_HISTORY]);
+        let depth_bg_layout = &self.depth_bg_layout;
+        let depth_bind_group = self.depth_bind_group.get_with(graph, |graph| {
+            let depth_view = graph.get_texture(GRAPH_DEPTH).texture.create_view(&wgpu::TextureViewDescriptor {
+                aspect: wgpu::TextureAspect::DepthOnly,
+                ..Default::default()
+            });
+
+            ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: depth_bg_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&depth_view),
+                }],
+                label: Some("TAA_Depth_Bind_Group"),
+            })
+        });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("TAA_Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: graph.get_view(GRAPH_TAA_RESOLVE),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, bind_group, &[]);
+            render_pass.set_bind_group(1, depth_bind_group, &[]);
+            render_pass.set_bind_group(2, &uniforms.taa.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // the resolved image replaces the HDR target for the following passes and becomes the next history
+        let resolve = &graph.get_texture(GRAPH_TAA_RESOLVE).texture;
+        for target in [GRAPH_HDR, GRAPH_TAA_HISTORY] {
+            encoder.copy_texture_to_texture(resolve.as_image_copy(), graph.get_texture(target).texture.as_image_copy(), resolve.size());
+        }
+
+        self.history_generation = Some(graph.get_generation());
+    }
+
+    pub fn hotload_shader(&mut self, ctx: &WgpuContext, uniforms: &UniformManager) {
+        self.pipeline = Self::create_pipeline(ctx, uniforms, &self.bind_group_layout, &self.depth_bg_layout);
+    }
+}
+
+fn halton(mut index: usize, base: usize) -> f32 {
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+
+    result
+}
@@ -1,45 +1,75 @@
-use crate::{asset_manager::AssetManager, common::{create_info::{GameObjectCreateInfo, LightObjectCreateInfo, MeshNodeCreateInfo}, enums::LightType, types::MeshRenderingInfo}, objects::{animated_game_object::AnimatedGameObject, game_object::GameObject, light_object::LightObject}, utils::json::{load_level}};
+use std::collections::{HashMap, HashSet};
+
+use cgmath::{InnerSpace, One, Rotation, SquareMatrix};
+
+use crate::{asset_manager::AssetManager, common::{constants::{LEVEL_FORMAT_VERSION, MAX_HIERARCHY_DEPTH, PREFAB_FORMAT_VERSION}, create_info::{AnimatedGameObjectCreateInfo, GameObjectCreateInfo, LevelCreateInfo, LightObjectCreateInfo, MeshNodeCreateInfo, ParentCreateInfo, PrefabCreateInfo, PrefabInstanceCreateInfo, PrefabOverrideCreateInfo, SceneNodeRef}, enums::{LightType, SceneNodeKind}, types::MeshRenderingInfo}, objects::{animated_game_object::AnimatedGameObject, game_object::GameObject, light_object::LightObject}, prefab::{apply_overrides, get_overrides, get_placed_prefab, PrefabInstance}, render_passes::postprocess_pass::PostProcessSettings, utils::{json::{get_prefab_path, load_prefab}, math::{decompose_matrix, quaternion_to_euler}}};
 
 pub struct Scene {
     pub game_objects: Vec<GameObject>,
     pub animated_game_objects: Vec<AnimatedGameObject>,
-    pub lights: Vec<LightObject>
+    pub lights: Vec<LightObject>,
+    pub environment: Option<String>,
+    pub post_process: PostProcessSettings,
+    pub prefab_instances: Vec<PrefabInstance>
 }
 
 impl Scene {
-    pub fn new(asset_manager: &AssetManager) -> Self {
+    pub fn new(level: LevelCreateInfo, asset_manager: &AssetManager) -> Self {
         let mut game_objects: Vec<GameObject> = Vec::new();
         let mut animated_game_objects: Vec<AnimatedGameObject> = Vec::new();  
         let mut lights: Vec<LightObject> = Vec::new();
 
-        let level = load_level().expect("Could not load level!!");
-        //let level = load_level_copy().expect("COULD NOT LOAD LEVEL COPY");
-
         for create_info in level.game_objects {
             game_objects.push(GameObject::new(&create_info, &asset_manager));
         }
 
-        // todo: serialize animated game objects into json instead
-         let glock_create_info = GameObjectCreateInfo {
-            model_name: "glock".to_string(),
-            position: [10.0, 2.0, 0.0],
-            rotation: [1.0, 1.0, 1.0],
-            size: [1.5, 1.5, 1.5],
-            tex_scale: [1.0, 1.0],
-            mesh_rendering_info: vec![]
-        };
-
-        animated_game_objects.push(AnimatedGameObject::new(&glock_create_info, &asset_manager));
+        for create_info in level.animated_game_objects {
+            animated_game_objects.push(AnimatedGameObject::new(&create_info, &asset_manager));
+        }
 
        for create_info in level.lights {
           lights.push(LightObject::new(&create_info));
        }
 
-        Self {
+        let mut scene = Self {
             game_objects,
             animated_game_objects,
-            lights
+            lights,
+            environment: level.environment,
+            post_process: level.post_process,
+            prefab_instances: Vec::new()
+        };
+
+        for create_info in level.prefab_instances {
+            scene.add_prefab_instance(create_info, asset_manager);
         }
+
+        for link in level.parents {
+            match (scene.get_node_id(link.child), scene.get_node_id(link.parent)) {
+                (Some(child), Some(parent)) if scene.can_parent(child, parent) => scene.set_parent(child, Some(parent)),
+                _ => println!("Scene::new() error: invalid parent link {:?}!!", link)
+            }
+        }
+
+        scene.update_transforms();
+        scene
+    }
+
+    /// Scene of a new, unsaved level.
+    pub fn new_empty(asset_manager: &AssetManager) -> Self {
+        let level = LevelCreateInfo {
+            version: LEVEL_FORMAT_VERSION,
+            name: String::new(),
+            environment: None,
+            post_process: PostProcessSettings::default(),
+            game_objects: Vec::new(),
+            animated_game_objects: Vec::new(),
+            lights: Vec::new(),
+            prefab_instances: Vec::new(),
+            parents: Vec::new()
+        };
+
+        Self::new(level, asset_manager)
     }
 
     pub fn add_game_object(&mut self, create_info: &GameObjectCreateInfo, asset_manager: &AssetManager) {
@@ -47,8 +77,27 @@ impl Scene {
     }
 
     pub fn remove_game_object_by_id(&mut self, id: usize) {
+        self.detach_children(id);
         self.game_objects.retain(|g| g.id != id);
     }
+
+    pub fn add_animated_game_object(&mut self, create_info: &AnimatedGameObjectCreateInfo, asset_manager: &AssetManager) {
+        self.animated_game_objects.push(AnimatedGameObject::new(&create_info, asset_manager));
+    }
+
+    pub fn remove_animated_game_object_by_id(&mut self, id: usize) {
+        self.detach_children(id);
+        self.animated_game_objects.retain(|animated_game_object| animated_game_object.object_id != id);
+    }
+
+    pub fn insert_animated_game_object(&mut self, index: usize, animated_game_object: AnimatedGameObject) {
+        self.animated_game_objects.insert(index.min(self.animated_game_objects.len()), animated_game_object);
+    }
+
+    /// Puts a removed game object back at its old index, clamped to the end.
+    pub fn insert_game_object(&mut self, index: usize, game_object: GameObject) {
+        self.game_objects.insert(index.min(self.game_objects.len()), game_object);
+    }
 }
 
 // Lights
@@ -56,4 +105,507 @@ impl Scene {
     pub fn add_light(&mut self, create_info: &LightObjectCreateInfo) {
         self.lights.push(LightObject::new(&create_info));
     }
+
+    pub fn insert_light(&mut self, index: usize, light: LightObject) {
+        self.lights.insert(index.min(self.lights.len()), light);
+    }
+
+    pub fn remove_light_by_id(&mut self, id: usize) {
+        self.detach_children(id);
+        self.lights.retain(|light| light.id != id);
+    }
+}
+
+// Hierarchy
+impl Scene {
+    /// Propagates the world matrices from the roots down, objects whose parent is gone act as roots.
+    pub fn update_transforms(&mut self) {
+        let mut nodes: HashMap<usize, (Option<usize>, cgmath::Matrix4<f32>)> = HashMap::new();
+
+        for game_object in self.game_objects.iter() {
+            nodes.insert(game_object.id, (game_object.parent, game_object.get_local_matrix()));
+        }
+
+        for animated_game_object in self.animated_game_objects.iter() {
+            nodes.insert(animated_game_object.object_id, (animated_game_object.parent, animated_game_object.get_local_matrix()));
+        }
+
+        for light in self.lights.iter() {
+            nodes.insert(light.id, (light.parent, light.get_local_matrix()));
+        }
+
+        let mut world_matrices = HashMap::with_capacity(nodes.len());
+        for &id in nodes.keys() {
+            resolve_world_matrix(id, &nodes, &mut world_matrices, 0);
+        }
+
+        for game_object in self.game_objects.iter_mut() {
+            game_object.set_world_matrix(world_matrices[&game_object.id]);
+        }
+
+        for animated_game_object in self.animated_game_objects.iter_mut() {
+            animated_game_object.set_world_matrix(world_matrices[&animated_game_object.object_id]);
+        }
+
+        for light in self.lights.iter_mut() {
+            light.set_world_matrix(world_matrices[&light.id]);
+        }
+    }
+
+    /// Kind of the object with `id` and its index in that kind's list.
+    pub fn get_node(&self, id: usize) -> Option<(SceneNodeKind, usize)> {
+        if let Some(index) = self.game_objects.iter().position(|game_object| game_object.id == id) {
+            return Some((SceneNodeKind::GameObject, index));
+        }
+
+        if let Some(index) = self.animated_game_objects.iter().position(|animated_game_object| animated_game_object.object_id == id) {
+            return Some((SceneNodeKind::AnimatedGameObject, index));
+        }
+
+        self.lights.iter().position(|light| light.id == id).map(|index| (SceneNodeKind::Light, index))
+    }
+
+    pub fn get_node_name(&self, id: usize) -> String {
+        match self.get_node(id) {
+            Some((SceneNodeKind::GameObject, index)) => self.game_objects[index].get_model_name().to_string(),
+            Some((SceneNodeKind::AnimatedGameObject, index)) => self.animated_game_objects[index].get_model_name().to_string(),
+            Some((SceneNodeKind::Light, index)) => format!("{:?} Light", self.lights[index].light_type),
+            None => String::new()
+        }
+    }
+
+    pub fn get_parent(&self, id: usize) -> Option<usize> {
+        match self.get_node(id)? {
+            (SceneNodeKind::GameObject, index) => self.game_objects[index].parent,
+            (SceneNodeKind::AnimatedGameObject, index) => self.animated_game_objects[index].parent,
+            (SceneNodeKind::Light, index) => self.lights[index].parent
+        }
+    }
+
+    /// Sets the parent without touching the local transform, see `reparent`.
+    pub fn set_parent(&mut self, id: usize, parent: Option<usize>) {
+        match self.get_node(id) {
+            Some((SceneNodeKind::GameObject, index)) => self.game_objects[index].parent = parent,
+            Some((SceneNodeKind::AnimatedGameObject, index)) => self.animated_game_objects[index].parent = parent,
+            Some((SceneNodeKind::Light, index)) => self.lights[index].parent = parent,
+            None => {}
+        }
+    }
+
+    /// Direct children of `parent`, `None` lists the roots.
+    pub fn get_children(&self, parent: Option<usize>) -> Vec<usize> {
+        let is_child = |object_parent: Option<usize>| match parent {
+            Some(_) => object_parent == parent,
+            None => object_parent.is_none_or(|object_parent| self.get_node(object_parent).is_none())
+        };
+
+        let game_objects = self.game_objects.iter().filter(|game_object| is_child(game_object.parent)).map(|game_object| game_object.id);
+        let animated_game_objects = self.animated_game_objects.iter().filter(|animated_game_object| is_child(animated_game_object.parent)).map(|animated_game_object| animated_game_object.object_id);
+        let lights = self.lights.iter().filter(|light| is_child(light.parent)).map(|light| light.id);
+
+        game_objects.chain(animated_game_objects).chain(lights).collect()
+    }
+
+    /// False when `parent` is the object itself or one of its descendants.
+    pub fn can_parent(&self, id: usize, parent: usize) -> bool {
+        let mut current = Some(parent);
+
+        for _ in 0..MAX_HIERARCHY_DEPTH {
+            match current {
+                Some(ancestor) if ancestor == id => return false,
+                Some(ancestor) => current = self.get_parent(ancestor),
+                None => return true
+            }
+        }
+
+        false
+    }
+
+    pub fn get_world_matrix(&self, id: usize) -> Option<cgmath::Matrix4<f32>> {
+        match self.get_node(id)? {
+            (SceneNodeKind::GameObject, index) => Some(self.game_objects[index].get_model_matrix()),
+            (SceneNodeKind::AnimatedGameObject, index) => Some(self.animated_game_objects[index].get_model_matrix()),
+            (SceneNodeKind::Light, index) => Some(self.lights[index].get_world_matrix())
+        }
+    }
+
+    /// World matrix the local transform of `id` is relative to.
+    pub fn get_parent_matrix(&self, id: usize) -> cgmath::Matrix4<f32> {
+        self.get_parent(id)
+            .and_then(|parent| self.get_world_matrix(parent))
+            .unwrap_or(cgmath::Matrix4::identity())
+    }
+
+    /// Transform relative to the parent. A light has no rotation of its own, its matrix turns -Y onto
+    /// its direction so `set_local_matrix` can restore the direction too.
+    pub fn get_local_matrix(&self, id: usize) -> Option<cgmath::Matrix4<f32>> {
+        match self.get_node(id)? {
+            (SceneNodeKind::GameObject, index) => Some(self.game_objects[index].get_local_matrix()),
+            (SceneNodeKind::AnimatedGameObject, index) => Some(self.animated_game_objects[index].get_local_matrix()),
+            (SceneNodeKind::Light, index) => {
+                let light = &self.lights[index];
+                let rotation = if light.direction.magnitude2() > 0.0 {
+                    cgmath::Quaternion::between_vectors(-cgmath::Vector3::unit_y(), light.direction.normalize())
+                } else {
+                    cgmath::Quaternion::one()
+                };
+
+                Some(light.get_local_matrix() * cgmath::Matrix4::from(rotation))
+            }
+        }
+    }
+
+    /// Sets the position, rotation and size from `matrix`, lights take the translation and the
+    /// direction the rotation turns -Y onto.
+    pub fn set_local_matrix(&mut self, id: usize, matrix: cgmath::Matrix4<f32>) {
+        let (position, rotation, scale) = decompose_matrix(&matrix);
+        // the model matrices scale their local y axis by `size.z` and z by `size.y`
+        let size = cgmath::Vector3::new(scale.x, scale.z, scale.y);
+
+        match self.get_node(id) {
+            Some((SceneNodeKind::GameObject, index)) => {
+                let game_object = &mut self.game_objects[index];
+                game_object.set_position(position);
+                game_object.set_rotation_quaternion(rotation);
+                game_object.set_size(size);
+            }
+            Some((SceneNodeKind::AnimatedGameObject, index)) => {
+                let animated_game_object = &mut self.animated_game_objects[index];
+                animated_game_object.set_position(position);
+                animated_game_object.set_rotation(quaternion_to_euler(rotation));
+                animated_game_object.set_size(size);
+            }
+            Some((SceneNodeKind::Light, index)) => {
+                let light = &mut self.lights[index];
+                light.position = position;
+
+                // zero directions fall back to -Y when the light is drawn, and stay zero
+                let length = light.direction.magnitude();
+                if length > 0.0 {
+                    light.direction = rotation.rotate_vector(-cgmath::Vector3::unit_y()) * length;
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Moves `id` under `parent` keeping its world transform. Returns false when the link would create a cycle.
+    pub fn reparent(&mut self, id: usize, parent: Option<usize>) -> bool {
+        if parent.is_some_and(|parent| !self.can_parent(id, parent)) {
+            return false;
+        }
+
+        // built from the local matrix so a light's direction is part of it
+        let Some(world_matrix) = self.get_local_matrix(id).map(|local_matrix| self.get_parent_matrix(id) * local_matrix) else {
+            return false;
+        };
+
+        let parent_matrix = parent.and_then(|parent| self.get_world_matrix(parent)).unwrap_or(cgmath::Matrix4::identity());
+        let Some(inverse) = parent_matrix.invert() else {
+            println!("Scene::reparent() error: parent matrix is not invertible!!");
+            return false;
+        };
+
+        self.set_parent(id, parent);
+        self.set_local_matrix(id, inverse * world_matrix);
+
+        true
+    }
+
+    /// Moves the children of `id` up to its parent before it is removed, so none is left pointing at it.
+    fn detach_children(&mut self, id: usize) {
+        let parent = self.get_parent(id);
+
+        for child in self.get_children(Some(id)) {
+            self.reparent(child, parent);
+        }
+    }
+
+    /// Object referenced by a level file, only valid while loading it as its own objects come first in the lists.
+    fn get_node_id(&self, node: SceneNodeRef) -> Option<usize> {
+        if let Some(instance) = node.prefab_instance {
+            return self.prefab_instances.get(instance)?.get_id(SceneNodeRef::new(node.kind, node.index));
+        }
+
+        match node.kind {
+            SceneNodeKind::GameObject => self.game_objects.get(node.index).map(|game_object| game_object.id),
+            SceneNodeKind::AnimatedGameObject => self.animated_game_objects.get(node.index).map(|animated_game_object| animated_game_object.object_id),
+            SceneNodeKind::Light => self.lights.get(node.index).map(|light| light.id)
+        }
+    }
+}
+
+// Prefabs
+impl Scene {
+    /// Spawns the objects of the prefab file with the instance transform and overrides applied.
+    /// A prefab that can't be loaded keeps its instance so the level is saved with it.
+    pub fn add_prefab_instance(&mut self, create_info: PrefabInstanceCreateInfo, asset_manager: &AssetManager) -> usize {
+        self.insert_prefab_instance(self.prefab_instances.len(), create_info, &[], asset_manager)
+    }
+
+    /// Spawns the instance at `index` in the instance list. Objects listed in `ids` by their prefab
+    /// reference get that id back, as when a removed instance is restored.
+    pub fn insert_prefab_instance(&mut self, index: usize, create_info: PrefabInstanceCreateInfo, ids: &[(SceneNodeRef, usize)], asset_manager: &AssetManager) -> usize {
+        let ids: HashMap<SceneNodeRef, usize> = ids.iter().copied().collect();
+        let prefab = match load_prefab(&get_prefab_path(&create_info.prefab), asset_manager) {
+            Ok(prefab) => Some(prefab),
+            Err(e) => {
+                println!("Scene::add_prefab_instance() error: {}!!", e);
+                None
+            }
+        };
+
+        let mut instance = PrefabInstance::new(create_info);
+
+        if let Some(prefab) = prefab {
+            let placed = get_placed_prefab(&prefab, &instance.create_info);
+            let removed: HashSet<SceneNodeRef> = instance.create_info.removed.iter().copied().collect();
+            let overrides = &instance.create_info.overrides;
+
+            for (index, create_info) in placed.game_objects.iter().enumerate() {
+                let node = SceneNodeRef::new(SceneNodeKind::GameObject, index);
+                let id = (!removed.contains(&node)).then(|| {
+                    let mut game_object = GameObject::new(&apply_overrides(create_info, overrides, node), asset_manager);
+                    game_object.id = ids.get(&node).copied().unwrap_or(game_object.id);
+                    let id = game_object.id;
+                    self.game_objects.push(game_object);
+                    id
+                });
+
+                instance.game_object_ids.push(id);
+            }
+
+            for (index, create_info) in placed.animated_game_objects.iter().enumerate() {
+                let node = SceneNodeRef::new(SceneNodeKind::AnimatedGameObject, index);
+                let id = (!removed.contains(&node)).then(|| {
+                    let mut animated_game_object = AnimatedGameObject::new(&apply_overrides(create_info, overrides, node), asset_manager);
+                    animated_game_object.object_id = ids.get(&node).copied().unwrap_or(animated_game_object.object_id);
+                    let id = animated_game_object.object_id;
+                    self.animated_game_objects.push(animated_game_object);
+                    id
+                });
+
+                instance.animated_game_object_ids.push(id);
+            }
+
+            for (index, create_info) in placed.lights.iter().enumerate() {
+                let node = SceneNodeRef::new(SceneNodeKind::Light, index);
+                let id = (!removed.contains(&node)).then(|| {
+                    let mut light = LightObject::new(&apply_overrides(create_info, overrides, node));
+                    light.id = ids.get(&node).copied().unwrap_or(light.id);
+                    let id = light.id;
+                    self.lights.push(light);
+                    id
+                });
+
+                instance.light_ids.push(id);
+            }
+
+            for link in prefab.parents.iter() {
+                if let (Some(child), Some(parent)) = (instance.get_id(link.child), instance.get_id(link.parent)) {
+                    if self.can_parent(child, parent) {
+                        self.set_parent(child, Some(parent));
+                    }
+                }
+            }
+
+            instance.prefab = Some(prefab);
+        }
+
+        let index = index.min(self.prefab_instances.len());
+        self.prefab_instances.insert(index, instance);
+        index
+    }
+
+    /// Removes the instance at `index` together with the objects spawned from it.
+    pub fn remove_prefab_instance(&mut self, index: usize) {
+        if index >= self.prefab_instances.len() {
+            return;
+        }
+
+        for (_, id) in self.prefab_instances.remove(index).get_objects() {
+            self.remove_game_object_by_id(id);
+            self.remove_animated_game_object_by_id(id);
+            self.remove_light_by_id(id);
+        }
+    }
+
+    /// Level file contents of the scene. Objects spawned from prefabs are saved as their instance
+    /// with the fields that differ from the prefab, every other object is saved in full.
+    pub fn get_level_create_info(&self, name: String, asset_manager: &AssetManager) -> LevelCreateInfo {
+        let mut node_refs: HashMap<usize, SceneNodeRef> = HashMap::new();
+        let mut prefab_instances: Vec<PrefabInstanceCreateInfo> = Vec::new();
+        let mut instance_prefabs: Vec<Option<&PrefabCreateInfo>> = Vec::new();
+
+        for instance in self.prefab_instances.iter() {
+            let Some(prefab) = &instance.prefab else {
+                prefab_instances.push(instance.create_info.clone());
+                instance_prefabs.push(None);
+                continue;
+            };
+
+            let objects: Vec<(SceneNodeRef, usize)> = instance.get_objects().into_iter()
+                .filter(|(_, id)| self.get_node(*id).is_some())
+                .collect();
+
+            // every object of the instance was deleted
+            if objects.is_empty() {
+                continue;
+            }
+
+            let alive: HashSet<SceneNodeRef> = objects.iter().map(|(node, _)| *node).collect();
+            let removed = (0..prefab.game_objects.len()).map(|index| SceneNodeRef::new(SceneNodeKind::GameObject, index))
+                .chain((0..prefab.animated_game_objects.len()).map(|index| SceneNodeRef::new(SceneNodeKind::AnimatedGameObject, index)))
+                .chain((0..prefab.lights.len()).map(|index| SceneNodeRef::new(SceneNodeKind::Light, index)))
+                .filter(|node| !alive.contains(node))
+                .collect();
+
+            let mut create_info = PrefabInstanceCreateInfo { overrides: Vec::new(), removed, ..instance.create_info.clone() };
+            let placed = get_placed_prefab(prefab, &create_info);
+            let instance_index = prefab_instances.len();
+
+            for (node, id) in objects {
+                let values = match self.get_node(id) {
+                    Some((SceneNodeKind::GameObject, index)) => get_overrides(&placed.game_objects[node.index], &self.game_objects[index].get_create_info(asset_manager)),
+                    Some((SceneNodeKind::AnimatedGameObject, index)) => get_overrides(&placed.animated_game_objects[node.index], &self.animated_game_objects[index].get_create_info(asset_manager)),
+                    Some((SceneNodeKind::Light, index)) => get_overrides(&placed.lights[node.index], &self.lights[index].get_create_info()),
+                    None => continue
+                };
+
+                if !values.is_empty() {
+                    create_info.overrides.push(PrefabOverrideCreateInfo { object: node, values });
+                }
+
+                node_refs.insert(id, SceneNodeRef { prefab_instance: Some(instance_index), ..node });
+            }
+
+            prefab_instances.push(create_info);
+            instance_prefabs.push(Some(prefab));
+        }
+
+        let instance_ids: HashSet<usize> = node_refs.keys().copied().collect();
+
+        let mut game_objects = Vec::new();
+        for game_object in self.game_objects.iter().filter(|game_object| !instance_ids.contains(&game_object.id)) {
+            node_refs.insert(game_object.id, SceneNodeRef::new(SceneNodeKind::GameObject, game_objects.len()));
+            game_objects.push(game_object.get_create_info(asset_manager));
+        }
+
+        let mut animated_game_objects = Vec::new();
+        for animated_game_object in self.animated_game_objects.iter().filter(|animated_game_object| !instance_ids.contains(&animated_game_object.object_id)) {
+            node_refs.insert(animated_game_object.object_id, SceneNodeRef::new(SceneNodeKind::AnimatedGameObject, animated_game_objects.len()));
+            animated_game_objects.push(animated_game_object.get_create_info(asset_manager));
+        }
+
+        let mut lights = Vec::new();
+        for light in self.lights.iter().filter(|light| !instance_ids.contains(&light.id)) {
+            node_refs.insert(light.id, SceneNodeRef::new(SceneNodeKind::Light, lights.len()));
+            lights.push(light.get_create_info());
+        }
+
+        let ids = self.game_objects.iter().map(|game_object| game_object.id)
+            .chain(self.animated_game_objects.iter().map(|animated_game_object| animated_game_object.object_id))
+            .chain(self.lights.iter().map(|light| light.id));
+
+        let parents = ids.filter_map(|id| {
+            let child = *node_refs.get(&id)?;
+            let parent = *node_refs.get(&self.get_parent(id)?)?;
+
+            // links of the prefab itself come back with the instance
+            if let (Some(instance), true) = (child.prefab_instance, child.prefab_instance == parent.prefab_instance) {
+                let link = ParentCreateInfo { child: SceneNodeRef { prefab_instance: None, ..child }, parent: SceneNodeRef { prefab_instance: None, ..parent } };
+                if instance_prefabs[instance].is_some_and(|prefab| prefab.parents.contains(&link)) {
+                    return None;
+                }
+            }
+
+            Some(ParentCreateInfo { child, parent })
+        }).collect();
+
+        LevelCreateInfo {
+            version: LEVEL_FORMAT_VERSION,
+            name,
+            environment: self.environment.clone(),
+            post_process: self.post_process.clone(),
+            game_objects,
+            animated_game_objects,
+            lights,
+            prefab_instances,
+            parents
+        }
+    }
+
+    /// Prefab of the objects in `ids` and their descendants. The roots keep their world position
+    /// relative to the first root, which becomes the prefab origin.
+    pub fn get_prefab_create_info(&self, name: String, ids: &HashSet<usize>, asset_manager: &AssetManager) -> PrefabCreateInfo {
+        let mut included: HashSet<usize> = HashSet::new();
+        let mut pending: Vec<usize> = ids.iter().copied().filter(|id| self.get_node(*id).is_some()).collect();
+
+        while let Some(id) = pending.pop() {
+            if included.insert(id) {
+                pending.extend(self.get_children(Some(id)));
+            }
+        }
+
+        let is_root = |id: usize| self.get_parent(id).is_none_or(|parent| !included.contains(&parent));
+        let world_position = |id: usize| self.get_world_matrix(id).map_or(cgmath::Vector3::new(0.0, 0.0, 0.0), |matrix| matrix.w.truncate());
+
+        let ordered: Vec<usize> = self.game_objects.iter().map(|game_object| game_object.id)
+            .chain(self.animated_game_objects.iter().map(|animated_game_object| animated_game_object.object_id))
+            .chain(self.lights.iter().map(|light| light.id))
+            .filter(|id| included.contains(id))
+            .collect();
+
+        let origin = ordered.iter().copied().find(|id| is_root(*id)).map_or(cgmath::Vector3::new(0.0, 0.0, 0.0), world_position);
+        let root_position = |id: usize, position: [f32; 3]| -> [f32; 3] { if is_root(id) { (world_position(id) - origin).into() } else { position } };
+
+        let mut prefab = PrefabCreateInfo { version: PREFAB_FORMAT_VERSION, name, game_objects: Vec::new(), animated_game_objects: Vec::new(), lights: Vec::new(), parents: Vec::new() };
+        let mut node_refs: HashMap<usize, SceneNodeRef> = HashMap::new();
+
+        for &id in ordered.iter() {
+            match self.get_node(id) {
+                Some((SceneNodeKind::GameObject, index)) => {
+                    let mut create_info = self.game_objects[index].get_create_info(asset_manager);
+                    create_info.position = root_position(id, create_info.position);
+                    node_refs.insert(id, SceneNodeRef::new(SceneNodeKind::GameObject, prefab.game_objects.len()));
+                    prefab.game_objects.push(create_info);
+                }
+                Some((SceneNodeKind::AnimatedGameObject, index)) => {
+                    let mut create_info = self.animated_game_objects[index].get_create_info(asset_manager);
+                    create_info.game_object.position = root_position(id, create_info.game_object.position);
+                    node_refs.insert(id, SceneNodeRef::new(SceneNodeKind::AnimatedGameObject, prefab.animated_game_objects.len()));
+                    prefab.animated_game_objects.push(create_info);
+                }
+                Some((SceneNodeKind::Light, index)) => {
+                    let mut create_info = self.lights[index].get_create_info();
+                    create_info.position = root_position(id, create_info.position);
+                    node_refs.insert(id, SceneNodeRef::new(SceneNodeKind::Light, prefab.lights.len()));
+                    prefab.lights.push(create_info);
+                }
+                None => {}
+            }
+        }
+
+        prefab.parents = ordered.iter().filter_map(|&id| {
+            let parent = self.get_parent(id).filter(|parent| included.contains(parent))?;
+            Some(ParentCreateInfo { child: node_refs[&id], parent: node_refs[&parent] })
+        }).collect();
+
+        prefab
+    }
+}
+
+fn resolve_world_matrix(id: usize, nodes: &HashMap<usize, (Option<usize>, cgmath::Matrix4<f32>)>, world_matrices: &mut HashMap<usize, cgmath::Matrix4<f32>>, depth: usize) -> cgmath::Matrix4<f32> {
+    if let Some(world_matrix) = world_matrices.get(&id) {
+        return *world_matrix;
+    }
+
+    let (parent, local_matrix) = nodes[&id];
+    let world_matrix = match parent.filter(|parent| nodes.contains_key(parent) && depth < MAX_HIERARCHY_DEPTH) {
+        Some(parent) => resolve_world_matrix(parent, nodes, world_matrices, depth + 1) * local_matrix,
+        None => local_matrix
+    };
+
+    world_matrices.insert(id, world_matrix);
+    world_matrix
 }
\ No newline at end of file
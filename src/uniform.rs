@@ -24,7 +24,7 @@ where T: ToU8Slice {
 
         let bind_group_layout = BindGroupManager::create_uniform_bind_group_layout(
             &device,
-            wgpu::ShaderStages::VERTEX_FRAGMENT,
+            wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
             Some(format!("bind_group_layout for {}", std::any::type_name::<T>()).as_ref()))
         .unwrap();
 
@@ -1,12 +1,19 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
 
 use cgmath::Matrix;
 use cgmath::SquareMatrix;
 
 use crate::asset_manager::AssetManager;
 use crate::bind_group_manager::BindGroupManager;
-use crate::common::constants::MAX_LIGHTS;
+use crate::dynamic_uniform::DynamicUniform;
+use crate::common::constants::{BLOOM_MIP_COUNT, SSAO_KERNEL_SIZE, MAX_LIGHTS, MAX_POINT_SHADOWS, MAX_SHADOW_CASTERS, MAX_SHADOW_MATRICES, MAX_SPOT_SHADOWS, MAX_DIRECTIONAL_SHADOWS, SHADOW_CASCADE_COUNT};
+use crate::common::enums::LightType;
 use crate::scene::Scene;
+use crate::objects::animated_game_object::AnimatedGameObject;
+use crate::render_passes::bloom_pass::BloomSettings;
+use crate::render_passes::post_effect_pass::PostEffect;
+use crate::render_passes::postprocess_pass::PostProcessSettings;
+use crate::render_passes::ssao_pass::SsaoSettings;
 use crate::ssbo::SSBO;
 use crate::{animation::skin::MAX_JOINTS_PER_MESH, camera::{Camera, Projection}, objects::game_object::GameObject, uniform::Uniform, wgpu_context::WgpuContext};
 
@@ -88,10 +95,15 @@ impl CameraUniform {
         }
     }
 
-    pub fn update(&mut self, camera: &Camera) {
+    /// `jitter` is a sub pixel offset in NDC, zero unless TAA is on.
+    pub fn update(&mut self, camera: &Camera, jitter: [f32; 2]) {
+        let mut projection = camera.get_projection().calc_matrix();
+        projection.z.x += jitter[0];
+        projection.z.y += jitter[1];
+
         self.view_position = camera.position.to_homogeneous().into();
         self.view = camera.calc_matrix().into();
-        self.projection = camera.get_projection().calc_matrix().into();
+        self.projection = projection.into();
     }
 }
 
@@ -99,74 +111,337 @@ impl CameraUniform {
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct LightUniform {
    pub position: [f32; 3],
-   pub _pad0: u32,
+   pub light_type: u32,
    pub color: [f32; 3],
-   pub _pad1: u32,
+   pub _pad0: u32,
+   pub direction: [f32; 3],
+   pub shadow_index: i32,
    pub strength: f32,
    pub radius: f32,
-   pub _pad2: u32,
-   pub _pad3: u32
+   pub inner_cone_cos: f32,
+   pub outer_cone_cos: f32
 }
 
 impl LightUniform {
     pub fn new() -> Self {
         Self {
           position: [2.0, 2.0, 2.0],
-          _pad0: 0,
+          light_type: LightType::Point.to_shader_index(),
           color: [1.0, 1.0, 1.0],
-          _pad1: 0,
+          _pad0: 0,
+          direction: [0.0, -1.0, 0.0],
+          shadow_index: -1,
           strength: 50.0,
           radius: 5.0,
-          _pad2: 0,
-          _pad3: 0
+          inner_cone_cos: 1.0,
+          outer_cone_cos: 1.0
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ShadowViewUniform {
+    pub view_projection: [[f32; 4]; 4],
+}
+
+impl ShadowViewUniform {
+    pub fn new() -> Self {
+        Self {
+          view_projection: cgmath::Matrix4::identity().into()
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ShadowCasterUniform {
+    pub light_type: u32,
+    pub layer: u32,
+    pub matrix_index: u32,
+    pub _pad0: u32
+}
+
+impl ShadowCasterUniform {
+    pub fn new() -> Self {
+        Self {
+          light_type: 0,
+          layer: 0,
+          matrix_index: 0,
+          _pad0: 0
+        }
+    }
+
+    pub fn get_face_count(&self) -> usize {
+        if self.light_type == LightType::Point.to_shader_index() {
+            6
+        } else if self.light_type == LightType::Directional.to_shader_index() {
+            SHADOW_CASCADE_COUNT
+        } else {
+            1
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ShadowsUniform {
+    pub matrices: [[[f32; 4]; 4]; MAX_SHADOW_MATRICES],
+    pub casters: [ShadowCasterUniform; MAX_SHADOW_CASTERS],
+    pub cascade_splits: [f32; SHADOW_CASCADE_COUNT],
+}
+
+impl ShadowsUniform {
+    pub fn new() -> Self {
+        Self {
+          matrices: [cgmath::Matrix4::<f32>::identity().into(); MAX_SHADOW_MATRICES],
+          casters: [ShadowCasterUniform::new(); MAX_SHADOW_CASTERS],
+          cascade_splits: [0.0; SHADOW_CASCADE_COUNT]
         }
     }
 }
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct BlurUniform {
-    pub direction: [f32; 2],
-    pub sample_distance: f32,
+pub struct BloomUniform {
+    pub threshold: f32,
+    pub knee: f32,
+    pub radius: f32,
+    pub intensity: f32
+}
+
+impl BloomUniform {
+  pub fn new() -> Self {
+    Self {
+      threshold: 1.0,
+      knee: 0.5,
+      radius: 1.0,
+      intensity: 0.0
+    }
+  }
+
+  pub fn update(&mut self, settings: &BloomSettings) {
+    self.threshold = settings.threshold;
+    self.knee = settings.knee;
+    self.radius = settings.radius;
+    // every mip of the chain is added on the way up, normalized so the intensity doesn't depend on the chain length
+    self.intensity = if settings.enabled { settings.intensity / BLOOM_MIP_COUNT as f32 } else { 0.0 };
+  }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SsaoUniform {
+    pub projection: [[f32; 4]; 4],
+    pub inv_projection: [[f32; 4]; 4],
+    pub kernel: [[f32; 4]; SSAO_KERNEL_SIZE],
+    pub radius: f32,
+    pub bias: f32,
+    pub intensity: f32,
+    pub power: f32,
+    pub sample_count: u32,
+    pub blur_sharpness: f32,
+    pub _pad0: u32,
+    pub _pad1: u32
+}
+
+impl SsaoUniform {
+  pub fn new() -> Self {
+    Self {
+      projection: cgmath::Matrix4::identity().into(),
+      inv_projection: cgmath::Matrix4::identity().into(),
+      kernel: Self::generate_kernel(),
+      radius: 0.5,
+      bias: 0.025,
+      intensity: 1.0,
+      power: 1.0,
+      sample_count: SSAO_KERNEL_SIZE as u32,
+      blur_sharpness: 4.0,
+      _pad0: 0,
+      _pad1: 0
+    }
+  }
+
+  /// Hemisphere samples along +Z, packed closer to the origin so nearby geometry counts more.
+  fn generate_kernel() -> [[f32; 4]; SSAO_KERNEL_SIZE] {
+    use cgmath::InnerSpace;
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    let mut kernel = [[0.0; 4]; SSAO_KERNEL_SIZE];
+
+    for (i, sample) in kernel.iter_mut().enumerate() {
+      let direction = cgmath::Vector3::new(
+        rng.gen_range(-1.0..1.0),
+        rng.gen_range(-1.0..1.0),
+        rng.gen_range(0.0..1.0)
+      );
+      let direction = if direction.magnitude2() > 0.0 { direction.normalize() } else { cgmath::Vector3::unit_z() };
+
+      let scale = i as f32 / SSAO_KERNEL_SIZE as f32;
+      let scale = 0.1 + scale * scale * 0.9;
+      let position = direction * rng.gen_range(0.0..1.0) * scale;
+
+      *sample = [position.x, position.y, position.z, 0.0];
+    }
+
+    kernel
+  }
+
+  pub fn update(&mut self, camera: &Camera, settings: &SsaoSettings) {
+    let projection = camera.get_projection().calc_matrix();
+
+    self.projection = projection.into();
+    self.inv_projection = projection.invert().unwrap_or(cgmath::Matrix4::identity()).into();
+    self.radius = settings.radius;
+    self.bias = settings.bias;
+    self.intensity = settings.intensity;
+    self.power = settings.power;
+    self.sample_count = settings.sample_count.min(SSAO_KERNEL_SIZE as u32);
+    self.blur_sharpness = settings.blur_sharpness;
+  }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TaaUniform {
+    pub inv_view_proj: [[f32; 4]; 4],
+    pub view_proj: [[f32; 4]; 4],
+    pub prev_view_proj: [[f32; 4]; 4],
+    pub feedback: f32,
+    pub reset: u32,
+    pub _pad0: u32,
+    pub _pad1: u32
+}
+
+impl TaaUniform {
+  pub fn new() -> Self {
+    Self {
+      inv_view_proj: cgmath::Matrix4::identity().into(),
+      view_proj: cgmath::Matrix4::identity().into(),
+      prev_view_proj: cgmath::Matrix4::identity().into(),
+      feedback: 0.9,
+      reset: 1,
+      _pad0: 0,
+      _pad1: 0
+    }
+  }
+
+  /// The inverse includes the jitter so positions rebuilt from the depth buffer are exact,
+  /// the other two don't so the camera motion between frames can be measured without it.
+  pub fn update(&mut self, camera: &Camera, jitter: [f32; 2], feedback: f32, reset: bool) {
+    let projection = camera.get_projection().calc_matrix();
+    let mut jittered_projection = projection;
+    jittered_projection.z.x += jitter[0];
+    jittered_projection.z.y += jitter[1];
+
+    let view = camera.calc_matrix();
+    let view_proj = projection * view;
+
+    self.prev_view_proj = if reset { view_proj.into() } else { self.view_proj };
+    self.view_proj = view_proj.into();
+    self.inv_view_proj = (jittered_projection * view).invert().unwrap_or(cgmath::Matrix4::identity()).into();
+    self.feedback = feedback;
+    self.reset = reset as u32;
+  }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PostProcessUniform {
+    pub tonemapper: u32,
+    pub exposure: f32,
+    pub auto_exposure: u32,
+    _pad: f32
+}
+
+impl PostProcessUniform {
+  pub fn new() -> Self {
+    Self {
+      tonemapper: 0,
+      exposure: 0.0,
+      auto_exposure: 0,
+      _pad: 0.0
+    }
+  }
+
+  pub fn update(&mut self, settings: &PostProcessSettings) {
+    self.tonemapper = settings.tonemapper.to_shader_index();
+    self.exposure = settings.exposure;
+    self.auto_exposure = settings.auto_exposure as u32;
+  }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ExposureUniform {
+    pub min_log_luminance: f32,
+    pub log_luminance_range: f32,
+    pub adaptation: f32,
     _pad: f32
 }
 
-impl BlurUniform {
+impl ExposureUniform {
   pub fn new() -> Self {
     Self {
-      direction: [1.0, 0.0],
-      sample_distance: 1.0,
+      min_log_luminance: -8.0,
+      log_luminance_range: 12.0,
+      adaptation: 1.0,
       _pad: 0.0
     }
   }
 
-  pub fn update(&mut self, direction: [f32; 2], sample_distance: f32) {
-    self.direction[0] = direction[0];
-    self.direction[1] = direction[1];
-    self.sample_distance = sample_distance;
+  /// `reset` snaps to the measured luminance instead of adapting towards it.
+  pub fn update(&mut self, settings: &PostProcessSettings, delta_time: f32, reset: bool) {
+    self.min_log_luminance = settings.min_log_luminance;
+    self.log_luminance_range = (settings.max_log_luminance - settings.min_log_luminance).max(0.01);
+    self.adaptation = if reset { 1.0 } else { 1.0 - (-delta_time * settings.adaptation_speed).exp() };
+  }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PostEffectUniform {
+    pub kind: u32,
+    pub time: f32,
+    _pad0: f32,
+    _pad1: f32,
+    pub values: [[f32; 4]; 3]
+}
+
+impl PostEffectUniform {
+  pub fn new(effect: &PostEffect, time: f32) -> Self {
+    Self {
+      kind: effect.kind.to_shader_index(),
+      time,
+      _pad0: 0.0,
+      _pad1: 0.0,
+      values: effect.kind.get_shader_values()
+    }
   }
 }
 
 pub struct UniformManager {
     pub camera: Uniform<CameraUniform>,
-    pub models: HashMap<usize, Uniform<ModelUniform>>,
+    pub models: DynamicUniform<ModelUniform>,
     pub bind_group_layout: wgpu::BindGroupLayout,
-    pub animation: Uniform<AnimationUniform>,
-    pub blurs: Vec<Uniform<BlurUniform>>,
-    pub lights_ssbo: SSBO
+    /// Joint palette per animated object, bound with the object's dynamic offset.
+    pub animation: DynamicUniform<AnimationUniform>,
+    pub bloom: Uniform<BloomUniform>,
+    pub post_process: Uniform<PostProcessUniform>,
+    pub exposure: Uniform<ExposureUniform>,
+    pub post_effects: DynamicUniform<PostEffectUniform>,
+    pub lights_ssbo: SSBO,
+    pub shadows: Uniform<ShadowsUniform>,
+    pub shadow_views: Vec<Uniform<ShadowViewUniform>>,
+    pub shadow_caster_count: usize,
+    pub ssao: Uniform<SsaoUniform>,
+    pub taa: Uniform<TaaUniform>
 }
 
 impl UniformManager {
     pub fn new(ctx: &WgpuContext, scene: &Scene) -> Self {
-      let mut model_uniforms: HashMap<usize, Uniform<ModelUniform>> = HashMap::new();
-
-      for game_object in scene.game_objects.iter() {
-        model_uniforms.insert(game_object.id, Uniform::new(ModelUniform::new(), &ctx.device));
-      }
-
-      for animated_game_object in scene.animated_game_objects.iter() {
-        model_uniforms.insert(animated_game_object.object_id, Uniform::new(ModelUniform::new(), &ctx.device));
-      }
+      let model_uniforms = DynamicUniform::new(&ctx.device, scene.game_objects.len() + scene.animated_game_objects.len());
 
       let lights_ssbo = SSBO::new((std::mem::size_of::<LightUniform>() * MAX_LIGHTS as usize) as u64, &ctx.device);
 
@@ -176,91 +451,194 @@ impl UniformManager {
         Some("Uniform_Bind_Group_Layout"))
       .unwrap();
 
-      let BLUR_PASSES = 4;
-      let mut blurs = Vec::new();
-      for _ in 0..BLUR_PASSES {
-        blurs.push(Uniform::new(BlurUniform::new(), &ctx.device));
+      let mut shadow_views = Vec::with_capacity(MAX_SHADOW_MATRICES);
+      for _ in 0..MAX_SHADOW_MATRICES {
+        shadow_views.push(Uniform::new(ShadowViewUniform::new(), &ctx.device));
       }
 
       Self {
         models: model_uniforms,
-        animation: Uniform::new(AnimationUniform::new(), &ctx.device),
+        animation: DynamicUniform::new(&ctx.device, scene.animated_game_objects.len()),
         camera: Uniform::new(CameraUniform::new(), &ctx.device),
-        blurs,
+        bloom: Uniform::new(BloomUniform::new(), &ctx.device),
+        post_process: Uniform::new(PostProcessUniform::new(), &ctx.device),
+        exposure: Uniform::new(ExposureUniform::new(), &ctx.device),
+        post_effects: DynamicUniform::new(&ctx.device, 4),
         bind_group_layout,
-        lights_ssbo
+        lights_ssbo,
+        shadows: Uniform::new(ShadowsUniform::new(), &ctx.device),
+        shadow_views,
+        shadow_caster_count: 0,
+        ssao: Uniform::new(SsaoUniform::new(), &ctx.device),
+        taa: Uniform::new(TaaUniform::new(), &ctx.device)
       }
     }
 
-    pub fn create_model(&mut self, ctx: &WgpuContext, id: usize) {
-      self.models.insert(id, Uniform::new(ModelUniform::new(), &ctx.device));
-    }
-
     pub fn submit_model_uniforms(&mut self, ctx: &WgpuContext, scene: &Scene) {
+      let alive_ids: HashSet<usize> = scene.game_objects.iter().map(|game_object| game_object.id)
+        .chain(scene.animated_game_objects.iter().map(|animated_game_object| animated_game_object.object_id))
+        .collect();
+
+      self.models.retain(&alive_ids);
+
+      let mut model_uniform = ModelUniform::new();
+
       for animated_game_object in scene.animated_game_objects.iter() {
-        if !self.models.contains_key(&animated_game_object.object_id) {
-           self.create_model(&ctx, animated_game_object.object_id);
-        }
-        if let Some(model_uniform) = self.models.get_mut(&animated_game_object.object_id) {
-          model_uniform.value_mut().update(&animated_game_object.get_model_matrix(), &animated_game_object.tex_scale);
-          model_uniform.update(&ctx.queue);
-        }
+        model_uniform.update(&animated_game_object.get_model_matrix(), &animated_game_object.tex_scale);
+        self.models.set(animated_game_object.object_id, &model_uniform);
       }
 
       for game_object in scene.game_objects.iter() {
-        if !self.models.contains_key(&game_object.id) {
-          self.create_model(&ctx, game_object.id);
-        }
-
-        if let Some(model_uniform) = self.models.get_mut(&game_object.id) {
-          model_uniform.value_mut().update(&game_object.get_model_matrix(), &game_object.tex_scale);
-          model_uniform.update(&ctx.queue);  
-        }
+        model_uniform.update(&game_object.get_model_matrix(), &game_object.tex_scale);
+        self.models.set(game_object.id, &model_uniform);
       }
+
+      self.models.update(&ctx);
     }
 
-    pub fn submit_animation_uniforms(&mut self, ctx: &WgpuContext, asset_manager: &mut AssetManager, delta_time: std::time::Duration) {
-         if let Some(glb_model) = asset_manager.get_model_by_name_mut("glock") {
-          glb_model.update(delta_time.as_secs_f32());
-          let skin_uniform = self.animation.value_mut();
+    /// Advances the playback of every animated object and uploads its joints into its own slot.
+    /// Objects share their model's nodes, so each one poses the model in turn before its joints are read.
+    pub fn submit_animation_uniforms(&mut self, ctx: &WgpuContext, asset_manager: &mut AssetManager, animated_game_objects: &mut [AnimatedGameObject], delta_time: std::time::Duration) {
+      let alive_ids: HashSet<usize> = animated_game_objects.iter().map(|animated_game_object| animated_game_object.object_id).collect();
+      self.animation.retain(&alive_ids);
+
+      for animated_game_object in animated_game_objects.iter_mut() {
+        let mut skin_uniform = AnimationUniform::new();
+
+        if let Some(glb_model) = asset_manager.get_model_by_name_mut(animated_game_object.get_model_name()) {
+          animated_game_object.update_animation(glb_model, delta_time.as_secs_f32());
 
           if let Some(skin) = glb_model.skins.get(0) {
-           for (i, joint) in skin.joints().iter().enumerate() {
-            if i >= MAX_JOINTS_PER_MESH {
-             break; 
+            for (i, joint) in skin.joints().iter().enumerate() {
+              if i >= MAX_JOINTS_PER_MESH {
+               break; 
+              }
+
+              // Convert cgmath::Matrix4 to [[f32; 4]; 4]
+              skin_uniform.joint_matrices[i] = joint.matrix().into();
             }
+          }
+        }
 
-           // Convert cgmath::Matrix4 to [[f32; 4]; 4]
-           skin_uniform.joint_matrices[i] = joint.matrix().into();
-         }
-       }
+        self.animation.set(animated_game_object.object_id, &skin_uniform);
       }
 
-      self.animation.update(&ctx.queue);
+      self.animation.update(ctx);
     }
 
-    pub fn submit_light_uniforms(&mut self, ctx: &WgpuContext, scene: &Scene) {
+    pub fn submit_light_uniforms(&mut self, ctx: &WgpuContext, scene: &Scene, camera: &Camera) {
       let mut light_uniforms: Vec<LightUniform> = Vec::with_capacity(scene.lights.len());
+      let mut shadows = ShadowsUniform::new();
+      let mut point_shadow_count = 0;
+      let mut spot_shadow_count = 0;
+      let mut directional_shadow_count = 0;
+      let mut caster_count = 0;
+      let mut matrix_count = 0;
+
       for light in scene.lights.iter() {
+        let mut shadow_index = -1;
+
+        if light.casts_shadows {
+          let shadow_maps = match light.light_type {
+            LightType::Point if point_shadow_count < MAX_POINT_SHADOWS => {
+              point_shadow_count += 1;
+              Some(((point_shadow_count - 1) * 6, light.get_point_shadow_matrices().to_vec()))
+            },
+            LightType::Spot if spot_shadow_count < MAX_SPOT_SHADOWS => {
+              spot_shadow_count += 1;
+              Some((spot_shadow_count - 1, vec![light.get_spot_shadow_matrix()]))
+            },
+            LightType::Directional if directional_shadow_count < MAX_DIRECTIONAL_SHADOWS => {
+              light.get_cascade_shadow_matrices(camera).map(|(matrices, splits)| {
+                shadows.cascade_splits = splits;
+                directional_shadow_count += 1;
+                ((directional_shadow_count - 1) * SHADOW_CASCADE_COUNT, matrices.to_vec())
+              })
+            },
+            _ => None
+          };
+
+          if let Some((layer, matrices)) = shadow_maps {
+            shadows.casters[caster_count] = ShadowCasterUniform {
+              light_type: light.light_type.to_shader_index(),
+              layer: layer as u32,
+              matrix_index: matrix_count as u32,
+              _pad0: 0
+            };
+
+            for matrix in matrices {
+              shadows.matrices[matrix_count] = matrix.into();
+              self.shadow_views[matrix_count].value_mut().view_projection = matrix.into();
+              self.shadow_views[matrix_count].update(&ctx.queue);
+              matrix_count += 1;
+            }
+
+            shadow_index = caster_count as i32;
+            caster_count += 1;
+          }
+        }
+
+        let (inner_cone_cos, outer_cone_cos) = light.get_cone_cosines();
+
         let light_uniform = LightUniform {
-          position: light.position.into(),
-          _pad0: 0,
+          position: light.get_world_position().into(),
+          light_type: light.light_type.to_shader_index(),
           color: light.color.into(),
-          _pad1: 0,
+          _pad0: 0,
+          direction: light.get_direction().into(),
+          shadow_index,
           strength: light.strength,
           radius: light.radius,
-          _pad2: 0,
-          _pad3: 0
+          inner_cone_cos,
+          outer_cone_cos
         };
 
         light_uniforms.push(light_uniform);
       }
 
       self.lights_ssbo.update(&ctx, (light_uniforms.len() * std::mem::size_of::<LightUniform>()) as u64, &light_uniforms);
+
+      self.shadow_caster_count = caster_count;
+      self.shadows.set_value(shadows);
+      self.shadows.update(&ctx.queue);
     }
 
-    pub fn submit_camera_uniforms(&mut self, ctx: &WgpuContext, camera: &Camera) {
-      self.camera.value_mut().update(&camera);
+    pub fn submit_camera_uniforms(&mut self, ctx: &WgpuContext, camera: &Camera, jitter: [f32; 2]) {
+      self.camera.value_mut().update(&camera, jitter);
       self.camera.update(&ctx.queue);
     }
+
+    pub fn submit_ssao_uniforms(&mut self, ctx: &WgpuContext, camera: &Camera, settings: &SsaoSettings) {
+      self.ssao.value_mut().update(&camera, settings);
+      self.ssao.update(&ctx.queue);
+    }
+
+    pub fn submit_taa_uniforms(&mut self, ctx: &WgpuContext, camera: &Camera, jitter: [f32; 2], feedback: f32, reset: bool) {
+      self.taa.value_mut().update(&camera, jitter, feedback, reset);
+      self.taa.update(&ctx.queue);
+    }
+
+    pub fn submit_bloom_uniforms(&mut self, ctx: &WgpuContext, settings: &BloomSettings) {
+      self.bloom.value_mut().update(settings);
+      self.bloom.update(&ctx.queue);
+    }
+
+    pub fn submit_post_process_uniforms(&mut self, ctx: &WgpuContext, settings: &PostProcessSettings, delta_time: f32, reset_exposure: bool) {
+      self.post_process.value_mut().update(settings);
+      self.post_process.update(&ctx.queue);
+
+      self.exposure.value_mut().update(settings, delta_time, reset_exposure);
+      self.exposure.update(&ctx.queue);
+    }
+
+    /// One slot per stack entry, keyed by its index.
+    pub fn submit_post_effect_uniforms(&mut self, ctx: &WgpuContext, effects: &[PostEffect], time: f32) {
+      self.post_effects.retain(&(0..effects.len()).collect());
+
+      for (index, effect) in effects.iter().enumerate() {
+        self.post_effects.set(index, &PostEffectUniform::new(effect, time));
+      }
+
+      self.post_effects.update(&ctx);
+    }
 }
\ No newline at end of file
@@ -1,44 +1,144 @@
-use std::{fs::{self, File}, io::Write};
+use std::{fs::{self, File}, io::Write, path::{Path, PathBuf}};
 
-use crate::{asset_manager::AssetManager, common::create_info::{GameObjectCreateInfo, LevelCreateInfo, LightObjectCreateInfo}, engine::GameData, objects::{game_object::GameObject, light_object::LightObject}};
+use crate::{asset_manager::AssetManager, common::{constants::{LEVEL_BINARY_EXTENSION, LEVEL_DIRECTORY, PREFAB_DIRECTORY, RECENT_LEVELS_FILE}, create_info::{LevelCreateInfo, PrefabCreateInfo}, errors::LoadLevelError}, engine::GameData, objects::{game_object::GameObject, light_object::LightObject}, utils::{level_binary::{decode_level, encode_level}, level_format::{parse_level, parse_prefab, validate_level, validate_prefab}}};
 
-pub fn save_level(game_data: &GameData) {
-    let mut game_object_create_infos: Vec<GameObjectCreateInfo> = Vec::new();
-    let mut light_create_infos: Vec<LightObjectCreateInfo> = Vec::new();
+/// Accepts a level name from `res/scenes` or a path to a level file, names without an extension
+/// are JSON levels.
+pub fn get_level_path(name_or_path: &str) -> PathBuf {
+    let path = Path::new(name_or_path);
 
-    for game_object in game_data.scene.game_objects.iter() {
-      game_object_create_infos.push(game_object.get_create_info(&game_data.asset_manager));
+    if path.extension().is_some() || path.components().count() > 1 {
+      path.to_path_buf()
+    } else {
+      Path::new(LEVEL_DIRECTORY).join(format!("{name_or_path}.json"))
     }
+}
 
-    for light in game_data.scene.lights.iter() {
-      light_create_infos.push(light.get_create_info());
-    }
+/// Whether the level at `path` is saved in the binary format, picked by its extension.
+pub fn is_binary_level(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == LEVEL_BINARY_EXTENSION)
+}
+
+/// JSON and binary level files in `res/scenes`, sorted by name.
+pub fn list_levels() -> Vec<PathBuf> {
+    list_files(LEVEL_DIRECTORY, &["json", LEVEL_BINARY_EXTENSION])
+}
 
-    let level_create_info = LevelCreateInfo {
-      name: "test".to_string(),
-      game_objects: game_object_create_infos,
-      lights: light_create_infos
+pub fn get_prefab_path(name: &str) -> PathBuf {
+    Path::new(PREFAB_DIRECTORY).join(format!("{name}.json"))
+}
+
+/// Prefab files in `res/prefabs`, sorted by name.
+pub fn list_prefabs() -> Vec<PathBuf> {
+    list_files(PREFAB_DIRECTORY, &["json"])
+}
+
+fn list_files(directory: &str, extensions: &[&str]) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(directory) else {
+      return Vec::new();
     };
 
-    let json = serde_json::to_string_pretty(&level_create_info).unwrap();
+    let mut levels: Vec<PathBuf> = entries.flatten()
+      .map(|entry| entry.path())
+      .filter(|path| path.extension().is_some_and(|extension| extensions.iter().any(|allowed| extension == *allowed)))
+      .collect();
 
-    match File::create(String::from("res/scenes/") + &level_create_info.name + ".json").unwrap().write_all(json.as_bytes()) {
-      Ok(_msg) => { println!("Level saved!") },
-      Err(err) => { println!("Could not save level. Error: {}", err) }
+    levels.sort();
+    levels
+}
+
+/// Writes the scene to `path`, the level is named after the file.
+pub fn save_level(game_data: &GameData, path: &Path) -> std::io::Result<()> {
+    let name = path.file_stem().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let level_create_info = game_data.scene.get_level_create_info(name, &game_data.asset_manager);
+
+    write_level(&level_create_info, path)?;
+    println!("Level saved to {}!", path.display());
+
+    Ok(())
+}
+
+/// Recently opened levels, most recent first. Levels that were deleted since are left out.
+pub fn load_recent_levels() -> Vec<PathBuf> {
+    let Ok(json) = fs::read_to_string(RECENT_LEVELS_FILE) else {
+      return Vec::new();
+    };
+
+    match serde_json::from_str::<Vec<PathBuf>>(&json) {
+      Ok(levels) => levels.into_iter().filter(|path| path.exists()).collect(),
+      Err(e) => {
+        println!("load_recent_levels() error: {}!!", e);
+        Vec::new()
+      }
     }
 }
 
-#[derive(Debug)]
-pub enum LoadLevelError {
-  ReadError,
-  SerdeError
+pub fn save_recent_levels(levels: &[PathBuf]) {
+    let json = serde_json::to_string_pretty(levels).unwrap();
+
+    if let Err(e) = fs::write(RECENT_LEVELS_FILE, json) {
+      println!("save_recent_levels() error: {}!!", e);
+    }
 }
 
-pub fn load_level() -> Result<LevelCreateInfo, LoadLevelError> {
-  let path = "res/scenes/test.json";
-  let json = fs::read_to_string(path).map_err(|_| LoadLevelError::ReadError)?;
+pub fn save_prefab(prefab: &PrefabCreateInfo, path: &Path) {
+    let json = serde_json::to_string_pretty(prefab).unwrap();
 
-  let level: LevelCreateInfo = serde_json::from_str(&json).map_err(|_| LoadLevelError::SerdeError)?;
+    match fs::create_dir_all(PREFAB_DIRECTORY).and_then(|_| File::create(path)).and_then(|mut file| file.write_all(json.as_bytes())) {
+      Ok(_msg) => { println!("Prefab saved to {}!", path.display()) },
+      Err(err) => { println!("Could not save prefab. Error: {}", err) }
+    }
+}
+
+/// Writes the level in the format picked by the extension of `path`.
+pub fn write_level(level: &LevelCreateInfo, path: &Path) -> std::io::Result<()> {
+  let bytes = if is_binary_level(path) {
+    encode_level(level)
+  } else {
+    serde_json::to_string_pretty(level).unwrap().into_bytes()
+  };
+
+  File::create(path)?.write_all(&bytes)
+}
+
+/// Reads, migrates and validates the level at `path`.
+pub fn load_level(path: &Path, asset_manager: &AssetManager) -> Result<LevelCreateInfo, LoadLevelError> {
+  let (level, json) = read_level_file(path)?;
+  validate_level(&level, json.as_deref(), path, asset_manager)?;
 
   Ok(level)
+}
+
+/// Reads and migrates the level at `path` without checking it against the assets.
+pub fn read_level(path: &Path) -> Result<LevelCreateInfo, LoadLevelError> {
+  read_level_file(path).map(|(level, _json)| level)
+}
+
+/// Level and, for JSON levels, the text it was parsed from.
+fn read_level_file(path: &Path) -> Result<(LevelCreateInfo, Option<String>), LoadLevelError> {
+  let read_error = |error| LoadLevelError::Read { path: path.to_path_buf(), error };
+
+  if is_binary_level(path) {
+    let bytes = fs::read(path).map_err(read_error)?;
+    Ok((decode_level(&bytes, path)?, None))
+  } else {
+    let json = fs::read_to_string(path).map_err(read_error)?;
+    Ok((parse_level(&json, path)?, Some(json)))
+  }
+}
+
+/// Rewrites the level at `input` to `output`, the formats are picked by the extensions.
+pub fn convert_level(input: &Path, output: &Path) -> Result<(), String> {
+  let level = read_level(input).map_err(|e| e.to_string())?;
+
+  write_level(&level, output).map_err(|e| format!("could not write {}: {}", output.display(), e))
+}
+
+pub fn load_prefab(path: &Path, asset_manager: &AssetManager) -> Result<PrefabCreateInfo, LoadLevelError> {
+  let json = fs::read_to_string(path).map_err(|error| LoadLevelError::Read { path: path.to_path_buf(), error })?;
+
+  let prefab = parse_prefab(&json, path)?;
+  validate_prefab(&prefab, Some(&json), path, asset_manager)?;
+
+  Ok(prefab)
 }
\ No newline at end of file
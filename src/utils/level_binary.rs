@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use crate::{common::{constants::{LEVEL_BINARY_MAGIC, LEVEL_FORMAT_VERSION}, create_info::LevelCreateInfo, errors::LoadLevelError}, utils::level_format::migrate_level};
+
+// magic, level format version (u32), payload length (u64) and payload CRC-32 (u32), little endian
+const HEADER_SIZE: usize = 20;
+
+/// Compact level file, the header followed by the level as MessagePack. Fields are written by
+/// name so the payload is self-describing, which older versions need to be migrated.
+pub fn encode_level(level: &LevelCreateInfo) -> Vec<u8> {
+    let payload = rmp_serde::to_vec_named(level).unwrap();
+
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+    bytes.extend_from_slice(&LEVEL_BINARY_MAGIC);
+    bytes.extend_from_slice(&level.version.to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+
+    bytes
+}
+
+pub fn decode_level(bytes: &[u8], path: &Path) -> Result<LevelCreateInfo, LoadLevelError> {
+    let corrupt = |message: String| LoadLevelError::Corrupt { path: path.to_path_buf(), message };
+
+    if bytes.len() < HEADER_SIZE || bytes[0..4] != LEVEL_BINARY_MAGIC {
+        return Err(corrupt("not a binary level file".to_string()));
+    }
+
+    let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    let length = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+    let checksum = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
+    let payload = &bytes[HEADER_SIZE..];
+
+    if version > LEVEL_FORMAT_VERSION {
+        return Err(LoadLevelError::UnsupportedVersion { path: path.to_path_buf(), version: version as u64, supported: LEVEL_FORMAT_VERSION });
+    }
+
+    if payload.len() as u64 != length {
+        return Err(corrupt(format!("expected {} payload bytes, found {}", length, payload.len())));
+    }
+
+    let actual_checksum = crc32fast::hash(payload);
+    if actual_checksum != checksum {
+        return Err(corrupt(format!("checksum mismatch, expected {:08x}, found {:08x}", checksum, actual_checksum)));
+    }
+
+    let parse_error = |e: rmp_serde::decode::Error| LoadLevelError::Parse { path: path.to_path_buf(), location: None, message: e.to_string() };
+
+    // only older levels take the slower detour through a JSON value
+    if version == LEVEL_FORMAT_VERSION {
+        rmp_serde::from_slice(payload).map_err(parse_error)
+    } else {
+        migrate_level(rmp_serde::from_slice(payload).map_err(parse_error)?, path)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::utils::json::{convert_level, read_level};
+
+    use super::*;
+
+    #[test]
+    fn converts_test_level_losslessly() {
+        let directory = std::env::temp_dir().join(format!("yhwh_level_binary_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let (binary, json) = (directory.join("test.lvl"), directory.join("test.json"));
+
+        let source = Path::new("res/scenes/test.json");
+        convert_level(source, &binary).unwrap();
+        convert_level(&binary, &json).unwrap();
+
+        let original = serde_json::to_value(read_level(source).unwrap()).unwrap();
+        let converted = serde_json::to_value(read_level(&json).unwrap()).unwrap();
+        let _ = std::fs::remove_dir_all(&directory);
+
+        assert_eq!(original, converted);
+    }
+
+    #[test]
+    fn rejects_corrupt_payload() {
+        let level = read_level(Path::new("res/scenes/test.json")).unwrap();
+        let mut bytes = encode_level(&level);
+        *bytes.last_mut().unwrap() ^= 0xff;
+
+        assert!(matches!(decode_level(&bytes, Path::new("test.lvl")), Err(LoadLevelError::Corrupt { .. })));
+    }
+}
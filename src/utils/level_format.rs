@@ -0,0 +1,443 @@
+use std::path::Path;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{asset_manager::AssetManager, common::{constants::{LEVEL_FORMAT_VERSION, PREFAB_FORMAT_VERSION}, create_info::{AnimatedGameObjectCreateInfo, GameObjectCreateInfo, LevelCreateInfo, ParentCreateInfo, PrefabCreateInfo, PrefabInstanceCreateInfo, SceneNodeRef}, enums::SceneNodeKind, errors::{LevelValidationError, LevelValidationErrorKind, LoadLevelError}}, prefab::apply_override, utils::json::{get_prefab_path, load_prefab}};
+
+/// Upgrades a document from the version it's indexed by to the next one, in place.
+type Migration = fn(&mut Map<String, Value>);
+
+/// `LEVEL_MIGRATIONS[n]` upgrades a version `n` level to `n + 1`. Bumping `LEVEL_FORMAT_VERSION`
+/// takes a migration for the previous version so existing levels keep loading.
+const LEVEL_MIGRATIONS: [Migration; LEVEL_FORMAT_VERSION as usize] = [migrate_level_v0];
+const PREFAB_MIGRATIONS: [Migration; PREFAB_FORMAT_VERSION as usize] = [migrate_prefab_v0];
+
+/// Levels saved before the format was versioned, the sections added since then are written out empty.
+fn migrate_level_v0(document: &mut Map<String, Value>) {
+    for section in ["animated_game_objects", "prefab_instances", "parents"] {
+        document.entry(section).or_insert_with(|| Value::Array(Vec::new()));
+    }
+}
+
+/// Prefabs saved before the format was versioned already have the version 1 layout.
+fn migrate_prefab_v0(_document: &mut Map<String, Value>) {}
+
+/// Level from the JSON text of `path`, migrated to the current format.
+pub fn parse_level(source: &str, path: &Path) -> Result<LevelCreateInfo, LoadLevelError> {
+    parse_versioned(source, path, &LEVEL_MIGRATIONS)
+}
+
+pub fn parse_prefab(source: &str, path: &Path) -> Result<PrefabCreateInfo, LoadLevelError> {
+    parse_versioned(source, path, &PREFAB_MIGRATIONS)
+}
+
+/// Level decoded from another format, migrated to the current one.
+pub fn migrate_level(document: Value, path: &Path) -> Result<LevelCreateInfo, LoadLevelError> {
+    migrate_versioned(document, path, &LEVEL_MIGRATIONS, None)
+}
+
+fn parse_versioned<T: DeserializeOwned>(source: &str, path: &Path, migrations: &[Migration]) -> Result<T, LoadLevelError> {
+    let value: Value = serde_json::from_str(source).map_err(|e| json_error(e, path))?;
+
+    if get_version(&value, path, migrations, Some(source))? == migrations.len() as u64 {
+        // parsed again from the text so errors keep their line and column
+        return serde_json::from_str(source).map_err(|e| json_error(e, path));
+    }
+
+    migrate_versioned(value, path, migrations, Some(source))
+}
+
+fn migrate_versioned<T: DeserializeOwned>(mut value: Value, path: &Path, migrations: &[Migration], source: Option<&str>) -> Result<T, LoadLevelError> {
+    let version = get_version(&value, path, migrations, source)?;
+
+    if let Value::Object(document) = &mut value {
+        for (from, migration) in migrations.iter().enumerate().skip(version as usize) {
+            migration(document);
+            document.insert("version".to_string(), Value::from(from + 1));
+        }
+    }
+
+    serde_json::from_value(value).map_err(|e| json_error(e, path))
+}
+
+/// Format version of the document, files from before versioning are version 0.
+fn get_version(value: &Value, path: &Path, migrations: &[Migration], source: Option<&str>) -> Result<u64, LoadLevelError> {
+    let Value::Object(document) = value else {
+        return Err(LoadLevelError::Parse { path: path.to_path_buf(), location: None, message: "expected an object".to_string() });
+    };
+
+    let version = match document.get("version") {
+        None => 0,
+        Some(version) => version.as_u64().ok_or_else(|| LoadLevelError::Parse {
+            path: path.to_path_buf(),
+            location: source.and_then(|source| locate(source, "/version")),
+            message: format!("version must be an unsigned integer, found {}", version)
+        })?
+    };
+
+    if version > migrations.len() as u64 {
+        return Err(LoadLevelError::UnsupportedVersion { path: path.to_path_buf(), version, supported: migrations.len() as u32 });
+    }
+
+    Ok(version)
+}
+
+fn json_error(e: serde_json::Error, path: &Path) -> LoadLevelError {
+    LoadLevelError::Parse {
+        path: path.to_path_buf(),
+        location: (e.line() > 0).then(|| (e.line(), e.column())),
+        message: e.to_string()
+    }
+}
+
+/// Checks the level against the loaded assets before any object is spawned. `source` is the JSON
+/// text the level was parsed from, used to report where the invalid values are.
+pub fn validate_level(level: &LevelCreateInfo, source: Option<&str>, path: &Path, asset_manager: &AssetManager) -> Result<(), LoadLevelError> {
+    let mut validator = Validator { source, asset_manager, errors: Vec::new() };
+
+    validator.validate_objects(&level.game_objects, &level.animated_game_objects);
+    validator.validate_links(&level.parents, [level.game_objects.len(), level.animated_game_objects.len(), level.lights.len()], level.prefab_instances.len());
+
+    for (index, instance) in level.prefab_instances.iter().enumerate() {
+        validator.validate_prefab_instance(&format!("/prefab_instances/{index}"), instance);
+    }
+
+    validator.finish(path)
+}
+
+pub fn validate_prefab(prefab: &PrefabCreateInfo, source: Option<&str>, path: &Path, asset_manager: &AssetManager) -> Result<(), LoadLevelError> {
+    let mut validator = Validator { source, asset_manager, errors: Vec::new() };
+
+    validator.validate_objects(&prefab.game_objects, &prefab.animated_game_objects);
+    // prefabs don't nest, so their links never point into an instance
+    validator.validate_links(&prefab.parents, [prefab.game_objects.len(), prefab.animated_game_objects.len(), prefab.lights.len()], 0);
+
+    validator.finish(path)
+}
+
+struct Validator<'a> {
+    source: Option<&'a str>,
+    asset_manager: &'a AssetManager,
+    errors: Vec<LevelValidationError>
+}
+
+impl Validator<'_> {
+    fn report(&mut self, pointer: String, kind: LevelValidationErrorKind) {
+        let location = self.source.and_then(|source| locate(source, &pointer));
+        self.errors.push(LevelValidationError { pointer, location, kind });
+    }
+
+    fn validate_objects(&mut self, game_objects: &[GameObjectCreateInfo], animated_game_objects: &[AnimatedGameObjectCreateInfo]) {
+        for (index, create_info) in game_objects.iter().enumerate() {
+            self.validate_game_object(&format!("/game_objects/{index}"), create_info);
+        }
+
+        for (index, create_info) in animated_game_objects.iter().enumerate() {
+            // the game object fields are flattened into the animated object
+            let pointer = format!("/animated_game_objects/{index}");
+            self.validate_game_object(&pointer, &create_info.game_object);
+
+            // unknown models are already reported
+            let asset_manager = self.asset_manager;
+            let model_name = &create_info.game_object.model_name;
+            let model = asset_manager.has_model(model_name).then(|| asset_manager.get_model_by_name(model_name)).flatten();
+
+            if let (Some(animation), Some(model)) = (&create_info.animation, model) {
+                if !model.get_animation_names().contains(animation) {
+                    self.report(format!("{pointer}/animation"), LevelValidationErrorKind::UnknownAnimation { model: model_name.clone(), animation: animation.clone() });
+                }
+            }
+        }
+    }
+
+    fn validate_game_object(&mut self, pointer: &str, create_info: &GameObjectCreateInfo) {
+        let asset_manager = self.asset_manager;
+        let model = asset_manager.has_model(&create_info.model_name).then(|| asset_manager.get_model_by_name(&create_info.model_name)).flatten();
+
+        if model.is_none() {
+            self.report(format!("{pointer}/model_name"), LevelValidationErrorKind::UnknownModel(create_info.model_name.clone()));
+        }
+
+        for (index, mesh_info) in create_info.mesh_rendering_info.iter().enumerate() {
+            // unknown models are already reported
+            if model.is_some_and(|model| !model.has_mesh(&mesh_info.mesh_name)) {
+                self.report(
+                    format!("{pointer}/mesh_rendering_info/{index}/mesh_name"),
+                    LevelValidationErrorKind::UnknownMesh { model: create_info.model_name.clone(), mesh: mesh_info.mesh_name.clone() }
+                );
+            }
+
+            if !self.asset_manager.has_material(&mesh_info.material_name) {
+                self.report(format!("{pointer}/mesh_rendering_info/{index}/material_name"), LevelValidationErrorKind::UnknownMaterial(mesh_info.material_name.clone()));
+            }
+        }
+    }
+
+    /// `counts` are the object counts by `SceneNodeKind`. Links into prefab instances only check the
+    /// instance, the prefab file decides how many objects it has.
+    fn validate_links(&mut self, parents: &[ParentCreateInfo], counts: [usize; 3], prefab_instance_count: usize) {
+        for (index, link) in parents.iter().enumerate() {
+            for (field, node) in [("child", link.child), ("parent", link.parent)] {
+                let is_valid = match node.prefab_instance {
+                    Some(instance) => instance < prefab_instance_count,
+                    None => is_in_range(node, counts)
+                };
+
+                if !is_valid {
+                    self.report(format!("/parents/{index}/{field}"), LevelValidationErrorKind::InvalidReference(node));
+                }
+            }
+        }
+    }
+
+    /// The prefab file has to load, and the overrides and removals have to point at its objects.
+    fn validate_prefab_instance(&mut self, pointer: &str, instance: &PrefabInstanceCreateInfo) {
+        let prefab = match load_prefab(&get_prefab_path(&instance.prefab), self.asset_manager) {
+            Ok(prefab) => prefab,
+            Err(LoadLevelError::Read { .. }) => {
+                self.report(format!("{pointer}/prefab"), LevelValidationErrorKind::MissingPrefab(instance.prefab.clone()));
+                return;
+            }
+            Err(e) => {
+                self.report(format!("{pointer}/prefab"), LevelValidationErrorKind::InvalidPrefab { prefab: instance.prefab.clone(), message: e.to_string() });
+                return;
+            }
+        };
+
+        for (index, prefab_override) in instance.overrides.iter().enumerate() {
+            let (node, values) = (prefab_override.object, &prefab_override.values);
+            let result = match node.kind {
+                SceneNodeKind::GameObject => check_override(&prefab.game_objects, node.index, values),
+                SceneNodeKind::AnimatedGameObject => check_override(&prefab.animated_game_objects, node.index, values),
+                SceneNodeKind::Light => check_override(&prefab.lights, node.index, values)
+            };
+
+            match result {
+                Some(Ok(())) => {}
+                Some(Err(e)) => self.report(format!("{pointer}/overrides/{index}/values"), LevelValidationErrorKind::InvalidOverride(e.to_string())),
+                None => self.report(format!("{pointer}/overrides/{index}/object"), LevelValidationErrorKind::InvalidReference(node))
+            }
+        }
+
+        let counts = [prefab.game_objects.len(), prefab.animated_game_objects.len(), prefab.lights.len()];
+        for (index, node) in instance.removed.iter().enumerate() {
+            if !is_in_range(*node, counts) {
+                self.report(format!("{pointer}/removed/{index}"), LevelValidationErrorKind::InvalidReference(*node));
+            }
+        }
+    }
+
+    fn finish(self, path: &Path) -> Result<(), LoadLevelError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(LoadLevelError::Invalid { path: path.to_path_buf(), errors: self.errors })
+        }
+    }
+}
+
+/// `None` when `index` is past the prefab's list, otherwise whether `values` deserialize over the object.
+fn check_override<T: Serialize + DeserializeOwned>(create_infos: &[T], index: usize, values: &Map<String, Value>) -> Option<Result<(), serde_json::Error>> {
+    create_infos.get(index).map(|create_info| apply_override(create_info, values).map(drop))
+}
+
+/// Whether `node` indexes into a list of the given `counts` by `SceneNodeKind`.
+fn is_in_range(node: SceneNodeRef, counts: [usize; 3]) -> bool {
+    node.index < counts[match node.kind {
+        SceneNodeKind::GameObject => 0,
+        SceneNodeKind::AnimatedGameObject => 1,
+        SceneNodeKind::Light => 2
+    }]
+}
+
+/// 1-based line and column of the value at the JSON `pointer` in `source`, `None` when the text
+/// doesn't contain it. Only walks the containers on the way, the rest of the text is skipped.
+fn locate(source: &str, pointer: &str) -> Option<(usize, usize)> {
+    let bytes = source.as_bytes();
+    let mut position = skip_whitespace(bytes, 0);
+
+    for segment in pointer.split('/').skip(1) {
+        match bytes.get(position)? {
+            b'{' => {
+                position = skip_whitespace(bytes, position + 1);
+
+                loop {
+                    if *bytes.get(position)? != b'"' {
+                        return None;
+                    }
+
+                    let key_end = skip_string(bytes, position)?;
+                    let key = &source[position + 1..key_end - 1];
+
+                    position = skip_whitespace(bytes, key_end);
+                    if *bytes.get(position)? != b':' {
+                        return None;
+                    }
+                    position = skip_whitespace(bytes, position + 1);
+
+                    if key == segment {
+                        break;
+                    }
+
+                    position = skip_whitespace(bytes, skip_value(bytes, position)?);
+                    if *bytes.get(position)? != b',' {
+                        return None;
+                    }
+                    position = skip_whitespace(bytes, position + 1);
+                }
+            }
+            b'[' => {
+                let index: usize = segment.parse().ok()?;
+                position = skip_whitespace(bytes, position + 1);
+
+                for _ in 0..index {
+                    position = skip_whitespace(bytes, skip_value(bytes, position)?);
+                    if *bytes.get(position)? != b',' {
+                        return None;
+                    }
+                    position = skip_whitespace(bytes, position + 1);
+                }
+
+                if *bytes.get(position)? == b']' {
+                    return None;
+                }
+            }
+            _ => return None
+        }
+    }
+
+    let before = &source[..position];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rfind('\n').map_or(before, |newline| &before[newline + 1..]).chars().count() + 1;
+
+    Some((line, column))
+}
+
+fn skip_whitespace(bytes: &[u8], mut position: usize) -> usize {
+    while bytes.get(position).is_some_and(|byte| byte.is_ascii_whitespace()) {
+        position += 1;
+    }
+
+    position
+}
+
+/// Position after the string starting at `position`.
+fn skip_string(bytes: &[u8], mut position: usize) -> Option<usize> {
+    position += 1;
+
+    loop {
+        match bytes.get(position)? {
+            b'\\' => position += 2,
+            b'"' => return Some(position + 1),
+            _ => position += 1
+        }
+    }
+}
+
+/// Position after the value starting at `position`.
+fn skip_value(bytes: &[u8], mut position: usize) -> Option<usize> {
+    match bytes.get(position)? {
+        b'"' => skip_string(bytes, position),
+        b'{' | b'[' => {
+            let mut depth = 0;
+
+            loop {
+                match bytes.get(position)? {
+                    b'"' => {
+                        position = skip_string(bytes, position)?;
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(position + 1);
+                        }
+                    }
+                    _ => {}
+                }
+
+                position += 1;
+            }
+        }
+        _ => {
+            while bytes.get(position).is_some_and(|byte| !matches!(byte, b',' | b'}' | b']') && !byte.is_ascii_whitespace()) {
+                position += 1;
+            }
+
+            Some(position)
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_unversioned_level() {
+        let source = r#"{ "name": "old", "game_objects": [], "lights": [] }"#;
+        let level = parse_level(source, Path::new("old.json")).unwrap();
+
+        assert_eq!(level.version, LEVEL_FORMAT_VERSION);
+        assert_eq!(level.name, "old");
+        assert!(level.animated_game_objects.is_empty());
+        assert!(level.prefab_instances.is_empty());
+        assert!(level.parents.is_empty());
+    }
+
+    #[test]
+    fn migration_keeps_existing_sections() {
+        let mut document = serde_json::json!({ "parents": [{ "child": 1 }] }).as_object().unwrap().clone();
+        migrate_level_v0(&mut document);
+
+        assert_eq!(document["parents"], serde_json::json!([{ "child": 1 }]));
+        assert_eq!(document["animated_game_objects"], serde_json::json!([]));
+        assert_eq!(document["prefab_instances"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let source = format!(r#"{{ "version": {}, "name": "new", "game_objects": [], "lights": [] }}"#, LEVEL_FORMAT_VERSION + 1);
+
+        match parse_level(&source, Path::new("new.json")) {
+            Err(LoadLevelError::UnsupportedVersion { version, supported, .. }) => {
+                assert_eq!(version, LEVEL_FORMAT_VERSION as u64 + 1);
+                assert_eq!(supported, LEVEL_FORMAT_VERSION);
+            }
+            other => panic!("expected UnsupportedVersion, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn locates_invalid_version() {
+        let source = "{\n  \"version\": \"one\",\n  \"name\": \"x\"\n}";
+
+        match parse_level(source, Path::new("x.json")) {
+            Err(LoadLevelError::Parse { location, .. }) => assert_eq!(location, Some((2, 14))),
+            other => panic!("expected Parse, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn locates_nested_values() {
+        let source = concat!(
+            "{\n",
+            "  \"name\": \"a \\\"quoted\\\" {name}\",\n",
+            "  \"game_objects\": [\n",
+            "    { \"model_name\": \"Cube\", \"position\": [0, 1, 2] },\n",
+            "    {\n",
+            "      \"model_name\": \"Plane\"\n",
+            "    }\n",
+            "  ]\n",
+            "}"
+        );
+
+        assert_eq!(locate(source, ""), Some((1, 1)));
+        assert_eq!(locate(source, "/name"), Some((2, 11)));
+        assert_eq!(locate(source, "/game_objects/0/position/2"), Some((4, 48)));
+        assert_eq!(locate(source, "/game_objects/1/model_name"), Some((6, 21)));
+        assert_eq!(locate(source, "/game_objects/2"), None);
+        assert_eq!(locate(source, "/lights"), None);
+    }
+}
@@ -0,0 +1,115 @@
+use cgmath::{InnerSpace, Rotation3, SquareMatrix};
+
+/// Composes x, y, z euler degrees in that order, as game objects store their rotation.
+pub fn euler_to_quaternion(euler: cgmath::Vector3<f32>) -> cgmath::Quaternion<f32> {
+    cgmath::Quaternion::from_angle_x(cgmath::Deg(euler.x))
+        * cgmath::Quaternion::from_angle_y(cgmath::Deg(euler.y))
+        * cgmath::Quaternion::from_angle_z(cgmath::Deg(euler.z))
+}
+
+/// Inverse of `euler_to_quaternion`, the angles are returned in 0-360.
+pub fn quaternion_to_euler(rotation: cgmath::Quaternion<f32>) -> cgmath::Vector3<f32> {
+    let matrix = cgmath::Matrix3::from(rotation);
+
+    // Rx * Ry * Rz has sin(y) in row 0 of column 2 and cos(y) in the rest of it,
+    // atan2 keeps the precision asin loses close to ±90
+    let y = matrix.z.x.atan2((matrix.z.y * matrix.z.y + matrix.z.z * matrix.z.z).sqrt());
+    let (x, z) = if matrix.z.x.abs() < 0.9999 {
+        ((-matrix.z.y).atan2(matrix.z.z), (-matrix.y.x).atan2(matrix.x.x))
+    } else {
+        // gimbal lock, x and z rotate around the same axis
+        (matrix.y.z.atan2(matrix.y.y), 0.0)
+    };
+
+    cgmath::Vector3::new(x, y, z).map(|angle| angle.to_degrees().rem_euclid(360.0))
+}
+
+/// Splits a `translation * rotation * scale` matrix into position, rotation and scale along each axis.
+/// Shear can't be represented and is dropped, a mirrored matrix gets a negative x scale.
+pub fn decompose_matrix(matrix: &cgmath::Matrix4<f32>) -> (cgmath::Vector3<f32>, cgmath::Quaternion<f32>, cgmath::Vector3<f32>) {
+    let (x, y, z) = (matrix.x.truncate(), matrix.y.truncate(), matrix.z.truncate());
+
+    let mut scale = cgmath::Vector3::new(x.magnitude(), y.magnitude(), z.magnitude());
+    if cgmath::Matrix3::from_cols(x, y, z).determinant() < 0.0 {
+        scale.x = -scale.x;
+    }
+
+    let axis = |column: cgmath::Vector3<f32>, scale: f32, fallback: cgmath::Vector3<f32>| {
+        if scale.abs() > 1e-6 { column / scale } else { fallback }
+    };
+
+    // orthonormalized so a sheared matrix still gives a rotation
+    let x = axis(x, scale.x, cgmath::Vector3::unit_x()).normalize();
+    let y = axis(y, scale.y, cgmath::Vector3::unit_y());
+    let y = (y - x * x.dot(y)).normalize();
+    let z = x.cross(y);
+
+    (matrix.w.truncate(), cgmath::Quaternion::from(cgmath::Matrix3::from_cols(x, y, z)), scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_matrix_eq(actual: cgmath::Matrix4<f32>, expected: cgmath::Matrix4<f32>) {
+        let actual: [[f32; 4]; 4] = actual.into();
+        let expected: [[f32; 4]; 4] = expected.into();
+
+        for (a, b) in actual.iter().flatten().zip(expected.iter().flatten()) {
+            assert!((a - b).abs() < 1e-4, "expected {:?}, got {:?}", expected, actual);
+        }
+    }
+
+    fn compose(position: cgmath::Vector3<f32>, rotation: cgmath::Quaternion<f32>, scale: cgmath::Vector3<f32>) -> cgmath::Matrix4<f32> {
+        cgmath::Matrix4::from_translation(position) * cgmath::Matrix4::from(rotation) * cgmath::Matrix4::from_nonuniform_scale(scale.x, scale.y, scale.z)
+    }
+
+    #[test]
+    fn euler_round_trips() {
+        let euler = cgmath::Vector3::new(30.0, 45.0, 300.0);
+        let result = quaternion_to_euler(euler_to_quaternion(euler));
+
+        assert!((result - euler).magnitude() < 1e-3, "expected {:?}, got {:?}", euler, result);
+    }
+
+    #[test]
+    fn euler_round_trips_at_pitch_singularity() {
+        for pitch in [90.0, -90.0] {
+            let rotation = euler_to_quaternion(cgmath::Vector3::new(30.0, pitch, 40.0));
+            let result = quaternion_to_euler(rotation);
+
+            // x and z share an axis at ±90 so only the rotation itself has to survive
+            assert_eq!(result.z, 0.0);
+            assert_matrix_eq(cgmath::Matrix4::from(euler_to_quaternion(result)), cgmath::Matrix4::from(rotation));
+        }
+    }
+
+    #[test]
+    fn decomposes_non_uniform_scale() {
+        let position = cgmath::Vector3::new(1.0, -2.0, 3.0);
+        let rotation = euler_to_quaternion(cgmath::Vector3::new(20.0, 70.0, 10.0));
+        let scale = cgmath::Vector3::new(2.0, 0.5, 3.0);
+        let matrix = compose(position, rotation, scale);
+
+        let (result_position, result_rotation, result_scale) = decompose_matrix(&matrix);
+
+        assert!((result_position - position).magnitude() < 1e-4);
+        assert!((result_scale - scale).magnitude() < 1e-4, "expected {:?}, got {:?}", scale, result_scale);
+        assert_matrix_eq(cgmath::Matrix4::from(result_rotation), cgmath::Matrix4::from(rotation));
+        assert_matrix_eq(compose(result_position, result_rotation, result_scale), matrix);
+    }
+
+    #[test]
+    fn decomposes_mirrored_matrix() {
+        let matrix = compose(
+            cgmath::Vector3::new(0.0, 1.0, 0.0),
+            euler_to_quaternion(cgmath::Vector3::new(0.0, 90.0, 45.0)),
+            cgmath::Vector3::new(1.0, -2.0, 1.5)
+        );
+
+        let (position, rotation, scale) = decompose_matrix(&matrix);
+
+        assert!(scale.x < 0.0);
+        assert_matrix_eq(compose(position, rotation, scale), matrix);
+    }
+}
@@ -1,3 +1,6 @@
 pub mod file;
 pub mod unique_id;
-pub mod json;
\ No newline at end of file
+pub mod json;
+pub mod math;
+pub mod level_format;
+pub mod level_binary;
\ No newline at end of file
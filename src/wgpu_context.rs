@@ -2,16 +2,33 @@ use std::sync::Arc;
 
 use winit::window::Window;
 
-use crate::common::errors::WgpuContextError;
+use crate::common::{constants::REQUIRED_BIND_GROUPS, errors::WgpuContextError};
 
 pub struct WgpuContext {
-    pub surface: wgpu::Surface<'static>,
+    /// `None` for headless contexts, which render into `offscreen_texture` instead.
+    pub surface: Option<wgpu::Surface<'static>>,
+    pub offscreen_texture: Option<wgpu::Texture>,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
     pub is_surface_configured: bool,
 }
 
+/// The texture a frame is rendered into, either the swapchain image or the headless offscreen texture.
+pub struct Frame {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    surface_texture: Option<wgpu::SurfaceTexture>
+}
+
+impl Frame {
+    pub fn present(self) {
+        if let Some(surface_texture) = self.surface_texture {
+            surface_texture.present();
+        }
+    }
+}
+
 impl WgpuContext {
     pub async fn new(window: &Arc<Window>) -> Result<Self, WgpuContextError> {
        let size = window.inner_size();
@@ -35,18 +52,7 @@ impl WgpuContext {
         let info = adapter.get_info();
         println!("Using Backend: {:?}", info.backend); 
 
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    required_features: wgpu::Features::DEPTH32FLOAT_STENCIL8,
-                    required_limits: wgpu::Limits::default(),
-                    memory_hints: Default::default(),
-                },
-                None,
-            )
-            .await
-            .map_err(WgpuContextError::RequestDeviceError)?;
+        let (device, queue) = Self::request_device(&adapter).await?;
 
         let surface_caps = surface.get_capabilities(&adapter);
 
@@ -57,8 +63,11 @@ impl WgpuContext {
             .copied()
             .unwrap_or(surface_caps.formats[0]);
 
+        // copying out of the swapchain is only needed for screenshots, so it's optional
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT | (surface_caps.usages & wgpu::TextureUsages::COPY_SRC);
+
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
             width: size.width,
             height: size.height,
@@ -75,7 +84,106 @@ impl WgpuContext {
             device, 
             is_surface_configured: true,
             queue,
-            surface
+            surface: Some(surface),
+            offscreen_texture: None
+        })
+    }
+
+    /// Creates a context without a window that renders into an offscreen texture. Falls back to a
+    /// software adapter when no hardware one is available so it also runs on CI machines.
+    pub async fn new_headless(width: u32, height: u32) -> Result<Self, WgpuContextError> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let mut adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await;
+
+        if adapter.is_none() {
+            adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::default(),
+                    compatible_surface: None,
+                    force_fallback_adapter: true,
+                })
+                .await;
+        }
+
+        let adapter = adapter.ok_or(WgpuContextError::NoAdapterFound)?;
+
+        let info = adapter.get_info();
+        println!("Using Backend: {:?} ({}, headless)", info.backend, info.name);
+
+        let (device, queue) = Self::request_device(&adapter).await?;
+
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+
+        let offscreen_texture = Self::create_offscreen_texture(&device, &config);
+
+        Ok(Self {
+            config,
+            device,
+            is_surface_configured: true,
+            queue,
+            surface: None,
+            offscreen_texture: Some(offscreen_texture)
+        })
+    }
+
+    async fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), WgpuContextError> {
+        // checked up front, a missing bind group otherwise only shows up as a failed pipeline creation
+        let supported = adapter.limits().max_bind_groups;
+        if supported < REQUIRED_BIND_GROUPS {
+            return Err(WgpuContextError::UnsupportedLimit { limit: "max_bind_groups", required: REQUIRED_BIND_GROUPS, supported });
+        }
+
+        adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: wgpu::Features::DEPTH32FLOAT_STENCIL8,
+                    required_limits: wgpu::Limits {
+                        max_bind_groups: REQUIRED_BIND_GROUPS,
+                        ..Default::default()
+                    },
+                    memory_hints: Default::default(),
+                },
+                None,
+            )
+            .await
+            .map_err(WgpuContextError::RequestDeviceError)
+    }
+
+    fn create_offscreen_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen_Texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            view_formats: &[],
         })
     }
 
@@ -83,17 +191,47 @@ impl WgpuContext {
          if width > 0 && height > 0 {
             self.config.width = width;
             self.config.height = height;
-            self.surface.configure(&self.device, &self.config);
+
+            if let Some(surface) = &self.surface {
+                surface.configure(&self.device, &self.config);
+            } else {
+                self.offscreen_texture = Some(Self::create_offscreen_texture(&self.device, &self.config));
+            }
+
             self.is_surface_configured = true;
         }
     }
 
+    /// Gets the texture to render the next frame into.
+    pub fn acquire_frame(&self) -> Result<Frame, wgpu::SurfaceError> {
+        let (texture, surface_texture) = match (&self.surface, &self.offscreen_texture) {
+            (Some(surface), _) => {
+                let surface_texture = surface.get_current_texture()?;
+                (surface_texture.texture.clone(), Some(surface_texture))
+            }
+            (None, Some(offscreen_texture)) => (offscreen_texture.clone(), None),
+            (None, None) => return Err(wgpu::SurfaceError::Lost)
+        };
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Ok(Frame {
+            texture,
+            view,
+            surface_texture
+        })
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
     pub fn get_device(&self) -> &wgpu::Device {
         return &self.device;
     }
 
-    pub fn get_surface(&self) -> &wgpu::Surface<'static> {
-        return &self.surface;
+    pub fn get_surface(&self) -> Option<&wgpu::Surface<'static>> {
+        return self.surface.as_ref();
     }
 
     pub fn get_surface_config(&self) -> &wgpu::SurfaceConfiguration {
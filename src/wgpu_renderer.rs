@@ -1,21 +1,37 @@
-use std::{sync::Arc};
+use std::{path::PathBuf, sync::Arc};
 
 use winit::{window::Window};
 
-use crate::{common::{constants::DEPTH_TEXTURE_STENCIL_FORMAT, create_info::{GameObjectCreateInfo, MeshNodeCreateInfo}, enums::GameState}, egui_renderer::{egui_renderer::EguiRenderer, ui_manager::UiManager, windows::scene_hierarchy::SceneHierarchyWindow}, engine::GameData, input::keyboard::Keyboard, objects::{animated_game_object::AnimatedGameObject, game_object::GameObject}, pipeline_manager::PipelineManager, render_passes::{animation_pass::AnimationPass, emissive_pass::EmissivePass, lighting_pass::LightingPass, outline_pass::OutlinePass, postprocess_pass::PostProcessPass, skybox_pass::SkyboxPass}, texture, uniform::Uniform, uniform_manager::{AnimationUniform, CameraUniform, LightUniform, ModelUniform, UniformManager}, utils::unique_id, vertex::Vertex, wgpu_context::{self, WgpuContext}};
+use crate::{environment_map::EnvironmentMap, frustum_culling::{CullingStats, FrustumCuller}, common::{constants::{DEPTH_TEXTURE_STENCIL_FORMAT, GRAPH_BLOOM_MIPS, GRAPH_DEPTH, GRAPH_EMISSIVE, HDR_TEX_FORMAT, GRAPH_EMISSIVE_MSAA, GRAPH_EXPOSURE, GRAPH_HDR, GRAPH_HDR_MSAA, GRAPH_LDR, GRAPH_OBJECT_ID, GRAPH_OBJECT_ID_DEPTH, GRAPH_PICKING_READBACK, GRAPH_POST_EFFECT_A, GRAPH_POST_EFFECT_B, GRAPH_SHADOW_MAPS, GRAPH_SSAO, GRAPH_SSAO_BLUR, GRAPH_SSAO_DEPTH, GRAPH_SSAO_RAW, GRAPH_SWAPCHAIN, GRAPH_TAA_HISTORY, GRAPH_TAA_RESOLVE}, create_info::{GameObjectCreateInfo, MeshNodeCreateInfo}, enums::{AntiAliasing, GameState, RenderGraphPass}}, common::errors::FrameCaptureError, egui_renderer::{egui_renderer::EguiRenderer, ui_manager::UiManager, windows::scene_hierarchy::SceneHierarchyWindow}, engine::GameData, frame_capture::{self, FrameCapture}, input::keyboard::Keyboard, objects::{animated_game_object::AnimatedGameObject, game_object::GameObject}, pipeline_manager::PipelineManager, render_graph::{RenderGraph, TransientTextureDesc}, render_passes::{animation_pass::AnimationPass, bloom_pass::BloomPass, debug_pass::DebugPass, picking_pass::{PickRequest, PickingPass}, exposure_pass::ExposurePass, post_effect_pass::PostEffectPass, emissive_pass::EmissivePass, fxaa_pass::FxaaPass, lighting_pass::LightingPass, outline_pass::OutlinePass, postprocess_pass::PostProcessPass, shadow_pass::ShadowPass, skybox_pass::SkyboxPass, ssao_pass::SsaoPass, taa_pass::TaaPass}, texture, uniform::Uniform, uniform_manager::{AnimationUniform, CameraUniform, LightUniform, ModelUniform, UniformManager}, utils::unique_id, vertex::Vertex, wgpu_context::{self, WgpuContext}};
 
 pub struct WgpuRenderer {
-    pub egui_renderer: EguiRenderer,
+    /// `None` when rendering headless.
+    pub egui_renderer: Option<EguiRenderer>,
     pub wgpu_context: WgpuContext,
-    depth_texture: texture::Texture,
+    render_graph: RenderGraph,
+    render_scale: f32,
+    anti_aliasing: AntiAliasing,
+    sample_count: u32,
     postprocess_pass: PostProcessPass,
     lighting_pass: LightingPass,
     animation_pass: AnimationPass,
     skybox_pass: SkyboxPass,
+    debug_pass: DebugPass,
+    picking_pass: PickingPass,
     outline_pass: OutlinePass,
     emissive_pass: EmissivePass,
+    shadow_pass: ShadowPass,
+    ssao_pass: SsaoPass,
+    fxaa_pass: FxaaPass,
+    bloom_pass: BloomPass,
+    exposure_pass: ExposurePass,
+    post_effect_pass: PostEffectPass,
+    taa_pass: TaaPass,
+    environment_map: EnvironmentMap,
+    frustum_culler: FrustumCuller,
     uniform_manager: UniformManager,
-    ui_manager: UiManager
+    ui_manager: UiManager,
+    screenshot_path: Option<PathBuf>
 }
 
 impl WgpuRenderer {
@@ -25,127 +41,423 @@ impl WgpuRenderer {
     }
 
     pub fn new(window: &Arc<Window>, context: WgpuContext, game_data: &GameData) -> Self {
-        // init wgpu
-        let config = context.get_surface_config();
-        let device = context.get_device();
-
         // init egui
         let mut egui_renderer = EguiRenderer::new(&context, &window);
         let mut ui_manager = UiManager::new();
         ui_manager.register_textures(&context, &mut egui_renderer.renderer, &game_data.asset_manager);
 
+        Self::create(context, Some(egui_renderer), ui_manager, game_data, AntiAliasing::Msaa)
+    }
+
+    /// Renderer for a context created with `WgpuContext::new_headless`, without the editor ui.
+    pub fn new_headless(context: WgpuContext, game_data: &GameData) -> Self {
+        // TAA depends on previous frames and MSAA resolves differently between adapters, FXAA gives the same image every run
+        Self::create(context, None, UiManager::new(), game_data, AntiAliasing::Fxaa)
+    }
+
+    fn create(context: WgpuContext, egui_renderer: Option<EguiRenderer>, ui_manager: UiManager, game_data: &GameData, anti_aliasing: AntiAliasing) -> Self {
+        // init wgpu
+        let config = context.get_surface_config();
+
         // load uniforms
         let wgpu_uniforms = UniformManager::new(&context, &game_data.scene);
 
-        // load fbos
-        let depth_texture = texture::Texture::create_depth_texture(&device, &config, "depth_texture", DEPTH_TEXTURE_STENCIL_FORMAT);
+        // load environment
+        let environment_map = EnvironmentMap::new(&context, &game_data.asset_manager, game_data.scene.environment.as_deref());
+
+        let sample_count = anti_aliasing.get_sample_count();
 
         // load render groups
-        let lighting_pass = LightingPass::new(&context, &wgpu_uniforms, &game_data.asset_manager);
-        let animation_pass = AnimationPass::new(&context, &wgpu_uniforms, &game_data.asset_manager);
-        let skybox_pass = SkyboxPass::new(&context, &game_data.asset_manager, &wgpu_uniforms);
-        let outline_pass = OutlinePass::new(&context, &wgpu_uniforms);
-        let postprocess_pass = PostProcessPass::new(&context, &config);
-        let emissive_pass = EmissivePass::new(&context, &wgpu_uniforms, &game_data.asset_manager, &postprocess_pass.get_emmisive_texture());
+        let shadow_pass = ShadowPass::new(&context, &wgpu_uniforms, &game_data.asset_manager);
+        let ssao_pass = SsaoPass::new(&context, &wgpu_uniforms, &game_data.asset_manager);
+        let lighting_pass = LightingPass::new(&context, &wgpu_uniforms, &game_data.asset_manager, &shadow_pass.bind_group_layout, &environment_map.bind_group_layout, &ssao_pass.bind_group_layout, sample_count);
+        let animation_pass = AnimationPass::new(&context, &wgpu_uniforms, &game_data.asset_manager, sample_count);
+        let skybox_pass = SkyboxPass::new(&context, &wgpu_uniforms, &environment_map, sample_count);
+        let debug_pass = DebugPass::new(&context, &wgpu_uniforms, sample_count);
+        let picking_pass = PickingPass::new(&context, &wgpu_uniforms);
+        let outline_pass = OutlinePass::new(&context, &wgpu_uniforms, sample_count);
+        let exposure_pass = ExposurePass::new(&context, &wgpu_uniforms);
+        let post_effect_pass = PostEffectPass::new(&context, &wgpu_uniforms);
+        let postprocess_pass = PostProcessPass::new(&context, &config, &wgpu_uniforms, &exposure_pass.bind_group_layout);
+        let emissive_pass = EmissivePass::new(&context, &wgpu_uniforms, sample_count);
+        let bloom_pass = BloomPass::new(&context, &wgpu_uniforms);
+        let fxaa_pass = FxaaPass::new(&context);
+        let taa_pass = TaaPass::new(&context, &wgpu_uniforms);
+
+        // load fbos
+        let render_graph = Self::build_render_graph(&context, sample_count);
 
         return Self {
             wgpu_context: context,
-            depth_texture,
+            render_graph,
+            render_scale: 1.0,
+            anti_aliasing,
+            sample_count,
             egui_renderer,
             lighting_pass,
             postprocess_pass,
             animation_pass,
             skybox_pass,
+            debug_pass,
+            picking_pass,
             outline_pass,
             emissive_pass,
+            shadow_pass,
+            ssao_pass,
+            fxaa_pass,
+            bloom_pass,
+            exposure_pass,
+            post_effect_pass,
+            taa_pass,
+            environment_map,
+            frustum_culler: FrustumCuller::new(),
             uniform_manager: wgpu_uniforms,
-            ui_manager
+            ui_manager,
+            screenshot_path: None
         };
     }
 
+    /// Declares every frame pass with the resources it reads and writes, the graph works out the order.
+    /// The multisampled targets are internal to the forward and outline passes and resolved into HDR and EMISSIVE,
+    /// so they aren't declared.
+    fn build_render_graph(ctx: &WgpuContext, sample_count: u32) -> RenderGraph {
+        let mut graph = RenderGraph::new(&ctx.device, (ctx.config.width, ctx.config.height));
+
+        graph.import(GRAPH_SWAPCHAIN);
+        graph.import(GRAPH_SHADOW_MAPS);
+        graph.import(GRAPH_EXPOSURE);
+        graph.import(GRAPH_PICKING_READBACK);
+        Self::register_forward_targets(ctx, &mut graph, sample_count);
+        PostProcessPass::register_textures(ctx, &mut graph);
+        EmissivePass::register_textures(ctx, &mut graph);
+        SsaoPass::register_textures(ctx, &mut graph);
+        FxaaPass::register_textures(ctx, &mut graph);
+        BloomPass::register_textures(ctx, &mut graph);
+        PostEffectPass::register_textures(ctx, &mut graph);
+        TaaPass::register_textures(ctx, &mut graph);
+        PickingPass::register_textures(ctx, &mut graph);
+
+        graph.add_pass(RenderGraphPass::Shadows, &[], &[GRAPH_SHADOW_MAPS]);
+        graph.add_pass(RenderGraphPass::Ssao, &[], &[GRAPH_SSAO_DEPTH, GRAPH_SSAO_RAW, GRAPH_SSAO_BLUR, GRAPH_SSAO]);
+        graph.add_pass(RenderGraphPass::Forward, &[GRAPH_SHADOW_MAPS, GRAPH_SSAO], &[GRAPH_HDR, GRAPH_EMISSIVE, GRAPH_DEPTH]);
+        graph.add_pass(RenderGraphPass::Outline, &[GRAPH_DEPTH], &[GRAPH_HDR]);
+        graph.add_pass(RenderGraphPass::Taa, &[GRAPH_DEPTH, GRAPH_TAA_HISTORY], &[GRAPH_TAA_RESOLVE, GRAPH_HDR, GRAPH_TAA_HISTORY]);
+        graph.add_pass(RenderGraphPass::Bloom, &[GRAPH_HDR, GRAPH_EMISSIVE], &GRAPH_BLOOM_MIPS);
+        graph.add_pass(RenderGraphPass::Exposure, &[GRAPH_HDR], &[GRAPH_EXPOSURE]);
+        graph.add_pass(RenderGraphPass::PostProcess, &[GRAPH_HDR, GRAPH_BLOOM_MIPS[0], GRAPH_EXPOSURE], &[GRAPH_POST_EFFECT_A, GRAPH_LDR, GRAPH_SWAPCHAIN]);
+        graph.add_pass(RenderGraphPass::PostEffects, &[GRAPH_POST_EFFECT_A], &[GRAPH_POST_EFFECT_A, GRAPH_POST_EFFECT_B, GRAPH_LDR, GRAPH_SWAPCHAIN]);
+        graph.add_pass(RenderGraphPass::Fxaa, &[GRAPH_LDR], &[GRAPH_SWAPCHAIN]);
+        graph.add_pass(RenderGraphPass::Ui, &[], &[GRAPH_SWAPCHAIN]);
+        graph.add_pass(RenderGraphPass::Picking, &[], &[GRAPH_OBJECT_ID, GRAPH_OBJECT_ID_DEPTH, GRAPH_PICKING_READBACK]);
+
+        if let Err(e) = graph.compile() {
+          println!("WgpuRenderer::build_render_graph() error: {:?}", e);
+        }
+
+        graph
+    }
+
+    fn register_forward_targets(ctx: &WgpuContext, graph: &mut RenderGraph, sample_count: u32) {
+        graph.add_texture(&ctx.device, GRAPH_DEPTH, TransientTextureDesc::render_target(DEPTH_TEXTURE_STENCIL_FORMAT).with_sample_count(sample_count));
+
+        if sample_count > 1 {
+          graph.add_texture(&ctx.device, GRAPH_HDR_MSAA, TransientTextureDesc::render_target(HDR_TEX_FORMAT).with_sample_count(sample_count));
+          graph.add_texture(&ctx.device, GRAPH_EMISSIVE_MSAA, TransientTextureDesc::render_target(HDR_TEX_FORMAT).with_sample_count(sample_count));
+        } else {
+          graph.remove_texture(GRAPH_HDR_MSAA);
+          graph.remove_texture(GRAPH_EMISSIVE_MSAA);
+        }
+    }
+
+    /// Rebuilds the forward targets and pipelines when the MSAA sample count changed.
+    fn apply_anti_aliasing(&mut self) {
+        let sample_count = self.anti_aliasing.get_sample_count();
+
+        if sample_count != self.sample_count {
+          self.sample_count = sample_count;
+
+          Self::register_forward_targets(&self.wgpu_context, &mut self.render_graph, sample_count);
+          self.lighting_pass.set_sample_count(&self.wgpu_context, &self.uniform_manager, sample_count);
+          self.animation_pass.set_sample_count(&self.wgpu_context, &self.uniform_manager, sample_count);
+          self.emissive_pass.set_sample_count(&self.wgpu_context, &self.uniform_manager, sample_count);
+          self.skybox_pass.set_sample_count(&self.wgpu_context, &self.uniform_manager, sample_count);
+          self.debug_pass.set_sample_count(&self.wgpu_context, &self.uniform_manager, sample_count);
+          self.outline_pass.set_sample_count(&self.wgpu_context, &self.uniform_manager, sample_count);
+        }
+
+        if self.anti_aliasing != AntiAliasing::Taa {
+          self.taa_pass.invalidate_history();
+        }
+    }
+
+    /// Attachment for a forward target, rendered multisampled and resolved when MSAA is on.
+    fn get_color_target<'a>(graph: &'a RenderGraph, sample_count: u32, name: &str, msaa_name: &str) -> (&'a wgpu::TextureView, Option<&'a wgpu::TextureView>) {
+        if sample_count > 1 {
+          (graph.get_view(msaa_name), Some(graph.get_view(name)))
+        } else {
+          (graph.get_view(name), None)
+        }
+    }
+
     pub fn render(&mut self, window: &Window, game_data: &mut GameData) -> Result<(), wgpu::SurfaceError> {
+        window.request_redraw();
+
+        let capture = self.render_frame(Some(window), game_data, self.screenshot_path.is_some())?;
+
+        // the request stays pending until a frame was actually rendered
+        if let Some(capture) = capture {
+          let path = self.screenshot_path.take().unwrap_or_default();
+
+          match capture.and_then(|capture| capture.read(&self.wgpu_context)).and_then(|image| frame_capture::save_png(&image, &path)) {
+            Ok(_) => println!("Saved screenshot to {}", path.display()),
+            Err(e) => println!("WgpuRenderer::render() error: failed to save screenshot!! {:?}", e)
+          }
+        }
+
+        if let Some(pick) = self.picking_pass.read(&self.wgpu_context) {
+          self.ui_manager.scene_hierarchy_window.select_picked(game_data, pick);
+        }
+
+        Ok(())
+    }
+
+    /// Renders a single frame into the offscreen texture of a headless context and reads it back.
+    pub fn render_headless(&mut self, game_data: &mut GameData) -> Result<image::RgbaImage, FrameCaptureError> {
+        match self.render_frame(None, game_data, true).map_err(FrameCaptureError::Surface)? {
+          Some(capture) => capture?.read(&self.wgpu_context),
+          None => Err(FrameCaptureError::Surface(wgpu::SurfaceError::Lost))
+        }
+    }
+
+    /// Drops the renderer and editor state tied to the previous level, call after the scene was replaced.
+    pub fn reset_level_state(&mut self, game_data: &GameData) {
+        self.uniform_manager.submit_model_uniforms(&self.wgpu_context, &game_data.scene);
+
+        if self.environment_map.name != game_data.scene.environment {
+          self.environment_map.load(&self.wgpu_context, &game_data.asset_manager, game_data.scene.environment.as_deref());
+        }
+
+        self.taa_pass.invalidate_history();
+        self.exposure_pass.invalidate();
+        self.ui_manager.reset_level_state();
+    }
+
+    /// Selects the object under the cursor once the next frame is rendered.
+    pub fn request_pick(&mut self, request: PickRequest) {
+        self.picking_pass.request(request);
+    }
+
+    /// Saves the next rendered frame as a PNG.
+    pub fn request_screenshot(&mut self, path: PathBuf) {
+        self.screenshot_path = Some(path);
+    }
+
+    fn render_frame(&mut self, window: Option<&Window>, game_data: &mut GameData, capture: bool) -> Result<Option<Result<FrameCapture, FrameCaptureError>>, wgpu::SurfaceError> {
+        // applied before recording so every pass of the frame sees the same targets
+        if self.render_scale != self.render_graph.get_render_scale() {
+          self.render_graph.set_render_scale(&self.wgpu_context.device, self.render_scale);
+        }
+
+        self.apply_anti_aliasing();
+
+        let jitter = match self.anti_aliasing {
+          AntiAliasing::Taa => self.taa_pass.next_jitter(self.render_graph.get_render_size()),
+          _ => [0.0, 0.0]
+        };
+
         // submit uniforms
-        self.uniform_manager.submit_animation_uniforms(&self.wgpu_context, &mut game_data.asset_manager, game_data.delta_time);
+        self.uniform_manager.submit_animation_uniforms(&self.wgpu_context, &mut game_data.asset_manager, &mut game_data.scene.animated_game_objects, game_data.delta_time);
         self.uniform_manager.submit_model_uniforms(&self.wgpu_context, &game_data.scene);
-        self.uniform_manager.submit_camera_uniforms(&self.wgpu_context, &game_data.active_camera());
-        self.uniform_manager.submit_light_uniforms(&self.wgpu_context, &game_data.scene);
-        
-        window.request_redraw();
+        self.uniform_manager.submit_camera_uniforms(&self.wgpu_context, &game_data.active_camera(), jitter);
+
+        if self.anti_aliasing == AntiAliasing::Taa {
+          let reset = self.taa_pass.needs_reset(&self.render_graph);
+          self.uniform_manager.submit_taa_uniforms(&self.wgpu_context, &game_data.active_camera(), jitter, self.taa_pass.feedback, reset);
+        }
+
+        self.uniform_manager.submit_light_uniforms(&self.wgpu_context, &game_data.scene, &game_data.active_camera());
+        self.uniform_manager.submit_ssao_uniforms(&self.wgpu_context, &game_data.active_camera(), &self.ssao_pass.settings);
+        self.uniform_manager.submit_bloom_uniforms(&self.wgpu_context, &self.bloom_pass.settings);
+
+        // toggling auto exposure on snaps to the scene instead of fading in from a stale value
+        let reset_exposure = if game_data.scene.post_process.auto_exposure {
+          self.exposure_pass.take_reset()
+        } else {
+          self.exposure_pass.invalidate();
+          false
+        };
+        let time = self.post_effect_pass.advance_time(game_data.delta_time.as_secs_f32());
+        self.uniform_manager.submit_post_effect_uniforms(&self.wgpu_context, &game_data.scene.post_process.effects, time);
+        self.uniform_manager.submit_post_process_uniforms(&self.wgpu_context, &game_data.scene.post_process, game_data.delta_time.as_secs_f32(), reset_exposure);
+
+        // the level can switch environments at runtime
+        if self.environment_map.name != game_data.scene.environment {
+          self.environment_map.load(&self.wgpu_context, &game_data.asset_manager, game_data.scene.environment.as_deref());
+          self.exposure_pass.invalidate();
+        }
+
+        // cull
+        self.frustum_culler.update(&game_data.active_camera(), &game_data.scene, &game_data.asset_manager);
+        self.lighting_pass.prepare(&self.wgpu_context, &game_data.asset_manager, &game_data.scene.game_objects, &self.frustum_culler, game_data.active_camera().position);
+        self.debug_pass.prepare(&self.wgpu_context, &game_data.debug_draw);
 
         let device = self.wgpu_context.get_device();
-        let surface = self.wgpu_context.get_surface();
-        let queue = self.wgpu_context.get_queue();
 
         if !self.wgpu_context.is_surface_configured() {
-            return Ok(());
+            return Ok(None);
         }
 
-        let swapchain_fbo = surface.get_current_texture()?;
-        let swapchain_view = swapchain_fbo.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let frame = self.wgpu_context.acquire_frame()?;
 
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
 
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("First_Pass"),
-            color_attachments: &[
-              Some(wgpu::RenderPassColorAttachment {
-                view: &self.postprocess_pass.get_view(),
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                    store: wgpu::StoreOp::Store,
-                },
-             }),
-              Some(wgpu::RenderPassColorAttachment {
-                view: &self.postprocess_pass.get_emissive_view(),
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                    store: wgpu::StoreOp::Store,
-                },
-             })
-            ],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-            view: &self.depth_texture.view,
-            depth_ops: Some(wgpu::Operations {
-              load: wgpu::LoadOp::Clear(1.0),
-              store: wgpu::StoreOp::Store,
-            }),
-            stencil_ops: Some(wgpu::Operations {
-                load: wgpu::LoadOp::Clear(0),
+        for pass in self.render_graph.get_execution_order() {
+          self.execute_pass(pass, &mut encoder, &frame.view, window, game_data);
+        }
+
+       let capture = capture.then(|| FrameCapture::record(&self.wgpu_context.device, &mut encoder, &frame.texture));
+
+       self.wgpu_context.queue.submit(std::iter::once(encoder.finish()));
+       frame.present();
+
+       Ok(capture)
+    }
+
+    fn execute_pass(&mut self, pass: RenderGraphPass, encoder: &mut wgpu::CommandEncoder, swapchain_view: &wgpu::TextureView, window: Option<&Window>, game_data: &mut GameData) {
+        match pass {
+          RenderGraphPass::Shadows => {
+            self.shadow_pass.render(&self.wgpu_context, encoder, &self.uniform_manager, &game_data.asset_manager, &game_data.scene.game_objects, &game_data.scene.animated_game_objects);
+          }
+          RenderGraphPass::Ssao => {
+            self.ssao_pass.render(&self.wgpu_context, encoder, &self.render_graph, &self.uniform_manager, &game_data.asset_manager, &self.lighting_pass);
+          }
+          RenderGraphPass::Forward => {
+            let ssao_bind_group = self.ssao_pass.get_bind_group(&self.wgpu_context, &self.render_graph);
+            let (hdr_view, hdr_resolve) = Self::get_color_target(&self.render_graph, self.sample_count, GRAPH_HDR, GRAPH_HDR_MSAA);
+            let (emissive_view, emissive_resolve) = Self::get_color_target(&self.render_graph, self.sample_count, GRAPH_EMISSIVE, GRAPH_EMISSIVE_MSAA);
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+              label: Some("First_Pass"),
+              color_attachments: &[
+                Some(wgpu::RenderPassColorAttachment {
+                  view: hdr_view,
+                  resolve_target: hdr_resolve,
+                  ops: wgpu::Operations {
+                      load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                      store: wgpu::StoreOp::Store,
+                  },
+               }),
+                Some(wgpu::RenderPassColorAttachment {
+                  view: emissive_view,
+                  resolve_target: emissive_resolve,
+                  ops: wgpu::Operations {
+                      load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                      store: wgpu::StoreOp::Store,
+                  },
+               })
+              ],
+              depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+              view: self.render_graph.get_view(GRAPH_DEPTH),
+              depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(1.0),
                 store: wgpu::StoreOp::Store,
-            }),
-            }),
-            occlusion_query_set: None,
-            timestamp_writes: None,
-        });
+              }),
+              stencil_ops: Some(wgpu::Operations {
+                  load: wgpu::LoadOp::Clear(0),
+                  store: wgpu::StoreOp::Store,
+              }),
+              }),
+              occlusion_query_set: None,
+              timestamp_writes: None,
+            });
 
-       self.lighting_pass.render(&mut render_pass, &self.uniform_manager, &game_data.asset_manager, &game_data.scene.game_objects);
-       self.animation_pass.render(&mut render_pass, &self.uniform_manager, &game_data.asset_manager, &game_data.scene.animated_game_objects);
-       self.emissive_pass.render(&mut render_pass, &game_data, &self.uniform_manager, self.postprocess_pass.get_view(), self.postprocess_pass.get_emissive_view(), &self.depth_texture.view);
+            self.lighting_pass.render(&mut render_pass, &self.uniform_manager, &game_data.asset_manager, &self.shadow_pass.bind_group, &self.environment_map.bind_group, ssao_bind_group);
+            self.animation_pass.render(&mut render_pass, &self.uniform_manager, &game_data.asset_manager, &game_data.scene.animated_game_objects, &self.frustum_culler);
+            self.emissive_pass.render(&mut render_pass, &game_data, &self.uniform_manager, self.render_graph.get_view(GRAPH_HDR), self.render_graph.get_view(GRAPH_EMISSIVE), self.render_graph.get_view(GRAPH_DEPTH), &self.frustum_culler);
 
-       // skybox
-       self.skybox_pass.render(&mut render_pass, &self.uniform_manager);
+            // skybox
+            self.skybox_pass.render(&mut render_pass, &self.uniform_manager, &self.environment_map);
 
-       drop(render_pass);
+            // transparency
+            self.lighting_pass.render_transparent(&mut render_pass, &self.uniform_manager, &game_data.asset_manager, &self.shadow_pass.bind_group, &self.environment_map.bind_group, ssao_bind_group);
 
-       // post process
-       self.outline_pass.render(&mut encoder, &self.postprocess_pass.get_view(), &self.depth_texture.view, &self.uniform_manager, &game_data.scene.game_objects, &game_data.asset_manager);
-       self.emissive_pass.render_blur(&mut encoder, &self.wgpu_context, &mut self.uniform_manager, self.postprocess_pass.get_emmisive_texture());
-       self.postprocess_pass.render(&mut encoder, &swapchain_view, &self.wgpu_context, &self.emissive_pass.get_final_texture());
+            // debug lines
+            self.debug_pass.render(&mut render_pass, &self.uniform_manager);
+          }
+          RenderGraphPass::Outline => {
+            let (hdr_view, hdr_resolve) = Self::get_color_target(&self.render_graph, self.sample_count, GRAPH_HDR, GRAPH_HDR_MSAA);
+            self.outline_pass.render(encoder, hdr_view, hdr_resolve, self.render_graph.get_view(GRAPH_DEPTH), &self.uniform_manager, &game_data.scene.game_objects, &game_data.asset_manager, &self.frustum_culler);
+          }
+          RenderGraphPass::Taa => {
+            if self.anti_aliasing == AntiAliasing::Taa {
+              self.taa_pass.render(&self.wgpu_context, encoder, &self.render_graph, &self.uniform_manager);
+            }
+          }
+          RenderGraphPass::Bloom => {
+            self.bloom_pass.render(&self.wgpu_context, encoder, &self.render_graph, &self.uniform_manager);
+          }
+          RenderGraphPass::Exposure => {
+            if game_data.scene.post_process.auto_exposure {
+              self.exposure_pass.render(&self.wgpu_context, encoder, &self.render_graph, &self.uniform_manager);
+            }
+          }
+          RenderGraphPass::PostProcess => {
+            let target_view = if PostEffectPass::is_active(&game_data.scene.post_process.effects) {
+              self.render_graph.get_view(GRAPH_POST_EFFECT_A)
+            } else {
+              Self::get_output_view(&self.render_graph, self.anti_aliasing, swapchain_view)
+            };
 
-       if game_data.game_state == GameState::Editor {
-        self.egui_renderer.draw(&self.wgpu_context, &mut encoder, &window, swapchain_view, |ui| {
-          self.ui_manager.scene_hierarchy_window.draw(ui, &self.ui_manager.materials, game_data, (window.inner_size().width, window.inner_size().height));
-        });
-       }
+            self.postprocess_pass.render(encoder, target_view, &self.wgpu_context, &self.render_graph, &self.uniform_manager, self.bloom_pass.get_final_texture(), &self.exposure_pass.bind_group);
+          }
+          RenderGraphPass::PostEffects => {
+            if PostEffectPass::is_active(&game_data.scene.post_process.effects) {
+              let target_view = Self::get_output_view(&self.render_graph, self.anti_aliasing, swapchain_view);
+              self.post_effect_pass.render(&self.wgpu_context, encoder, &self.render_graph, &self.uniform_manager, &game_data.scene.post_process.effects, target_view);
+            }
+          }
+          RenderGraphPass::Fxaa => {
+            if self.anti_aliasing == AntiAliasing::Fxaa {
+              self.fxaa_pass.render(&self.wgpu_context, encoder, swapchain_view, &self.render_graph);
+            }
+          }
+          RenderGraphPass::Ui => {
+            let (Some(egui_renderer), Some(window)) = (&mut self.egui_renderer, window) else {
+              return;
+            };
+
+            if game_data.game_state == GameState::Editor {
+              egui_renderer.draw(&self.wgpu_context, encoder, &window, swapchain_view.clone(), |ui| {
+                let command_history = &mut self.ui_manager.command_history;
+                command_history.handle_shortcuts(ui, game_data);
+                let scene_hierarchy_window = &self.ui_manager.scene_hierarchy_window;
+                command_history.begin_edit(game_data, scene_hierarchy_window.get_selected_game_object_ids(), scene_hierarchy_window.get_selected_animated_object_ids(), scene_hierarchy_window.get_selected_light_ids());
 
-       queue.submit(std::iter::once(encoder.finish()));
-       swapchain_fbo.present();
+                self.ui_manager.scene_hierarchy_window.draw(ui, &self.ui_manager.materials, game_data, (window.inner_size().width, window.inner_size().height), command_history);
+                self.ui_manager.render_settings_window.draw(ui, &mut self.render_scale, &mut self.anti_aliasing, &mut self.taa_pass.feedback, &mut self.bloom_pass.settings, &mut game_data.scene.post_process, &mut self.ssao_pass.settings, &mut game_data.debug_draw.settings);
+                self.ui_manager.transform_gizmo.draw(ui, game_data, self.ui_manager.scene_hierarchy_window.get_selected_game_object_ids(), self.ui_manager.scene_hierarchy_window.get_selected_light_ids());
 
-       Ok(())
+                // drags are recorded once released
+                command_history.end_edit(game_data, ui.input(|input| input.pointer.any_down()));
+              });
+            }
+          }
+          RenderGraphPass::Picking => {
+            if self.picking_pass.is_pending() {
+              self.picking_pass.render(&self.wgpu_context, encoder, &self.render_graph, &self.uniform_manager, &game_data.asset_manager, &game_data.scene);
+            }
+          }
+        }
+    }
+
+    /// Where the last LDR pass before FXAA writes.
+    fn get_output_view<'a>(graph: &'a RenderGraph, anti_aliasing: AntiAliasing, swapchain_view: &'a wgpu::TextureView) -> &'a wgpu::TextureView {
+        match anti_aliasing {
+          AntiAliasing::Fxaa => graph.get_view(GRAPH_LDR),
+          _ => swapchain_view
+        }
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
@@ -154,20 +466,27 @@ impl WgpuRenderer {
         }
 
         self.wgpu_context.resize(width, height);
-       // self.depth_texture = texture::Texture::create_depth_texture(&self.wgpu_context.get_device(), &self.wgpu_context.get_surface_config(), "depth_texture", DEPTH_TEXTURE_STENCIL_FORMAT);
-        self.emissive_pass.resize(&self.wgpu_context.device, width, height, &self.postprocess_pass.get_emmisive_texture());
-        self.postprocess_pass.resize(&self.wgpu_context.get_device(), width, height, &self.emissive_pass.get_final_texture());
+        self.render_graph.resize(&self.wgpu_context.device, width, height);
     }
 
-    pub fn resize_ctx(&mut self, width: u32, height: u32) {
-        self.wgpu_context.resize(width, height);
+    pub fn get_culling_stats(&self) -> &CullingStats {
+        self.frustum_culler.get_stats()
     }
 
     pub fn hot_load_shaders(&mut self) {
          self.outline_pass.hotload_shader(&self.wgpu_context, &self.uniform_manager);
-         self.postprocess_pass.hotload_shader(&self.wgpu_context);
+         self.postprocess_pass.hotload_shader(&self.wgpu_context, &self.uniform_manager, &self.exposure_pass.bind_group_layout);
+         self.exposure_pass.hotload_shader(&self.wgpu_context);
+         self.post_effect_pass.hotload_shader(&self.wgpu_context, &self.uniform_manager);
          self.lighting_pass.hotload_shader(&self.wgpu_context, &self.uniform_manager);
-         self.emissive_pass.hotload_shader(&self.wgpu_context.device, &self.uniform_manager);
+         self.debug_pass.hotload_shader(&self.wgpu_context, &self.uniform_manager);
+         self.picking_pass.hotload_shader(&self.wgpu_context, &self.uniform_manager);
+         self.shadow_pass.hotload_shader(&self.wgpu_context, &self.uniform_manager);
+         self.ssao_pass.hotload_shader(&self.wgpu_context, &self.uniform_manager);
+         self.emissive_pass.hotload_shader(&self.wgpu_context, &self.uniform_manager);
+         self.bloom_pass.hotload_shader(&self.wgpu_context, &self.uniform_manager);
+         self.fxaa_pass.hotload_shader(&self.wgpu_context);
+         self.taa_pass.hotload_shader(&self.wgpu_context, &self.uniform_manager);
          println!("Hot-Loaded shaders!");
     }
 }
\ No newline at end of file